@@ -37,6 +37,8 @@ leaflet_geometryutil_js = read_file("./js/leaflet_07/leaflet.geometryutil.js")
 select_map_js = read_file("./js/coordinate-conversion.js")
 nutzungsarten_json = read_file("./nutzung.json");
 nutzungsartenkatalog_js = "        window.nutzungsarten = " + "      ".join(nutzungsarten_json.splitlines(True)) + ";"
+nutzungsarten_ranking_json = read_file("./nutzungsarten_ranking.json");
+nutzungsarten_ranking_js = "        window.nutzungsarten_ranking = " + "        ".join(nutzungsarten_ranking_json.splitlines(True)) + ";"
 
 # leaflet_js = read_file("./js/leaflet/leaflet.js")
 # leaflet_css = read_file("./js/leaflet/leaflet.css")
@@ -108,6 +110,8 @@ for line in index_html.splitlines():
         out_file.append(select_map_js)
     elif "// INJECT_NUTZUNGSARTEN_KATALOG" in line:
         out_file.append(nutzungsartenkatalog_js);
+    elif "// INJECT_NUTZUNGSARTEN_RANKING" in line:
+        out_file.append(nutzungsarten_ranking_js);
     else:
         out_file.append(line)
 
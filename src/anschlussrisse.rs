@@ -0,0 +1,321 @@
+//! Automatic Anschlussriss-Verweise between neighbouring Risse
+//!
+//! The outer ring of every Riss-Gebiet (the `rissgebiet` or, if none is
+//! drawn, the map area of the sheet, see [RissExtentReprojected::get_poly])
+//! is sampled in 1 mm steps. Samples that lie inside or on the border of a
+//! neighbouring Riss-Gebiet form the shared edge, the longest piece per
+//! neighbour gets a box "s. Anschlussriss (X / Y)" placed on the inner side
+//! of the edge and moved into the map area of the sheet.
+
+use crate::{
+    nas::{
+        point_is_in_polygon,
+        SvgLine,
+        SvgPoint,
+        SvgPolygonInner,
+    },
+    pdf::{
+        RissExtentReprojected,
+        Risse,
+    },
+    rissplanung::{
+        BLATTKOPF_BREITE_MM,
+        BLATTKOPF_HOEHE_MM,
+    },
+};
+
+/// Text height of the Verweis in mm
+pub const ANSCHLUSSRISS_TEXT_HOEHE_MM: f32 = 3.0;
+/// Padding between box and text in mm
+pub const ANSCHLUSSRISS_PADDING_MM: f32 = 1.5;
+/// Distance between the box and the shared edge in mm
+const ABSTAND_KANTE_MM: f64 = 2.0;
+/// Tolerance for "on the border of the neighbour" in mm
+const TOLERANZ_MM: f64 = 1.0;
+/// Shared edges shorter than this (in mm) only touch at a corner
+const MIN_KANTE_MM: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnschlussrissMarker {
+    /// Number of the neighbouring Riss (1-based)
+    pub nachbar: usize,
+    pub total: usize,
+    /// Center of the box, in the CRS of the Riss
+    pub pos: SvgPoint,
+    /// Shared edge runs vertically, text is rotated by 90 degrees
+    pub vert: bool,
+    /// Box size along / across the text
+    pub breite_mm: f32,
+    pub hoehe_mm: f32,
+    pub scale: f64,
+}
+
+impl AnschlussrissMarker {
+    pub fn get_text(&self) -> String {
+        format!("s. Anschlussriss ({} / {})", self.nachbar, self.total)
+    }
+
+    fn mm_to_m(&self, mm: f32) -> f64 {
+        mm as f64 * self.scale / 1000.0
+    }
+
+    /// Box width / height in map coordinates
+    fn get_size_m(&self) -> (f64, f64) {
+        let (b, h) = (self.mm_to_m(self.breite_mm), self.mm_to_m(self.hoehe_mm));
+        if self.vert {
+            (h, b)
+        } else {
+            (b, h)
+        }
+    }
+
+    /// Start of the baseline of the text, in the CRS of the Riss
+    pub fn get_text_pos(&self) -> SvgPoint {
+        let (w, h) = self.get_size_m();
+        let p = self.mm_to_m(ANSCHLUSSRISS_PADDING_MM);
+        if self.vert {
+            SvgPoint {
+                x: self.pos.x + w / 2.0 - p,
+                y: self.pos.y - h / 2.0 + p,
+            }
+        } else {
+            SvgPoint {
+                x: self.pos.x - w / 2.0 + p,
+                y: self.pos.y - h / 2.0 + p,
+            }
+        }
+    }
+
+    pub fn get_box(&self) -> SvgLine {
+        let (w, h) = self.get_size_m();
+        let (min_x, max_x) = (self.pos.x - w / 2.0, self.pos.x + w / 2.0);
+        let (min_y, max_y) = (self.pos.y - h / 2.0, self.pos.y + h / 2.0);
+        SvgLine {
+            points: vec![
+                SvgPoint { x: min_x, y: min_y },
+                SvgPoint { x: max_x, y: min_y },
+                SvgPoint { x: max_x, y: max_y },
+                SvgPoint { x: min_x, y: max_y },
+                SvgPoint { x: min_x, y: min_y },
+            ],
+        }
+    }
+}
+
+/// Box size (along, across the text) in mm, measured in Helvetica-Bold like
+/// the Verweis is written into the Riss PDF
+pub fn get_box_size_mm(text: &str) -> (f32, f32) {
+    use printpdf::Mm;
+
+    let text_w: Mm =
+        crate::geograf::calc_text_width_helvetica_bold_pt(text, Mm(ANSCHLUSSRISS_TEXT_HOEHE_MM).into_pt().0).into();
+
+    (
+        text_w.0 + 2.0 * ANSCHLUSSRISS_PADDING_MM,
+        ANSCHLUSSRISS_TEXT_HOEHE_MM + 2.0 * ANSCHLUSSRISS_PADDING_MM,
+    )
+}
+
+/// Markers for every Riss (index = Riss number - 1, in the order of `risse`)
+pub fn get_anschlussrisse(risse: &Risse, crs: &str) -> Vec<Vec<AnschlussrissMarker>> {
+    let extents = risse
+        .values()
+        .map(|rc| rc.get_extent_special(crs).and_then(|s| s.reproject(crs)))
+        .collect::<Vec<_>>();
+
+    let total = extents.len();
+    extents
+        .iter()
+        .enumerate()
+        .map(|(i, ex)| {
+            let ex = match ex {
+                Some(s) => s,
+                None => return Vec::new(),
+            };
+            let gebiet = get_rissgebiet(ex);
+            extents
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .filter_map(|(j, nachbar)| {
+                    let nachbar = get_rissgebiet(nachbar.as_ref()?);
+                    let (start, end) = get_gemeinsame_kante(&gebiet, &nachbar, ex.scale)?;
+                    Some(platziere_marker(ex, &gebiet, &start, &end, j + 1, total))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn get_rissgebiet(ex: &RissExtentReprojected) -> SvgPolygonInner {
+    ex.rissgebiet.clone().unwrap_or_else(|| ex.get_poly())
+}
+
+fn dist_to_segment(p: &SvgPoint, a: &SvgPoint, b: &SvgPoint) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    if len2 == 0.0 {
+        return p.dist(a);
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0);
+    p.dist(&SvgPoint {
+        x: a.x + t * dx,
+        y: a.y + t * dy,
+    })
+}
+
+fn beruehrt(p: &SvgPoint, poly: &SvgPolygonInner, toleranz: f64) -> bool {
+    point_is_in_polygon(p, poly)
+        || poly
+            .outer_ring
+            .points
+            .windows(2)
+            .any(|w| dist_to_segment(p, &w[0], &w[1]) <= toleranz)
+}
+
+/// Longest piece of the outer ring of `gebiet` that lies inside or on the
+/// border of `nachbar`
+fn get_gemeinsame_kante(
+    gebiet: &SvgPolygonInner,
+    nachbar: &SvgPolygonInner,
+    scale: f64,
+) -> Option<(SvgPoint, SvgPoint)> {
+    let mm = scale / 1000.0;
+    let mut beste: Option<(f64, SvgPoint, SvgPoint)> = None;
+
+    for w in gebiet.outer_ring.points.windows(2) {
+        let (a, b) = (&w[0], &w[1]);
+        let len = a.dist(b);
+        if len < MIN_KANTE_MM * mm {
+            continue;
+        }
+        let n = ((len / mm).ceil() as usize).min(10_000);
+        let punkte = (0..=n)
+            .map(|k| {
+                let t = k as f64 / n as f64;
+                SvgPoint {
+                    x: a.x + t * (b.x - a.x),
+                    y: a.y + t * (b.y - a.y),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut start = None;
+        for (k, p) in punkte.iter().enumerate() {
+            let drin = beruehrt(p, nachbar, TOLERANZ_MM * mm);
+            if drin && start.is_none() {
+                start = Some(k);
+            }
+            let ende = if !drin {
+                start.take().map(|s| (s, k - 1))
+            } else if k == n {
+                start.take().map(|s| (s, k))
+            } else {
+                None
+            };
+            if let Some((s, e)) = ende {
+                let l = punkte[s].dist(&punkte[e]);
+                if l >= MIN_KANTE_MM * mm && beste.as_ref().map(|b| l > b.0).unwrap_or(true) {
+                    beste = Some((l, punkte[s], punkte[e]));
+                }
+            }
+        }
+    }
+
+    beste.map(|(_, s, e)| (s, e))
+}
+
+fn platziere_marker(
+    ex: &RissExtentReprojected,
+    gebiet: &SvgPolygonInner,
+    start: &SvgPoint,
+    end: &SvgPoint,
+    nachbar: usize,
+    total: usize,
+) -> AnschlussrissMarker {
+    let mm = ex.scale / 1000.0;
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let len = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+    let vert = dy.abs() > dx.abs();
+
+    let mut marker = AnschlussrissMarker {
+        nachbar,
+        total,
+        pos: SvgPoint {
+            x: (start.x + end.x) / 2.0,
+            y: (start.y + end.y) / 2.0,
+        },
+        vert,
+        breite_mm: 0.0,
+        hoehe_mm: 0.0,
+        scale: ex.scale,
+    };
+    let (breite_mm, hoehe_mm) = get_box_size_mm(&marker.get_text());
+    marker.breite_mm = breite_mm;
+    marker.hoehe_mm = hoehe_mm;
+
+    // normal pointing into the Riss-Gebiet
+    let (mut nx, mut ny) = (-dy / len, dx / len);
+    if !point_is_in_polygon(&marker.pos.translate(nx * mm, ny * mm), gebiet) {
+        nx = -nx;
+        ny = -ny;
+    }
+    let (w, h) = marker.get_size_m();
+    let abstand = (w / 2.0) * nx.abs() + (h / 2.0) * ny.abs() + ABSTAND_KANTE_MM * mm;
+    marker.pos = marker.pos.translate(nx * abstand, ny * abstand);
+
+    // keep the box inside the map area and out of the Blattkopf
+    let clamp = |v: f64, min: f64, max: f64| if min > max { (min + max) / 2.0 } else { v.clamp(min, max) };
+    marker.pos.x = clamp(marker.pos.x, ex.min_x + w / 2.0, ex.max_x - w / 2.0);
+    marker.pos.y = clamp(marker.pos.y, ex.min_y + h / 2.0, ex.max_y - h / 2.0);
+
+    let kopf_x = ex.min_x + BLATTKOPF_BREITE_MM * mm;
+    let kopf_y = ex.max_y - BLATTKOPF_HOEHE_MM * mm;
+    let ueberlappt_x = kopf_x - (marker.pos.x - w / 2.0);
+    let ueberlappt_y = (marker.pos.y + h / 2.0) - kopf_y;
+    if ueberlappt_x > 0.0 && ueberlappt_y > 0.0 {
+        if ueberlappt_x < ueberlappt_y {
+            marker.pos.x += ueberlappt_x;
+        } else {
+            marker.pos.y -= ueberlappt_y;
+        }
+    }
+
+    marker
+}
+
+#[test]
+fn test_anschlussrisse_gemeinsame_kante() {
+    use crate::test_utils::test_rechteck as rect;
+
+    // two Risse 1:500 that share the edge x = 100 between y = 50 and y = 100
+    let links = rect(0.0, 0.0, 100.0, 100.0);
+    let rechts = rect(100.02, 50.0, 200.0, 150.0);
+    let (start, end) = get_gemeinsame_kante(&links, &rechts, 500.0).unwrap();
+    assert!((start.x - 100.0).abs() < 0.001 && (end.x - 100.0).abs() < 0.001);
+    assert!((start.y.min(end.y) - 50.0).abs() < 0.6);
+    assert!((start.y.max(end.y) - 100.0).abs() < 0.001);
+
+    // only touching at the corner
+    let ecke = rect(100.02, 100.02, 200.0, 200.0);
+    assert!(get_gemeinsame_kante(&links, &ecke, 500.0).is_none());
+
+    let ex = RissExtentReprojected {
+        crs: String::new(),
+        scale: 500.0,
+        min_x: 0.0,
+        max_x: 100.0,
+        min_y: 0.0,
+        max_y: 100.0,
+        rissgebiet: None,
+    };
+    let m = platziere_marker(&ex, &links, &start, &end, 2, 2);
+    assert!(m.vert);
+    assert_eq!(m.get_text(), "s. Anschlussriss (2 / 2)");
+    let (w, _) = m.get_size_m();
+    assert!(m.pos.x + w / 2.0 < 100.0);
+    // 3 mm Helvetica-Bold: 10.838 em
+    let (breite, _) = get_box_size_mm(&m.get_text());
+    assert!((breite - 32.514 - 2.0 * ANSCHLUSSRISS_PADDING_MM).abs() < 0.01);
+    assert!(m.pos.y > 50.0 && m.pos.y < 100.0);
+}
@@ -0,0 +1,256 @@
+//! Registry of the coordinate reference systems used in NAS files:
+//! maps AdV `urn:adv:crs:` identifiers to proj strings, EPSG codes and PRJ WKT.
+
+use crate::nas::SvgPoint;
+
+pub const ADV_CRS_PREFIX: &str = "urn:adv:crs:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrsInfo {
+    /// AdV identifier without the `urn:adv:crs:` prefix, e.g. "ETRS89_UTM33"
+    pub adv_id: String,
+    pub proj: String,
+    pub epsg: Option<u32>,
+    pub prj_wkt: String,
+    /// Zone number in front of the easting (33412345.678 instead of 412345.678)
+    /// if the coordinates of the NAS file already carry it
+    pub zone_prefix: Option<usize>,
+    kind: CrsKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum CrsKind {
+    Utm { zone: usize },
+    GaussKrueger { zone: usize },
+}
+
+impl CrsInfo {
+    fn utm(zone: usize) -> Self {
+        let epsg = if (28..=38).contains(&zone) {
+            Some(25800 + zone as u32)
+        } else {
+            None
+        };
+        Self {
+            adv_id: format!("ETRS89_UTM{zone}"),
+            proj: format!("+proj=utm +ellps=GRS80 +units=m +no_defs +zone={zone}"),
+            epsg,
+            prj_wkt: utm_wkt(zone, 500000.0, &format!("ETRS_1989_UTM_Zone_{zone}N"), epsg),
+            zone_prefix: None,
+            kind: CrsKind::Utm { zone },
+        }
+    }
+
+    /// UTM with the zone number in front of the easting ("zE-N")
+    fn utm_zone_prefixed(zone: usize) -> Self {
+        let epsg = match zone {
+            31 => Some(5649),
+            32 => Some(4647),
+            33 => Some(5650),
+            _ => None,
+        };
+        let false_easting = zone as f64 * 1_000_000.0 + 500000.0;
+        let lon_0 = zone as f64 * 6.0 - 183.0;
+        Self {
+            adv_id: format!("ETRS89_UTM{zone}"),
+            proj: format!(
+                "+proj=tmerc +lat_0=0 +lon_0={lon_0} +k=0.9996 +x_0={false_easting} +y_0=0 +ellps=GRS80 +units=m +no_defs"
+            ),
+            epsg,
+            prj_wkt: utm_wkt(zone, false_easting, &format!("ETRS_1989_UTM_Zone_{zone}N_zE-N"), epsg),
+            zone_prefix: Some(zone),
+            kind: CrsKind::Utm { zone },
+        }
+    }
+
+    fn gauss_krueger(zone: usize) -> Self {
+        let epsg = Some(31464 + zone as u32);
+        let false_easting = zone as f64 * 1_000_000.0 + 500000.0;
+        let lon_0 = zone as f64 * 3.0;
+        Self {
+            adv_id: format!("DE_DHDN_3GK{zone}"),
+            proj: format!(
+                "+proj=tmerc +lat_0=0 +lon_0={lon_0} +k=1 +x_0={false_easting} +y_0=0 +ellps=bessel +towgs84=598.1,73.7,418.2,0.202,0.045,-2.455,6.7 +units=m +no_defs"
+            ),
+            epsg,
+            prj_wkt: format!(
+                "PROJCS[\"DHDN_3_Degree_Gauss_Zone_{zone}\",GEOGCS[\"GCS_Deutsches_Hauptdreiecksnetz\",DATUM[\"D_Deutsches_Hauptdreiecksnetz\",SPHEROID[\"Bessel_1841\",6377397.155,299.1528128]],PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]],PROJECTION[\"Gauss_Kruger\"],PARAMETER[\"False_Easting\",{false_easting:.1}],PARAMETER[\"False_Northing\",0.0],PARAMETER[\"Central_Meridian\",{lon_0:.1}],PARAMETER[\"Scale_Factor\",1.0],PARAMETER[\"Latitude_Of_Origin\",0.0],UNIT[\"Meter\",1.0]{authority}]",
+                authority = wkt_authority(epsg),
+            ),
+            // Gauß-Krüger eastings always start with the zone number
+            zone_prefix: Some(zone),
+            kind: CrsKind::GaussKrueger { zone },
+        }
+    }
+
+    /// Variant of this CRS for coordinates with the zone number in front of the easting
+    pub fn with_zone_prefix(&self) -> Option<Self> {
+        match self.kind {
+            CrsKind::Utm { zone } if self.zone_prefix.is_none() => Some(Self::utm_zone_prefixed(zone)),
+            _ => None,
+        }
+    }
+
+    /// Offset of the GEOgraf eastings: zone number * 1 000 000 for UTM
+    /// without zone prefix, 0 if the eastings already carry the zone
+    pub fn dxf_offset(&self) -> f64 {
+        match (self.kind, self.zone_prefix) {
+            (CrsKind::Utm { zone }, None) => zone as f64 * 1_000_000.0,
+            _ => 0.0,
+        }
+    }
+
+    /// GEOgraf (DXF / GRAFBAT) expects eastings with the zone number in front
+    pub fn dxf_x(&self, x: f64) -> f64 {
+        x + self.dxf_offset()
+    }
+
+    /// Inverse of [CrsInfo::dxf_x], for coordinates read back from GEOgraf
+    pub fn from_dxf_x(&self, x: f64) -> f64 {
+        let offset = self.dxf_offset();
+        if offset > 0.0 && x >= 1_000_000.0 {
+            x - offset
+        } else {
+            x
+        }
+    }
+
+    /// SRS and coordinates for WMS requests: servers usually only know the
+    /// plain UTM codes, so zone-prefixed eastings are converted back
+    pub fn wms_srs(&self) -> String {
+        let epsg = match self.kind {
+            CrsKind::Utm { zone } if self.zone_prefix.is_some() => Self::utm(zone).epsg,
+            _ => self.epsg,
+        };
+        format!("EPSG:{}", epsg.unwrap_or(25833))
+    }
+
+    pub fn wms_point(&self, p: &SvgPoint) -> SvgPoint {
+        match (self.kind, self.zone_prefix) {
+            (CrsKind::Utm { zone }, Some(_)) => SvgPoint {
+                x: p.x - zone as f64 * 1_000_000.0,
+                y: p.y,
+            },
+            _ => *p,
+        }
+    }
+
+    pub fn get_urn(&self) -> String {
+        format!("{ADV_CRS_PREFIX}{}", self.adv_id)
+    }
+}
+
+fn wkt_authority(epsg: Option<u32>) -> String {
+    epsg.map(|e| format!(",AUTHORITY[\"EPSG\",{e}]"))
+        .unwrap_or_default()
+}
+
+fn utm_wkt(zone: usize, false_easting: f64, name: &str, epsg: Option<u32>) -> String {
+    let lon_0 = zone as f64 * 6.0 - 183.0;
+    format!(
+        "PROJCS[\"{name}\",GEOGCS[\"GCS_ETRS_1989\",DATUM[\"D_ETRS_1989\",SPHEROID[\"GRS_1980\",6378137.0,298.257222101]],PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]],PROJECTION[\"Transverse_Mercator\"],PARAMETER[\"False_Easting\",{false_easting:.1}],PARAMETER[\"False_Northing\",0.0],PARAMETER[\"Central_Meridian\",{lon_0:.1}],PARAMETER[\"Scale_Factor\",0.9996],PARAMETER[\"Latitude_Of_Origin\",0.0],UNIT[\"Meter\",1.0]{authority}]",
+        authority = wkt_authority(epsg),
+    )
+}
+
+fn get_all_crs() -> Vec<CrsInfo> {
+    let mut v = Vec::new();
+    for zone in 1..=60 {
+        v.push(CrsInfo::utm(zone));
+    }
+    for zone in 28..=38 {
+        v.push(CrsInfo::utm_zone_prefixed(zone));
+    }
+    for zone in 2..=5 {
+        v.push(CrsInfo::gauss_krueger(zone));
+    }
+    v
+}
+
+/// Lookup by AdV identifier, with or without `urn:adv:crs:` prefix
+pub fn get_crs_by_adv_id(id: &str) -> Option<CrsInfo> {
+    let id = id.trim().trim_start_matches(ADV_CRS_PREFIX);
+    get_all_crs().into_iter().find(|c| {
+        let is_zone_prefixed_utm = matches!(c.kind, CrsKind::Utm { .. }) && c.zone_prefix.is_some();
+        c.adv_id == id && !is_zone_prefixed_utm
+    })
+}
+
+/// Lookup by proj string (as stored in `NasXMLFile.crs` / `SplitNasXml.crs`)
+pub fn get_crs_by_proj(proj: &str) -> Option<CrsInfo> {
+    let proj = proj.trim();
+    get_all_crs().into_iter().find(|c| c.proj == proj)
+}
+
+/// Same as [get_crs_by_proj], falls back to ETRS89_UTM33 for unknown proj
+/// strings and logs a warning
+pub fn get_crs_by_proj_or_default(proj: &str) -> CrsInfo {
+    get_crs_by_proj(proj).unwrap_or_else(|| {
+        crate::log_status(&format!(
+            "WARNUNG: unbekanntes Koordinatensystem \"{proj}\", verwende ETRS89_UTM33"
+        ));
+        CrsInfo::utm(33)
+    })
+}
+
+/// Lookup by EPSG code, also finds the zone-prefixed UTM variants (e.g. 5650)
+pub fn get_crs_by_epsg(epsg: u32) -> Option<CrsInfo> {
+    get_all_crs().into_iter().find(|c| c.epsg == Some(epsg))
+}
+
+/// Lookup by the WKT of a `.prj` file: by `AUTHORITY["EPSG",...]` if present,
+/// otherwise by the PROJCS name. Geographic WKT (`GEOGCS` only) returns None.
+pub fn get_crs_by_prj_wkt(wkt: &str) -> Option<CrsInfo> {
+    let wkt = wkt.trim();
+    if let Some(pos) = wkt.rfind("AUTHORITY[\"EPSG\",") {
+        let rest = &wkt[pos + "AUTHORITY[\"EPSG\",".len()..];
+        let code = rest
+            .trim_start_matches('"')
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>();
+        if let Some(c) = code.parse().ok().and_then(get_crs_by_epsg) {
+            return Some(c);
+        }
+    }
+    let name = get_wkt_projcs_name(wkt)?;
+    get_all_crs()
+        .into_iter()
+        .find(|c| get_wkt_projcs_name(&c.prj_wkt).as_deref() == Some(name.as_str()))
+}
+
+fn get_wkt_projcs_name(wkt: &str) -> Option<String> {
+    let rest = wkt.strip_prefix("PROJCS[\"")?;
+    Some(rest.split('"').next()?.to_string())
+}
+
+#[test]
+fn test_crs_registry() {
+    let utm33 = get_crs_by_adv_id("urn:adv:crs:ETRS89_UTM33").unwrap();
+    assert_eq!(utm33.proj, crate::nas::default_etrs33());
+    assert_eq!(utm33.epsg, Some(25833));
+    assert_eq!(
+        utm33.prj_wkt,
+        "PROJCS[\"ETRS_1989_UTM_Zone_33N\",GEOGCS[\"GCS_ETRS_1989\",DATUM[\"D_ETRS_1989\",SPHEROID[\"GRS_1980\",6378137.0,298.257222101]],PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]],PROJECTION[\"Transverse_Mercator\"],PARAMETER[\"False_Easting\",500000.0],PARAMETER[\"False_Northing\",0.0],PARAMETER[\"Central_Meridian\",15.0],PARAMETER[\"Scale_Factor\",0.9996],PARAMETER[\"Latitude_Of_Origin\",0.0],UNIT[\"Meter\",1.0],AUTHORITY[\"EPSG\",25833]]"
+    );
+    assert_eq!(utm33.dxf_x(412345.5), 33412345.5);
+    assert_eq!(utm33.from_dxf_x(33412345.5), 412345.5);
+    assert_eq!(utm33.dxf_x(99999.25), 33099999.25);
+    assert_eq!(utm33.from_dxf_x(33099999.25), 99999.25);
+    assert_eq!(get_crs_by_proj_or_default("+proj=unbekannt"), CrsInfo::utm(33));
+
+    let prefixed = utm33.with_zone_prefix().unwrap();
+    assert_eq!(prefixed.epsg, Some(5650));
+    assert_eq!(prefixed.dxf_x(33412345.5), 33412345.5);
+    assert_eq!(get_crs_by_proj(&prefixed.proj), Some(prefixed));
+
+    let gk4 = get_crs_by_adv_id("DE_DHDN_3GK4").unwrap();
+    assert_eq!(gk4.epsg, Some(31468));
+    assert_eq!(gk4.wms_srs(), "EPSG:31468");
+    assert!(get_crs_by_adv_id("ETRS89_UTM32").is_some());
+
+    assert_eq!(get_crs_by_epsg(25833), Some(utm33.clone()));
+    assert_eq!(get_crs_by_prj_wkt(&utm33.prj_wkt), Some(utm33.clone()));
+    let ohne_authority = utm33.prj_wkt.replace(",AUTHORITY[\"EPSG\",25833]", "");
+    assert_eq!(get_crs_by_prj_wkt(&ohne_authority), Some(utm33));
+}
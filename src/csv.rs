@@ -157,6 +157,141 @@ pub struct CsvDatensatz {
     pub notiz: String,
 }
 
+/// Error while importing a LiKa CSV / XLSX, `zeile` and `spalte` start at 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CsvFehler {
+    pub zeile: usize,
+    pub spalte: Option<String>,
+    pub meldung: String,
+}
+
+impl std::fmt::Display for CsvFehler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.spalte {
+            Some(s) => write!(f, "Fehler in Zeile {}, Spalte {s}: {}", self.zeile, self.meldung),
+            None => write!(f, "Fehler in Zeile {}: {}", self.zeile, self.meldung),
+        }
+    }
+}
+
+/// Records of the file with the line number on which each record starts
+pub type CsvRecords = Vec<(usize, Vec<String>)>;
+
+const DELIMITER_KANDIDATEN: &[char] = &['|', ';', '\t', ','];
+
+/// RFC 4180: fields may be quoted, quotes inside quoted fields are escaped
+/// as `""`, quoted fields may contain the delimiter and line breaks.
+/// A record with an unclosed quote is reported and skipped, splitting
+/// continues with the next line.
+pub fn split_csv_records(csv: &str, delimiter: char) -> (CsvRecords, Vec<CsvFehler>) {
+    let mut records = Vec::new();
+    let mut fehler = Vec::new();
+    let mut rest = csv;
+    let mut zeile = 1;
+
+    while !rest.is_empty() {
+        match split_csv_record(rest, delimiter) {
+            Ok((record, verbraucht, zeilen)) => {
+                records.push((zeile, record));
+                rest = &rest[verbraucht..];
+                zeile += zeilen;
+            }
+            Err(spalte) => {
+                fehler.push(CsvFehler {
+                    zeile,
+                    spalte: Some(spalte.to_string()),
+                    meldung: "Anführungszeichen wird nicht geschlossen".to_string(),
+                });
+                rest = match rest.find('\n') {
+                    Some(i) => &rest[(i + 1)..],
+                    None => "",
+                };
+                zeile += 1;
+            }
+        }
+    }
+
+    (records, fehler)
+}
+
+/// Splits the first record of `csv`, returns the fields, the number of bytes
+/// and lines consumed or the column of an unclosed quote
+fn split_csv_record(csv: &str, delimiter: char) -> Result<(Vec<String>, usize, usize), usize> {
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut zeilen = 0;
+    let mut chars = csv.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek().map(|(_, c)| *c) == Some('"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\r' | '\n' => {
+                    if c == '\r' && chars.peek().map(|(_, c)| *c) == Some('\n') {
+                        chars.next();
+                    }
+                    zeilen += 1;
+                    field.push('\n');
+                }
+                c => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek().map(|(_, c)| *c) == Some('\n') {
+                    chars.next();
+                }
+                record.push(field);
+                let verbraucht = chars.peek().map(|(i, _)| *i).unwrap_or(csv.len());
+                return Ok((record, verbraucht, zeilen + 1));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(record.len() + 1);
+    }
+
+    record.push(field);
+    Ok((record, csv.len(), zeilen + 1))
+}
+
+/// Picks the delimiter that splits the first records into the same number
+/// (> 1) of columns, on a tie the one with more columns wins. Default: "|"
+pub fn detect_delimiter(csv: &str) -> char {
+    DELIMITER_KANDIDATEN
+        .iter()
+        .filter_map(|d| {
+            let (records, _) = split_csv_records(csv, *d);
+            let spalten = records
+                .iter()
+                .filter(|(_, r)| r.iter().any(|c| !c.trim().is_empty()))
+                .take(20)
+                .map(|(_, r)| r.len())
+                .collect::<Vec<_>>();
+            let min = spalten.iter().copied().min()?;
+            let max = spalten.iter().copied().max()?;
+            if min < 2 {
+                return None;
+            }
+            Some((min == max, min, *d))
+        })
+        .max_by_key(|(konsistent, spalten, _)| (*konsistent, *spalten))
+        .map(|(_, _, d)| d)
+        .unwrap_or('|')
+}
+
+/// `delimiter` empty = automatic detection, otherwise a single character
 pub fn parse_csv(
     csv: &str,
     id_col: &str,
@@ -164,44 +299,136 @@ pub fn parse_csv(
     eigentuemer_col: &str,
     delimiter: &str,
     ignore_firstline: bool,
-) -> Result<CsvDataType, String> {
-    let mut cells = csv
-        .lines()
-        .map(|l| l.split(delimiter).map(String::from).collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+) -> Result<CsvDataType, Vec<CsvFehler>> {
+    let mut zeichen = delimiter.chars();
+    let delimiter = match (zeichen.next(), zeichen.next()) {
+        (None, _) => detect_delimiter(csv),
+        (Some(d), None) => d,
+        (Some(_), Some(_)) => {
+            return Err(vec![CsvFehler {
+                zeile: 0,
+                spalte: None,
+                meldung: format!("Trennzeichen \"{delimiter}\" muss ein einzelnes Zeichen sein"),
+            }]);
+        }
+    };
+    let (records, mut fehler) = split_csv_records(csv, delimiter);
+    match parse_csv_records(records, id_col, nutzung_col, eigentuemer_col, ignore_firstline) {
+        Ok(o) if fehler.is_empty() => Ok(o),
+        Ok(_) => Err(fehler),
+        Err(e) => {
+            fehler.extend(e);
+            fehler.sort_by_key(|f| f.zeile);
+            Err(fehler)
+        }
+    }
+}
+
+/// LiKa export saved as .xlsx, only the first worksheet is read
+pub fn parse_xlsx(
+    xlsx: &[u8],
+    id_col: &str,
+    nutzung_col: &str,
+    eigentuemer_col: &str,
+    ignore_firstline: bool,
+) -> Result<CsvDataType, Vec<CsvFehler>> {
+    let records = read_xlsx_records(xlsx).map_err(|e| vec![e])?;
+    parse_csv_records(records, id_col, nutzung_col, eigentuemer_col, ignore_firstline)
+}
+
+pub fn parse_csv_records(
+    mut records: CsvRecords,
+    id_col: &str,
+    nutzung_col: &str,
+    eigentuemer_col: &str,
+    ignore_firstline: bool,
+) -> Result<CsvDataType, Vec<CsvFehler>> {
     let mut map = BTreeMap::new();
+    records.retain(|(_, r)| r.iter().any(|c| !c.trim().is_empty()));
 
-    if cells.is_empty() {
+    if records.is_empty() {
         return Ok(CsvDataType::Old(map).migrate_new());
     }
 
-    let attributes_in_order = if ignore_firstline {
-        cells.remove(0)
+    let (header_zeile, attributes_in_order) = if ignore_firstline {
+        let (zeile, header) = records.remove(0);
+        let header = header
+            .iter()
+            .map(|s| s.trim_start_matches('\u{feff}').trim().to_string())
+            .collect::<Vec<_>>();
+        (zeile, header)
     } else {
-        vec![
-            id_col.to_string(),
-            nutzung_col.to_string(),
-            eigentuemer_col.to_string(),
-        ]
+        (
+            0,
+            vec![
+                id_col.to_string(),
+                nutzung_col.to_string(),
+                eigentuemer_col.to_string(),
+            ],
+        )
+    };
+
+    let mut fehler = Vec::new();
+    let mut spalte = |col: &str| {
+        let pos = attributes_in_order.iter().position(|a| a == col);
+        if pos.is_none() {
+            fehler.push(CsvFehler {
+                zeile: header_zeile,
+                spalte: Some(col.to_string()),
+                meldung: "Spalte fehlt in der Kopfzeile".to_string(),
+            });
+        }
+        pos
+    };
+    let (id_i, nutzung_i, eigentuemer_i) = match (spalte(id_col), spalte(nutzung_col), spalte(eigentuemer_col)) {
+        (Some(a), Some(b), Some(c)) => (a, b, c),
+        _ => return Err(fehler),
     };
 
-    for (i, line) in cells.iter().enumerate() {
-        let i = if ignore_firstline { i + 2 } else { i + 1 };
-        let mut attributes = BTreeMap::new();
-        for (cell, col_name) in line.iter().zip(attributes_in_order.iter()) {
-            attributes.insert(col_name.clone(), cell.clone());
-        }
-        let id = attributes
-            .remove(id_col)
-            .ok_or_else(|| format!("Fehler in Zeile {i}: ID fehlt (Spalte {id_col})"))?;
-        let eigentuemer = attributes.remove(eigentuemer_col).ok_or_else(|| {
-            format!("Fehler in Zeile {i}: Eigentümer fehlt (Spalte {eigentuemer_col})")
-        })?;
-        let nutzung = attributes
-            .remove(nutzung_col)
-            .ok_or_else(|| format!("Fehler in Zeile {i}: Nutzung fehlt (Spalte {nutzung_col})"))?;
-
-        map.entry(id)
+    for (zeile, line) in records.iter() {
+        let zeile = *zeile;
+        let anzahl = attributes_in_order.len();
+        if line.len() > anzahl && line[anzahl..].iter().any(|c| !c.trim().is_empty()) {
+            fehler.push(CsvFehler {
+                zeile,
+                spalte: Some((anzahl + 1).to_string()),
+                meldung: format!(
+                    "{} Spalten statt {anzahl} (Trennzeichen in einer Zelle ohne Anführungszeichen?)",
+                    line.len()
+                ),
+            });
+            continue;
+        }
+
+        let get = |i: usize, col: &str, name: &str| {
+            line.get(i).cloned().ok_or_else(|| CsvFehler {
+                zeile,
+                spalte: Some(col.to_string()),
+                meldung: format!("{name} fehlt"),
+            })
+        };
+        let (id, nutzung, eigentuemer) = match (
+            get(id_i, id_col, "ID"),
+            get(nutzung_i, nutzung_col, "Nutzung"),
+            get(eigentuemer_i, eigentuemer_col, "Eigentümer"),
+        ) {
+            (Ok(a), Ok(b), Ok(c)) => (a, b, c),
+            (a, b, c) => {
+                fehler.extend([a.err(), b.err(), c.err()].into_iter().flatten());
+                continue;
+            }
+        };
+
+        if id.trim().is_empty() {
+            fehler.push(CsvFehler {
+                zeile,
+                spalte: Some(id_col.to_string()),
+                meldung: "ID ist leer".to_string(),
+            });
+            continue;
+        }
+
+        map.entry(id.trim().to_string())
             .or_insert_with(|| Vec::new())
             .push(CsvDatensatz {
                 eigentuemer,
@@ -210,9 +437,159 @@ pub fn parse_csv(
             });
     }
 
+    if !fehler.is_empty() {
+        return Err(fehler);
+    }
+
     Ok(CsvDataType::Old(map).migrate_new())
 }
 
+/// Reads the cells of the first worksheet (shared strings, inline strings
+/// and values), the line number is the row number in Excel
+pub fn read_xlsx_records(xlsx: &[u8]) -> Result<CsvRecords, CsvFehler> {
+    use crate::xml::{
+        parse_xml_string,
+        XmlNode,
+    };
+
+    let fehler = |meldung: String| CsvFehler {
+        zeile: 0,
+        spalte: None,
+        meldung,
+    };
+
+    let files = crate::zip::read_files_from_zip(xlsx, false, &[]);
+    let get_xml = |dir: &str, name: &str| -> Result<Option<Vec<XmlNode>>, CsvFehler> {
+        let bytes = match files
+            .iter()
+            .find(|(d, f, _)| d.as_deref() == Some(dir) && f.to_string_lossy() == name)
+        {
+            Some(s) => &s.2,
+            None => return Ok(None),
+        };
+        let s = String::from_utf8_lossy(bytes);
+        parse_xml_string(&s, &mut Vec::new())
+            .map(Some)
+            .map_err(|e| fehler(format!("{dir}/{name}: ungültiges XML: {e:?}")))
+    };
+
+    fn text(node: &XmlNode, target: &mut String) {
+        if node.node_type == "t" {
+            if let Some(t) = node.text.as_deref() {
+                target.push_str(&html_escape::decode_html_entities(t));
+            }
+        }
+        for c in node.children.iter() {
+            text(c, target);
+        }
+    }
+
+    let shared_strings = get_xml("xl", "sharedStrings.xml")?
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|sst| sst.get_all_children_of_type("si"))
+        .map(|si| {
+            let mut s = String::new();
+            text(si, &mut s);
+            s
+        })
+        .collect::<Vec<_>>();
+
+    let mut sheets = files
+        .iter()
+        .filter(|(d, f, _)| {
+            d.as_deref() == Some("xl/worksheets") && f.to_string_lossy().ends_with(".xml")
+        })
+        .map(|(_, f, _)| f.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    sheets.sort();
+    let sheet_name = sheets
+        .first()
+        .ok_or_else(|| fehler("Keine gültige XLSX-Datei: kein Tabellenblatt gefunden".to_string()))?;
+    let sheet = get_xml("xl/worksheets", sheet_name)?.unwrap_or_default();
+
+    let mut records = Vec::new();
+    let rows = sheet
+        .iter()
+        .flat_map(|ws| ws.get_all_children_of_type("sheetData"))
+        .flat_map(|sd| sd.get_all_children_of_type("row"));
+    for (i, row) in rows.enumerate() {
+        let zeile = row
+            .attributes
+            .get("r")
+            .and_then(|r| r.parse().ok())
+            .unwrap_or(i + 1);
+        let mut record = Vec::new();
+        for c in row.get_all_children_of_type("c") {
+            let spalte = c
+                .attributes
+                .get("r")
+                .map(|r| get_xlsx_spalte(r))
+                .transpose()
+                .map_err(|meldung| CsvFehler {
+                    zeile,
+                    spalte: c.attributes.get("r").cloned(),
+                    meldung,
+                })?
+                .flatten()
+                .unwrap_or(record.len());
+            let wert = c
+                .get_all_children_of_type("v")
+                .first()
+                .and_then(|v| v.text.clone())
+                .map(|v| html_escape::decode_html_entities(&v).to_string())
+                .unwrap_or_default();
+            let wert = match c.attributes.get("t").map(|s| s.as_str()) {
+                Some("s") => {
+                    let idx = wert.trim().parse::<usize>().ok();
+                    match idx.and_then(|i| shared_strings.get(i)) {
+                        Some(s) => s.clone(),
+                        None => {
+                            return Err(CsvFehler {
+                                zeile,
+                                spalte: Some((spalte + 1).to_string()),
+                                meldung: format!("Verweis auf unbekannten Text \"{wert}\""),
+                            })
+                        }
+                    }
+                }
+                Some("inlineStr") => {
+                    let mut s = String::new();
+                    text(c, &mut s);
+                    s
+                }
+                _ => wert,
+            };
+            if record.len() <= spalte {
+                record.resize(spalte + 1, String::new());
+            }
+            record[spalte] = wert;
+        }
+        records.push((zeile, record));
+    }
+
+    Ok(records)
+}
+
+/// Number of columns in Excel, the last one is "XFD"
+const XLSX_MAX_SPALTEN: usize = 16384;
+
+/// "AB12" -> 27 (starting at 0), error for columns after "XFD"
+fn get_xlsx_spalte(r: &str) -> Result<Option<usize>, String> {
+    let buchstaben = r.chars().take_while(|c| c.is_ascii_alphabetic()).collect::<String>();
+    if buchstaben.is_empty() {
+        return Ok(None);
+    }
+    let n = buchstaben
+        .to_ascii_uppercase()
+        .bytes()
+        .fold(0_usize, |acc, b| acc.saturating_mul(26).saturating_add((b - b'A' + 1) as usize));
+    if n > XLSX_MAX_SPALTEN {
+        return Err(format!("Ungültige Spalte \"{buchstaben}\", die letzte Excel-Spalte ist XFD"));
+    }
+    Ok(Some(n - 1))
+}
+
 pub fn search_for_flst_id(csv: &CsvDataType, flst_id: &str) -> Option<(String, Vec<CsvDatensatz>)> {
     let flst_id = flst_id.replace("_", "");
     let parsed = FlstIdParsed::from_str(&flst_id)
@@ -226,3 +603,41 @@ pub fn search_for_flst_id(csv: &CsvDataType, flst_id: &str) -> Option<(String, V
         }
     })
 }
+
+#[test]
+fn test_parse_csv_rfc4180() {
+    let csv = "Flurstueck;Nutzung;Eigentuemer\r\n\
+        120001001000010000;GR;\"Müller; Hans (Herr)\"\r\n\
+        120001001000020000;\"A\"\"B\";\"Firma\nZeile 2\"\r\n";
+    assert_eq!(detect_delimiter(csv), ';');
+    let (records, fehler) = split_csv_records(csv, ';');
+    assert!(fehler.is_empty());
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[1].1[2], "Müller; Hans (Herr)");
+    assert_eq!(records[2].1[1], "A\"B");
+    assert_eq!(records[2].1[2], "Firma\nZeile 2");
+    let data = parse_csv(csv, "Flurstueck", "Nutzung", "Eigentuemer", "", true).unwrap();
+    assert_eq!(data.keys().len(), 2);
+
+    let fehler = parse_csv("Flurstueck|Nutzung|Eigentuemer\n1|GR|a|b\n|GR|c\n", "Flurstueck", "Nutzung", "Eigentuemer", "|", true)
+        .unwrap_err();
+    assert_eq!(fehler.len(), 2);
+    assert_eq!(fehler[0].zeile, 2);
+    assert_eq!(fehler[1].zeile, 3);
+    assert_eq!(fehler[1].spalte.as_deref(), Some("Flurstueck"));
+    let (records, fehler) = split_csv_records("a;b\n\"c;d\ne;f\n", ';');
+    assert_eq!(records, vec![(1, vec!["a".to_string(), "b".to_string()]), (3, vec!["e".to_string(), "f".to_string()])]);
+    assert_eq!(fehler.len(), 1);
+    assert_eq!(fehler[0].zeile, 2);
+
+    let fehler = parse_csv("Flurstueck|Nutzung|Eigentuemer\n\"1|GR|a\n|GR|c\n2|GR|d\n", "Flurstueck", "Nutzung", "Eigentuemer", "|", true)
+        .unwrap_err();
+    assert_eq!(fehler.iter().map(|f| f.zeile).collect::<Vec<_>>(), vec![2, 3]);
+    let fehler = parse_csv("a||b\n", "a", "b", "c", "||", false).unwrap_err();
+    assert_eq!(fehler.len(), 1);
+    assert_eq!(get_xlsx_spalte("AB12"), Ok(Some(27)));
+    assert_eq!(get_xlsx_spalte("XFD1"), Ok(Some(16383)));
+    assert_eq!(get_xlsx_spalte("12"), Ok(None));
+    assert!(get_xlsx_spalte("XFE1").is_err());
+    assert!(get_xlsx_spalte("ZZZZZZZZZZZZZZZZ1").is_err());
+}
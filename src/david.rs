@@ -1,12 +1,22 @@
 use crate::{
     csv::CsvDataType, nas::{
-        self, MemberObject, NasXMLFile, NasXmlObjects, NasXmlQuadTree, SplitNasXml, SplitNasXmlQuadTree, SvgLine, SvgPoint, SvgPolygon, SvgPolygonInner, TaggedPolygon
+        self, GmlArc, MemberObject, NasXMLFile, NasXmlObjects, NasXmlQuadTree, SplitNasXml, SplitNasXmlQuadTree, SvgLine, SvgPoint, SvgPolygon, SvgPolygonInner, TaggedPolygon
     }, ops::{
         intersect_polys, join_polys, join_polys_fast, subtract_from_poly
     }, ui::{Aenderungen, PolyNeu}, uuid_wasm::{
         log_status, log_status_clear, uuid
     }
 };
+use crate::pdf::{
+    AntragskopfConfig,
+    ProjektInfo,
+    SignaturKatalog,
+    SignaturTyp,
+};
+use serde_derive::{
+    Deserialize,
+    Serialize,
+};
 use std::collections::{
     BTreeMap,
     BTreeSet,
@@ -58,17 +68,77 @@ impl Operation {
     }
 }
 
+/// Header fields of the `AX_Fortfuehrungsauftrag` (antrag.xml)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Antragskopf {
+    pub profilkennung: String,
+    pub antragsnummer: String,
+    pub auftragsnummer: String,
+    pub verarbeitungsart: String,
+    pub implizite_loeschung_der_reservierung: String,
+    /// AdV CRS id without the `urn:adv:crs:` prefix
+    pub crs: String,
+    pub oid_praefix: String,
+}
+
+impl Antragskopf {
+    pub fn new(
+        info: &ProjektInfo,
+        config: &AntragskopfConfig,
+        nas_crs: &str,
+        datum_jetzt: &chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<Self, String> {
+        let oid_praefix = config.oid_praefix.trim().to_string();
+        crate::oid::check_oid_praefix(&oid_praefix)?;
+
+        let nummer = |schema: &str| {
+            schema
+                .trim()
+                .replace("{dienststelle}", config.dienststelle.trim())
+                .replace("{datum}", &datum_jetzt.format("%Y%m%d").to_string())
+                .replace("{auftragsart}", config.auftragsart.trim())
+                .replace("{antragsnr}", info.antragsnr.trim())
+                .replace("{katasteramt}", info.katasteramt.trim())
+        };
+        let antragsnummer = nummer(&config.antragsnummer_schema);
+        let auftragsnummer = match config.auftragsnummer_schema.trim() {
+            "" => antragsnummer.clone(),
+            s => nummer(s),
+        };
+
+        let crs = match config.crs.trim() {
+            "" => crate::crs::get_crs_by_proj_or_default(nas_crs).adv_id,
+            s => s.trim_start_matches(crate::crs::ADV_CRS_PREFIX).to_string(),
+        };
+
+        Ok(Self {
+            profilkennung: config.profilkennung.trim().to_string(),
+            antragsnummer,
+            auftragsnummer,
+            verarbeitungsart: config.verarbeitungsart.trim().to_string(),
+            implizite_loeschung_der_reservierung: config
+                .implizite_loeschung_der_reservierung
+                .trim()
+                .to_string(),
+            crs,
+            oid_praefix,
+        })
+    }
+}
+
 pub fn aenderungen_zu_fa_xml(
     aenderungen: &Aenderungen,
     nas_xml: &NasXMLFile,
     split_nas: &SplitNasXml,
     csv: &CsvDataType,
     objects: &NasXmlObjects,
+    antragskopf: &Antragskopf,
+    signaturen: &SignaturKatalog,
     datum_jetzt: &chrono::DateTime<chrono::FixedOffset>,
-) -> String {
+) -> Result<String, String> {
     let aenderungen_gesamt = build_operations(aenderungen, nas_xml, split_nas, csv);
     crate::david::insert_gebaeude_delete(&aenderungen, &aenderungen_gesamt);
-    operations_to_xml_file(&aenderungen_gesamt, objects, datum_jetzt)
+    operations_to_xml_file(&aenderungen_gesamt, objects, datum_jetzt, antragskopf, signaturen)
 }
 
 pub fn build_operations(
@@ -169,25 +239,77 @@ impl OpPrereq {
     }
 }
 
+/// Object id of a `xlink:href` reference (`urn:adv:oid:DE...` or `#DE...`)
+pub fn get_referenz_id(href: &str) -> &str {
+    href.trim()
+        .trim_start_matches('#')
+        .trim_start_matches("urn:adv:oid:")
+}
+
+/// Presentation objects (AP_PTO, AP_PPO, AP_Darstellung, ...) per object id:
+/// objects that point to it via `dientZurDarstellungVon` / `istBestandteilVon`
+/// and objects it points to via `hat`
+pub struct PraesentationsIndex {
+    abhaengig: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl PraesentationsIndex {
+    pub fn new(objects: &NasXmlObjects) -> Self {
+        let mut abhaengig = BTreeMap::<String, BTreeSet<String>>::new();
+        for (id, o) in objects.objects.iter() {
+            if o.member_type.starts_with("AP_") {
+                for r in [&o.dient_zur_darstellung_von, &o.ist_bestandteil_von].into_iter().flatten() {
+                    abhaengig.entry(get_referenz_id(r).to_string()).or_default().insert(id.clone());
+                }
+            }
+            if let Some(hat) = o.hat.as_deref() {
+                let hat = get_referenz_id(hat);
+                let ist_ap = objects.objects.get(hat).map(|h| h.member_type.starts_with("AP_")).unwrap_or(false);
+                if ist_ap {
+                    abhaengig.entry(id.clone()).or_default().insert(hat.to_string());
+                }
+            }
+        }
+        Self { abhaengig }
+    }
+
+    /// All presentation objects that depend on `obj_id`, transitively
+    /// (e.g. AP_Darstellung -> AP_PTO that is part of it)
+    pub fn get_abhaengige_objekte(&self, obj_id: &str) -> BTreeSet<String> {
+        let mut result = BTreeSet::new();
+        let mut todo = vec![obj_id.to_string()];
+        while let Some(id) = todo.pop() {
+            for a in self.abhaengig.get(&id).into_iter().flatten() {
+                if a != obj_id && result.insert(a.clone()) {
+                    todo.push(a.clone());
+                }
+            }
+        }
+        result
+    }
+}
+
 pub fn operations_to_xml_file_internal(
     aenderungen_todo: &[Operation], 
     objects: &NasXmlObjects, 
-    datum_jetzt: &chrono::DateTime<chrono::FixedOffset>
+    datum_jetzt: &chrono::DateTime<chrono::FixedOffset>,
+    signaturen: &SignaturKatalog,
+    oids: &mut crate::oid::OidGenerator,
 ) -> Vec<(String, OpPrereq)> {
+    let insert_ids = aenderungen_todo.iter()
+    .enumerate()
+    .filter(|(_, s)| matches!(s, Operation::Insert { .. }))
+    .map(|(i, _)| (i, oids.next_oid()))
+    .collect::<BTreeMap<_, _>>();
+
     let mut final_strings = aenderungen_todo.iter()
     .enumerate()
     .filter_map(|(i, s)| {
         match s {
         Operation::Delete { obj_id, .. } => {
             let o = objects.objects.get(obj_id)?;
-            if o.poly.is_empty() {
-                return None; // TODO: Delete non-polygon objects (attributes, AP_PTO, etc.)
-            }
-            let beginnt = o.beginnt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true).replace("-", "").replace(":", "");
-            let rid = format!("{obj_id}{beginnt}");
-            let typename = &o.member_type;
             Some((
-                format!("            <wfs:Delete typeName=\"{typename}\"><fes:Filter><fes:ResourceId rid=\"{rid}\" /></fes:Filter></wfs:Delete>"),
+                get_delete_xml_node(obj_id, o),
                 OpPrereq::Delete { id: obj_id.clone() }
             ))
         },
@@ -195,10 +317,10 @@ pub fn operations_to_xml_file_internal(
             let mut auto_attribute = TaggedPolygon::get_auto_attributes_for_kuerzel(&kuerzel, &[]);
             auto_attribute.remove("AX_Ebene");
             let auto_attribute = auto_attribute.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect::<Vec<_>>();
-            let obj_id = "DE_001".to_string() + &format!("{i:010}");
+            let obj_id = insert_ids.get(&i)?;
             Some((get_insert_xml_node(
                     ebene,
-                    &obj_id,
+                    obj_id,
                     &auto_attribute,
                     datum_jetzt,
                     poly_neu,
@@ -215,7 +337,7 @@ pub fn operations_to_xml_file_internal(
         } => {
             let o = objects.objects.get(obj_id)?;
             if o.poly.is_empty() {
-                return None; // TODO: Delete non-polygon objects (attributes, AP_PTO, etc.)
+                return None; // Objekt ohne Fläche, Geometrie kann nicht ersetzt werden
             }
             Some((
                 (get_replace_xml_node(
@@ -228,16 +350,22 @@ pub fn operations_to_xml_file_internal(
         }
     }}).collect::<Vec<_>>();
 
+    final_strings.extend(get_praesentationsobjekte_operations(aenderungen_todo, objects));
+
     for (id, a) in aenderungen_todo.iter().enumerate() {
         if let Operation::Insert { kuerzel, poly_neu, .. } = a {
-            let obj_id_new = "DE_001".to_string() + &format!("{id:010}");
+            let obj_id_new = match insert_ids.get(&id) {
+                Some(s) => s,
+                None => continue,
+            };
             if let Some(symbol) = Signatur::from_kuerzel(
                 kuerzel, 
-                &obj_id_new,
+                obj_id_new,
                 poly_neu, 
-                id * 2,
+                signaturen,
+                oids,
             ) {
-                final_strings.push((symbol.get_xml(), OpPrereq::Insert { id: obj_id_new.clone() }));
+                final_strings.push((symbol.get_xml(), OpPrereq::Insert { id: symbol.get_id().to_string() }));
             }
         }
     }
@@ -245,6 +373,73 @@ pub fn operations_to_xml_file_internal(
     final_strings    
 }
 
+/// Deletes the presentation objects of deleted objects, so that no orphaned
+/// labels / signatures stay in the DHK. Labels of replaced objects that are
+/// no longer inside the new geometry are moved to its label position.
+pub fn get_praesentationsobjekte_operations(
+    aenderungen_todo: &[Operation],
+    objects: &NasXmlObjects,
+) -> Vec<(String, OpPrereq)> {
+    let index = PraesentationsIndex::new(objects);
+
+    let geloescht = aenderungen_todo
+        .iter()
+        .filter_map(|s| match s {
+            Operation::Delete { obj_id, .. } => Some(obj_id.clone()),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut abhaengig_geloescht = BTreeSet::new();
+    for obj_id in geloescht.iter() {
+        for a in index.get_abhaengige_objekte(obj_id) {
+            if !geloescht.contains(&a) {
+                abhaengig_geloescht.insert(a);
+            }
+        }
+    }
+
+    let mut v = abhaengig_geloescht
+        .iter()
+        .filter_map(|id| {
+            let o = objects.objects.get(id)?;
+            Some((get_delete_xml_node(id, o), OpPrereq::Delete { id: id.clone() }))
+        })
+        .collect::<Vec<_>>();
+
+    let mut verschoben = BTreeSet::new();
+    for op in aenderungen_todo.iter() {
+        let (obj_id, poly_neu) = match op {
+            Operation::Replace { obj_id, poly_neu, .. } => (obj_id, poly_neu),
+            _ => continue,
+        };
+        for a in index.get_abhaengige_objekte(obj_id) {
+            if geloescht.contains(&a) || abhaengig_geloescht.contains(&a) || verschoben.contains(&a) {
+                continue;
+            }
+            let o = match objects.objects.get(&a) {
+                Some(s) => s,
+                None => continue,
+            };
+            let punkt = match o.punkt.as_ref() {
+                Some(s) => s,
+                None => continue,
+            };
+            if crate::nas::point_is_in_polygon(punkt, poly_neu) {
+                continue;
+            }
+            let neu = match poly_neu.get_label_pos() {
+                Some(s) => s,
+                None => continue,
+            };
+            v.push((get_replace_punkt_xml_node(&a, o, &neu), OpPrereq::Replace { id: a.clone() }));
+            verschoben.insert(a);
+        }
+    }
+
+    v
+}
+
 pub fn process_final_strings(
     input: &[(String, OpPrereq)]
 ) -> Vec<(String, OpPrereq)> {
@@ -287,10 +482,13 @@ pub fn filter_identic_operations(
 pub fn operations_to_xml_file(
     aenderungen_todo: &[Operation], 
     objects: &NasXmlObjects, 
-    datum_jetzt: &chrono::DateTime<chrono::FixedOffset>
-) -> String {
+    datum_jetzt: &chrono::DateTime<chrono::FixedOffset>,
+    antragskopf: &Antragskopf,
+    signaturen: &SignaturKatalog,
+) -> Result<String, String> {
 
-    let final_strings = operations_to_xml_file_internal(aenderungen_todo, objects, datum_jetzt);
+    let mut oids = crate::oid::OidGenerator::new(&antragskopf.oid_praefix, objects, datum_jetzt)?;
+    let final_strings = operations_to_xml_file_internal(aenderungen_todo, objects, datum_jetzt, signaturen, &mut oids);
     let final_strings = process_final_strings(&final_strings);
     let mut final_strings = final_strings.iter().map(|s| s.0.clone()).collect::<Vec<_>>();
     final_strings.sort();
@@ -299,16 +497,19 @@ pub fn operations_to_xml_file(
 
     let s = format!(
         include_str!("./antrag.xml"),
-        crs = "ETRS89_UTM33",
+        crs = antragskopf.crs,
         content = final_strings,
-        profilkennung = "schuettf",
-        antragsnr = "73_0073_".to_string() + &format!("{}", datum_jetzt.format("%Y%m%d")) + "_999",
+        profilkennung = antragskopf.profilkennung,
+        antragsnr = antragskopf.antragsnummer,
+        auftragsnr = antragskopf.auftragsnummer,
+        implizite_loeschung = antragskopf.implizite_loeschung_der_reservierung,
+        verarbeitungsart = antragskopf.verarbeitungsart,
     );
 
-    s.lines()
+    Ok(s.lines()
     .filter_map(|s| if s.trim().is_empty() { None } else { Some(s.to_string()) })
     .collect::<Vec<_>>()
-    .join("\r\n")
+    .join("\r\n"))
 }
 
 pub fn aenderungen_zu_nas_xml(
@@ -647,98 +848,47 @@ pub enum Signatur {
         fuer: String,
         signaturnummer: String,
         art: String,
-        positionierungsregel: String,
+        positionierungsregel: Option<String>,
     }
 }
 
 impl Signatur {
 
-    fn from_kuerzel(kuerzel: &str, obj_id: &str, poly: &SvgPolygonInner, id: usize) -> Option<Self> {
-        match kuerzel {
-            "SUM" => {
+    fn from_kuerzel(
+        kuerzel: &str,
+        obj_id: &str,
+        poly: &SvgPolygonInner,
+        signaturen: &SignaturKatalog,
+        oids: &mut crate::oid::OidGenerator,
+    ) -> Option<Self> {
+        let stil = signaturen.get(kuerzel)?;
+        match stil.typ {
+            SignaturTyp::Punkt => {
                 let pt = poly.get_label_pos()?;
-                Some(Self::Punkt { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    pos: pt, 
-                    signaturnummer: "3478".to_string(), 
-                    art: "Sumpf".to_string()
-                })
-            },
-            "WAS" => {
-                let pt = poly.get_label_pos()?;
-                Some(Self::Punkt { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    pos: pt, 
-                    signaturnummer: "3490".to_string(), 
-                    art: "FKT".to_string()
-                })
-            },
-            "WALD" => {
-                Some(Self::Flaeche { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    signaturnummer: "3456".to_string(), 
-                    art: "VEG".to_string(), 
-                    positionierungsregel: "1104".to_string()
-                })
-            },
-            "LH" => {
-                Some(Self::Flaeche { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    signaturnummer: "3458".to_string(),
-                    art: "VEG".to_string(), 
-                    positionierungsregel: "1104".to_string()
-                })
-            },
-            "NH" => {
-                Some(Self::Flaeche { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    signaturnummer: "3460".to_string(),
-                    art: "VEG".to_string(), 
-                    positionierungsregel: "1104".to_string()
-                })
-            },
-            "LNH" => {
-                Some(Self::Flaeche { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    signaturnummer: "3462".to_string(),
-                    art: "VEG".to_string(), 
-                    positionierungsregel: "1104".to_string()
+                Some(Self::Punkt {
+                    id: oids.next_oid(),
+                    fuer: obj_id.to_string(),
+                    pos: pt,
+                    signaturnummer: stil.signaturnummer.clone(),
+                    art: stil.art.clone(),
                 })
             },
-            "GR" => {
-                Some(Self::Flaeche { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    signaturnummer: "3413".to_string(),
-                    art: "VEG".to_string(), 
-                    positionierungsregel: "1100".to_string()
+            SignaturTyp::Flaeche => {
+                Some(Self::Flaeche {
+                    id: oids.next_oid(),
+                    fuer: obj_id.to_string(),
+                    signaturnummer: stil.signaturnummer.clone(),
+                    art: stil.art.clone(),
+                    positionierungsregel: stil.positionierungsregel.clone(),
                 })
             },
-            "GRÜ" => {
-                Some(Self::Flaeche { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    signaturnummer: "3413".to_string(),
-                    art: "FKT".to_string(), 
-                    positionierungsregel: "1100".to_string()
-                })
-            },
-            "G" => {
-                Some(Self::Flaeche { 
-                    id: ("DE_001".to_string() + &format!("{id:010}")), 
-                    fuer: obj_id.to_string(), 
-                    signaturnummer: "3421".to_string(),
-                    art: "VEG".to_string(), 
-                    positionierungsregel: "1100".to_string()
-                })
-            },
-            _ => None,
+        }
+    }
+
+    fn get_id(&self) -> &str {
+        match self {
+            Signatur::Punkt { id, .. } => id,
+            Signatur::Flaeche { id, .. } => id,
         }
     }
 
@@ -791,7 +941,7 @@ impl Signatur {
 					<signaturnummer>$$SIGNATURNUMMER$$</signaturnummer>
 					<art>$$ART$$</art>
 					<dientZurDarstellungVon xlink:href="urn:adv:oid:$$FUER$$"/>
-					<positionierungsregel>$$POSITIONIERUNGSREGEL$$</positionierungsregel>
+					$$POSITIONIERUNGSREGEL$$
 				</AP_Darstellung>
 			</wfs:Insert>
         "#;
@@ -809,7 +959,10 @@ impl Signatur {
                 FLAECHENSIGNATUR
                 .replace("$$OBJ_ID$$", id)
                 .replace("$$FUER$$", fuer)
-                .replace("$$POSITIONIERUNGSREGEL$$", positionierungsregel)
+                .replace("$$POSITIONIERUNGSREGEL$$", &match positionierungsregel {
+                    Some(p) => format!("<positionierungsregel>{p}</positionierungsregel>"),
+                    None => String::new(),
+                })
                 .replace("$$ART$$", art)
                 .replace("$$SIGNATURNUMMER$$", signaturnummer)
             },
@@ -1096,32 +1249,90 @@ pub fn log_aenderungen(aenderungen_todo: &[Operation]) {
 }
 
 pub fn line_to_ring(l: &SvgLine) -> String {
+    line_to_ring_with_arcs(l, &[])
+}
+
+enum RingSegment {
+    Line(Vec<SvgPoint>),
+    Arc(GmlArc),
+}
+
+/// Splits the ring into straight segments and the original (unchanged) GML arcs
+fn split_ring_segments(points: &[SvgPoint], arcs: &[GmlArc]) -> Vec<RingSegment> {
+    let mut segments = Vec::new();
+    let mut current = Vec::new();
+    let mut i = 0;
+    while i < points.len() {
+        current.push(points[i]);
+        match arcs.iter().find_map(|a| a.matches_start_of(&points[i..])) {
+            Some(a) => {
+                if current.len() > 1 {
+                    segments.push(RingSegment::Line(current));
+                }
+                // next segment starts at the end point of the arc
+                current = Vec::new();
+                i += a.points.len() - 1;
+                segments.push(RingSegment::Arc(a));
+            }
+            None => i += 1,
+        }
+    }
+    if current.len() > 1 {
+        segments.push(RingSegment::Line(current));
+    }
+    segments
+}
+
+pub fn line_to_ring_with_arcs(l: &SvgLine, arcs: &[GmlArc]) -> String {
     const RING_XML: &str = r#"
                                         <gml:Ring>
                                             <gml:curveMember>
                                                 <gml:Curve>
                                                     <gml:segments>
-                                                        <gml:LineStringSegment>
-                                                            <gml:posList>$$POSLIST$$</gml:posList>
-                                                        </gml:LineStringSegment>
+$$SEGMENTS$$
                                                     </gml:segments>
                                                 </gml:Curve>
                                             </gml:curveMember>
                                         </gml:Ring>
     "#;
 
-    RING_XML
-        .replace(
-            "$$POSLIST$$",
-            &l.points
-                .iter()
-                .map(|s| format!("{:.3} {:.3}", s.x, s.y))
-                .collect::<Vec<_>>()
-                .join(" "),
-        )
+    const SEGMENT_XML: &str = r#"                                                        <gml:$$SEGMENT_TYPE$$>
+                                                            <gml:posList>$$POSLIST$$</gml:posList>
+                                                        </gml:$$SEGMENT_TYPE$$>"#;
+
+    let poslist = |points: &[SvgPoint]| {
+        points
+            .iter()
+            .map(|s| format!("{:.3} {:.3}", s.x, s.y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let segments = split_ring_segments(&l.points, arcs)
+        .iter()
+        .map(|s| {
+            let (segment_type, points) = match s {
+                RingSegment::Line(points) => ("LineStringSegment", points.clone()),
+                RingSegment::Arc(a) if a.circle => ("Circle", vec![a.start, a.mid, a.end]),
+                RingSegment::Arc(a) => ("Arc", vec![a.start, a.mid, a.end]),
+            };
+            SEGMENT_XML
+                .replace("$$SEGMENT_TYPE$$", segment_type)
+                .replace("$$POSLIST$$", &poslist(&points))
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    RING_XML.replace("$$SEGMENTS$$", &segments)
 }
 
 pub fn polygon_to_position_node(p: &SvgPolygonInner) -> String {
+    polygon_to_position_node_with_arcs(p, &[])
+}
+
+/// Writes the polygon as `<position>`, boundaries that still match one of
+/// the original `arcs` are written as `gml:Arc` / `gml:Circle`
+pub fn polygon_to_position_node_with_arcs(p: &SvgPolygonInner, arcs: &[GmlArc]) -> String {
     const POLY_XML: &str = r#"
                     <position>
                         <gml:Surface>
@@ -1135,7 +1346,7 @@ pub fn polygon_to_position_node(p: &SvgPolygonInner) -> String {
                     </position>
     "#;
 
-    let outer_rings = Some(line_to_ring(&p.outer_ring))
+    let outer_rings = Some(line_to_ring_with_arcs(&p.outer_ring, arcs))
         .map(|or| {
             format!("
                                     <gml:exterior>
@@ -1148,7 +1359,7 @@ pub fn polygon_to_position_node(p: &SvgPolygonInner) -> String {
         .inner_rings
         .iter()
         .map(|l| {
-            line_to_ring(l)
+            line_to_ring_with_arcs(l, arcs)
         })
         .map(|ir| {
             format!("
@@ -1214,11 +1425,47 @@ pub fn get_insert_xml_node(
         .replace("$$EXTRA_ATTRIBUTE$$", &attribute)
 }
 
+pub fn get_delete_xml_node(obj_id: &str, member_object: &MemberObject) -> String {
+    let beginnt = member_object
+        .beginnt
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        .replace("-", "")
+        .replace(":", "");
+    let rid = format!("{obj_id}{beginnt}");
+    let typename = &member_object.member_type;
+    format!("            <wfs:Delete typeName=\"{typename}\"><fes:Filter><fes:ResourceId rid=\"{rid}\" /></fes:Filter></wfs:Delete>")
+}
+
 pub fn get_replace_xml_node(
     obj_id: &str,
     member_object: &MemberObject,
     poly: &SvgPolygonInner,
 ) -> String {
+    get_replace_xml_node_internal(
+        obj_id,
+        member_object,
+        &polygon_to_position_node_with_arcs(poly, &member_object.arcs),
+    )
+}
+
+/// Replace for presentation objects with a `gml:Point` position (AP_PTO, AP_PPO)
+pub fn get_replace_punkt_xml_node(
+    obj_id: &str,
+    member_object: &MemberObject,
+    punkt: &SvgPoint,
+) -> String {
+    let position = format!(
+        "<position><gml:Point><gml:pos>{:.3} {:.3}</gml:pos></gml:Point></position>",
+        punkt.x, punkt.y
+    );
+    get_replace_xml_node_internal(obj_id, member_object, &position)
+}
+
+fn get_replace_xml_node_internal(
+    obj_id: &str,
+    member_object: &MemberObject,
+    position_node: &str,
+) -> String {
 
     let mut attr = member_object.extra_attribute.clone();
     attr.remove("datumDerLetztenUeberpruefung");
@@ -1276,9 +1523,147 @@ $$EXTRA_ATTRIBUTE$$
         .replace("$$RESOURCE_ID$$", &rid)
         .replace("$$OBJECT_ID$$", obj_id)
         .replace("$$ORIGINAL_DATE$$", &member_object.beginnt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
-        .replace(
-            "$$POSITION_NODE$$",
-            &polygon_to_position_node(poly),
-        )
+        .replace("$$POSITION_NODE$$", position_node)
         .replace("$$EXTRA_ATTRIBUTE$$", &attribute.join("\r\n"))
 }
+
+#[cfg(test)]
+fn test_objekt(member_type: &str, dient_zur_darstellung_von: Option<&str>, punkt: Option<SvgPoint>, poly: Vec<SvgPolygonInner>) -> MemberObject {
+    MemberObject {
+        member_type: member_type.to_string(),
+        beginnt: chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap(),
+        dient_zur_darstellung_von: dient_zur_darstellung_von.map(String::from),
+        ist_bestandteil_von: None,
+        hat: None,
+        ist_teil_von: None,
+        extra_attribute: BTreeMap::new(),
+        poly,
+        arcs: Vec::new(),
+        punkt,
+    }
+}
+
+#[test]
+fn test_praesentationsobjekte_geloeschtes_flurstueck() {
+    use crate::test_utils::test_quadrat;
+
+    let flst = test_quadrat(0.0, 0.0, 10.0);
+    let mut objects = NasXmlObjects::default();
+    objects.objects.insert("DEFLST01".to_string(), test_objekt("AX_Flurstueck", None, None, vec![flst.clone()]));
+    objects.objects.insert("DEFLST02".to_string(), test_objekt("AX_Flurstueck", None, None, vec![test_quadrat(20.0, 20.0, 10.0)]));
+    let pto = |flst: &str| test_objekt("AP_PTO", Some(flst), Some(SvgPoint { x: 5.0, y: 5.0 }), Vec::new());
+    objects.objects.insert("DEPTO001".to_string(), pto("urn:adv:oid:DEFLST01"));
+    objects.objects.insert("DEPTO002".to_string(), pto("urn:adv:oid:DEFLST02"));
+
+    let ops = vec![Operation::Delete {
+        obj_id: "DEFLST01".to_string(),
+        ebene: "AX_Flurstueck".to_string(),
+        kuerzel: String::new(),
+        poly_alt: flst,
+    }];
+    let result = get_praesentationsobjekte_operations(&ops, &objects);
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].1, OpPrereq::Delete { id: "DEPTO001".to_string() });
+    assert!(result[0].0.contains("typeName=\"AP_PTO\""));
+    assert!(result[0].0.contains("rid=\"DEPTO001"));
+}
+
+#[test]
+fn test_praesentationsobjekte_verschobene_nutzung() {
+    use crate::test_utils::test_quadrat;
+
+    let alt = test_quadrat(0.0, 0.0, 10.0);
+    let neu = test_quadrat(20.0, 20.0, 10.0);
+    let mut objects = NasXmlObjects::default();
+    objects.objects.insert("DENUTZ01".to_string(), test_objekt("AX_Wald", None, None, vec![alt.clone()]));
+    // outside of the new geometry: moved
+    objects.objects.insert(
+        "DEPPO001".to_string(),
+        test_objekt("AP_PPO", Some("#DENUTZ01"), Some(SvgPoint { x: 5.0, y: 5.0 }), Vec::new()),
+    );
+    // inside of the new geometry: stays
+    objects.objects.insert(
+        "DEPPO002".to_string(),
+        test_objekt("AP_PPO", Some("#DENUTZ01"), Some(SvgPoint { x: 25.0, y: 25.0 }), Vec::new()),
+    );
+
+    let ops = vec![Operation::Replace {
+        obj_id: "DENUTZ01".to_string(),
+        ebene: "AX_Wald".to_string(),
+        kuerzel: "WALD".to_string(),
+        poly_alt: alt,
+        poly_neu: neu.clone(),
+    }];
+    let result = get_praesentationsobjekte_operations(&ops, &objects);
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].1, OpPrereq::Replace { id: "DEPPO001".to_string() });
+    let lp = neu.get_label_pos().unwrap();
+    assert!(crate::nas::point_is_in_polygon(&lp, &neu));
+    assert!(result[0].0.contains(&format!("<gml:pos>{:.3} {:.3}</gml:pos>", lp.x, lp.y)));
+    assert!(result[0].0.contains("<AP_PPO gml:id=\"DEPPO001"));
+    assert!(result[0].0.contains("<dientZurDarstellungVon href=\"#DENUTZ01\"/>"));
+}
+
+#[test]
+fn test_antragskopf() {
+    let info = ProjektInfo {
+        antragsnr: " 2024-12 ".to_string(),
+        katasteramt: "Neuruppin".to_string(),
+        ..Default::default()
+    };
+    let datum = chrono::DateTime::parse_from_rfc3339("2024-05-06T10:00:00+02:00").unwrap();
+    let crs = crate::nas::default_etrs33();
+
+    let mut config = AntragskopfConfig::default();
+    let kopf = Antragskopf::new(&info, &config, &crs, &datum).unwrap();
+    assert_eq!(kopf.antragsnummer, "73_0073_20240506_999");
+    assert_eq!(kopf.auftragsnummer, kopf.antragsnummer);
+    assert_eq!(kopf.crs, "ETRS89_UTM33");
+
+    config.auftragsnummer_schema = "{katasteramt}-{antragsnr}".to_string();
+    config.implizite_loeschung_der_reservierung = "3000".to_string();
+    config.crs = "urn:adv:crs:ETRS89_UTM32".to_string();
+    let kopf = Antragskopf::new(&info, &config, &crs, &datum).unwrap();
+    assert_eq!(kopf.antragsnummer, "73_0073_20240506_999");
+    assert_eq!(kopf.auftragsnummer, "Neuruppin-2024-12");
+    assert_eq!(kopf.crs, "ETRS89_UTM32");
+
+    let xml = operations_to_xml_file(&[], &NasXmlObjects::default(), &datum, &kopf, &SignaturKatalog::default()).unwrap();
+    assert!(xml.contains("<crs xlink:href=\"urn:adv:crs:ETRS89_UTM32\"></crs>"));
+    assert!(xml.contains("<profilkennung>schuettf</profilkennung>"));
+    assert!(xml.contains("<antragsnummer>73_0073_20240506_999</antragsnummer>"));
+    assert!(xml.contains("<auftragsnummer>Neuruppin-2024-12</auftragsnummer>"));
+    assert!(xml.contains("<impliziteLoeschungDerReservierung>3000</impliziteLoeschungDerReservierung>"));
+    assert!(xml.contains("<verarbeitungsart>2000</verarbeitungsart>"));
+
+    config.oid_praefix = "XY".to_string();
+    assert!(Antragskopf::new(&info, &config, &crs, &datum).is_err());
+}
+
+#[test]
+fn test_signaturen_aus_konfiguration() {
+    use crate::test_utils::test_quadrat;
+
+    // as edited in the UI: Konfiguration JSON with changed / added entries
+    let mut konfiguration = serde_json::to_value(crate::pdf::Konfiguration::default()).unwrap();
+    konfiguration["signaturen"]["WALD"]["typ"] = "punkt".into();
+    konfiguration["signaturen"]["WALD"]["signaturnummer"] = "9999".into();
+    konfiguration["signaturen"]["WALD"]["art"] = "Test".into();
+    let konfiguration = serde_json::to_string(&konfiguration).unwrap();
+    let konfiguration = crate::edit_konfiguration_signatur_neu(konfiguration, "NEU".to_string());
+    let konfiguration = serde_json::from_str::<crate::pdf::Konfiguration>(&konfiguration).unwrap();
+    assert!(konfiguration.signaturen.0.contains_key("NEU"));
+
+    let poly = test_quadrat(0.0, 0.0, 10.0);
+    let mut oids = crate::oid::OidGenerator::with_belegt("DEBBAL01", 1, &Vec::<String>::new()).unwrap();
+    let wald = Signatur::from_kuerzel("WALD", "DEWALD01", &poly, &konfiguration.signaturen, &mut oids).unwrap();
+    match wald {
+        Signatur::Punkt { signaturnummer, art, fuer, .. } => {
+            assert_eq!(signaturnummer, "9999");
+            assert_eq!(art, "Test");
+            assert_eq!(fuer, "DEWALD01");
+        }
+        _ => panic!("WALD: Punktsignatur erwartet"),
+    }
+    assert!(Signatur::from_kuerzel("NEU", "DENEU001", &poly, &konfiguration.signaturen, &mut oids).is_none());
+}
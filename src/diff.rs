@@ -0,0 +1,475 @@
+//! Comparison of two project states (e.g. the last saved file and the current
+//! state) for the second review: which PolyNeu were added / removed / moved,
+//! which Kürzel and Gebäude changed and which Risse were edited.
+
+use crate::{
+    nas::{
+        SvgPolygonInner,
+        TaggedPolygon,
+    },
+    pdf::{
+        reproject_aenderungen_into_target_space,
+        RissConfig,
+        Risse,
+    },
+    ui::{
+        Aenderungen,
+        FlstPartId,
+        Kuerzel,
+        NewPolyId,
+    },
+    xml_templates::{
+        AenderungsberichtInfo,
+        AenderungsberichtZeile,
+    },
+    SaveFile,
+};
+use serde_derive::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffArt {
+    Hinzugefuegt,
+    Entfernt,
+    /// Geometry changed
+    Verschoben,
+    /// Only attributes changed (Nutzung, Kürzel, gesperrt, Maßstab, ...)
+    Geaendert,
+}
+
+impl DiffArt {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            DiffArt::Hinzugefuegt => "hinzugefügt",
+            DiffArt::Entfernt => "entfernt",
+            DiffArt::Verschoben => "verschoben",
+            DiffArt::Geaendert => "geändert",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolyNeuDiff {
+    pub id: NewPolyId,
+    pub art: DiffArt,
+    pub nutzung_alt: Option<Kuerzel>,
+    pub nutzung_neu: Option<Kuerzel>,
+    pub flaeche_alt_m2: f64,
+    pub flaeche_neu_m2: f64,
+    pub flaeche_delta_m2: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poly_alt: Option<SvgPolygonInner>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poly_neu: Option<SvgPolygonInner>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NaDefiniertDiff {
+    pub id: FlstPartId,
+    pub art: DiffArt,
+    pub alt: Option<Kuerzel>,
+    pub neu: Option<Kuerzel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GebaeudeLoeschenDiff {
+    pub gebaeude_id: String,
+    pub art: DiffArt,
+    pub flst_id: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RissDiff {
+    pub id: String,
+    pub art: DiffArt,
+    /// Names of the changed fields, e.g. ["Maßstab", "Rissgebiet"]
+    pub felder: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rissgebiet_alt: Option<SvgPolygonInner>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rissgebiet_neu: Option<SvgPolygonInner>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AenderungenDiff {
+    pub na_polygone_neu: Vec<PolyNeuDiff>,
+    pub na_definiert: Vec<NaDefiniertDiff>,
+    pub gebaeude_loeschen: Vec<GebaeudeLoeschenDiff>,
+    pub risse: Vec<RissDiff>,
+}
+
+impl AenderungenDiff {
+    pub fn is_empty(&self) -> bool {
+        self.na_polygone_neu.is_empty()
+            && self.na_definiert.is_empty()
+            && self.gebaeude_loeschen.is_empty()
+            && self.risse.is_empty()
+    }
+}
+
+fn flaeche(p: Option<&SvgPolygonInner>) -> f64 {
+    p.map(|p| p.area_m2().abs()).unwrap_or(0.0)
+}
+
+/// Both `Aenderungen` have to be in the same (projected) CRS for the areas to be correct
+pub fn diff_aenderungen(alt: &Aenderungen, neu: &Aenderungen) -> AenderungenDiff {
+    let mut na_polygone_neu = Vec::new();
+    let ids = alt
+        .na_polygone_neu
+        .keys()
+        .chain(neu.na_polygone_neu.keys())
+        .collect::<BTreeSet<_>>();
+    for id in ids {
+        let a = alt.na_polygone_neu.get(id);
+        let n = neu.na_polygone_neu.get(id);
+        let poly_alt = a.map(|p| p.poly.get_inner());
+        let poly_neu = n.map(|p| p.poly.get_inner());
+        let art = match (a, n) {
+            (None, None) => continue,
+            (None, Some(_)) => DiffArt::Hinzugefuegt,
+            (Some(_), None) => DiffArt::Entfernt,
+            (Some(a), Some(n)) => {
+                let geometrie_gleich = poly_alt
+                    .as_ref()
+                    .zip(poly_neu.as_ref())
+                    .map(|(pa, pn)| pa.equals(pn))
+                    .unwrap_or(false);
+                if !geometrie_gleich {
+                    DiffArt::Verschoben
+                } else if a.nutzung != n.nutzung || a.locked != n.locked {
+                    DiffArt::Geaendert
+                } else {
+                    continue;
+                }
+            }
+        };
+        let flaeche_alt_m2 = flaeche(poly_alt.as_ref());
+        let flaeche_neu_m2 = flaeche(poly_neu.as_ref());
+        na_polygone_neu.push(PolyNeuDiff {
+            id: id.clone(),
+            art,
+            nutzung_alt: a.and_then(|p| p.nutzung.clone()),
+            nutzung_neu: n.and_then(|p| p.nutzung.clone()),
+            flaeche_alt_m2,
+            flaeche_neu_m2,
+            flaeche_delta_m2: flaeche_neu_m2 - flaeche_alt_m2,
+            poly_alt,
+            poly_neu,
+        });
+    }
+
+    let mut na_definiert = Vec::new();
+    let ids = alt
+        .na_definiert
+        .keys()
+        .chain(neu.na_definiert.keys())
+        .collect::<BTreeSet<_>>();
+    for id in ids {
+        let a = alt.na_definiert.get(id);
+        let n = neu.na_definiert.get(id);
+        let art = match (a, n) {
+            (None, Some(_)) => DiffArt::Hinzugefuegt,
+            (Some(_), None) => DiffArt::Entfernt,
+            (Some(a), Some(n)) if a != n => DiffArt::Geaendert,
+            _ => continue,
+        };
+        na_definiert.push(NaDefiniertDiff {
+            id: id.clone(),
+            art,
+            alt: a.cloned(),
+            neu: n.cloned(),
+        });
+    }
+
+    // keys of gebaeude_loeschen are generated in the UI ("g_<timestamp>"),
+    // so compare by gebaeude_id
+    let gebaeude_alt = alt
+        .gebaeude_loeschen
+        .values()
+        .map(|g| (g.gebaeude_id.clone(), g))
+        .collect::<BTreeMap<_, _>>();
+    let gebaeude_neu = neu
+        .gebaeude_loeschen
+        .values()
+        .map(|g| (g.gebaeude_id.clone(), g))
+        .collect::<BTreeMap<_, _>>();
+    let mut gebaeude_loeschen = Vec::new();
+    for (id, g) in gebaeude_neu.iter() {
+        if !gebaeude_alt.contains_key(id) {
+            gebaeude_loeschen.push(GebaeudeLoeschenDiff {
+                gebaeude_id: id.clone(),
+                art: DiffArt::Hinzugefuegt,
+                flst_id: g.flst_id.clone(),
+            });
+        }
+    }
+    for (id, g) in gebaeude_alt.iter() {
+        if !gebaeude_neu.contains_key(id) {
+            gebaeude_loeschen.push(GebaeudeLoeschenDiff {
+                gebaeude_id: id.clone(),
+                art: DiffArt::Entfernt,
+                flst_id: g.flst_id.clone(),
+            });
+        }
+    }
+
+    AenderungenDiff {
+        na_polygone_neu,
+        na_definiert,
+        gebaeude_loeschen,
+        risse: Vec::new(),
+    }
+}
+
+fn get_riss_felder(a: &RissConfig, n: &RissConfig) -> Vec<String> {
+    let mut felder = Vec::new();
+    if a.lat != n.lat || a.lon != n.lon {
+        felder.push("Position".to_string());
+    }
+    if a.width_mm != n.width_mm || a.height_mm != n.height_mm {
+        felder.push("Format".to_string());
+    }
+    if a.scale != n.scale {
+        felder.push("Maßstab".to_string());
+    }
+    if a.crs != n.crs {
+        felder.push("CRS".to_string());
+    }
+    let rg_a = a.rissgebiet.as_ref().map(|s| s.get_inner());
+    let rg_n = n.rissgebiet.as_ref().map(|s| s.get_inner());
+    let rg_gleich = match (rg_a.as_ref(), rg_n.as_ref()) {
+        (None, None) => true,
+        (Some(a), Some(n)) => a.equals(n),
+        _ => false,
+    };
+    if !rg_gleich {
+        felder.push("Rissgebiet".to_string());
+    }
+    if a.beschriftungen != n.beschriftungen {
+        felder.push("Beschriftungen".to_string());
+    }
+    felder
+}
+
+pub fn diff_risse(alt: &Risse, neu: &Risse) -> Vec<RissDiff> {
+    let ids = alt.keys().chain(neu.keys()).collect::<BTreeSet<_>>();
+    let mut v = Vec::new();
+    for id in ids {
+        let a = alt.get(id);
+        let n = neu.get(id);
+        let (art, felder) = match (a, n) {
+            (None, Some(_)) => (DiffArt::Hinzugefuegt, Vec::new()),
+            (Some(_), None) => (DiffArt::Entfernt, Vec::new()),
+            (Some(a), Some(n)) => {
+                let felder = get_riss_felder(a, n);
+                if felder.is_empty() {
+                    continue;
+                }
+                let art = if felder.iter().any(|f| f == "Position" || f == "Rissgebiet") {
+                    DiffArt::Verschoben
+                } else {
+                    DiffArt::Geaendert
+                };
+                (art, felder)
+            }
+            (None, None) => continue,
+        };
+        v.push(RissDiff {
+            id: id.clone(),
+            art,
+            felder,
+            rissgebiet_alt: a.and_then(|r| r.rissgebiet.as_ref()).map(|s| s.get_inner()),
+            rissgebiet_neu: n.and_then(|r| r.rissgebiet.as_ref()).map(|s| s.get_inner()),
+        });
+    }
+    v
+}
+
+/// Compares two savefiles (as loaded from disk or as sent from the UI),
+/// the Änderungen are compared in `target_crs`
+pub fn diff_savefiles(
+    alt: &SaveFile,
+    neu: &SaveFile,
+    target_crs: &str,
+) -> Result<AenderungenDiff, String> {
+    if let (Some(a), Some(n)) = (alt.crs.as_deref(), neu.crs.as_deref()) {
+        if a.trim() != n.trim() {
+            return Err(format!(
+                "Projektdateien haben unterschiedliche Koordinatensysteme: {a:?} / {n:?}"
+            ));
+        }
+    }
+    let alt = alt.migrate_old();
+    let neu = neu.migrate_old();
+    let aenderungen_alt = reproject_aenderungen_into_target_space(&alt.aenderungen, target_crs)?;
+    let aenderungen_neu = reproject_aenderungen_into_target_space(&neu.aenderungen, target_crs)?;
+    let mut diff = diff_aenderungen(&aenderungen_alt, &aenderungen_neu);
+    diff.risse = diff_risse(&alt.risse, &neu.risse);
+    Ok(diff)
+}
+
+/// Overlay for the map: one feature per changed polygon, old and new geometry
+/// as separate features (property "seite" = "alt" / "neu")
+pub fn diff_to_geojson(diff: &AenderungenDiff, crs: &str) -> String {
+    let mut polys = Vec::new();
+    let mut push = |poly: &Option<SvgPolygonInner>, seite: &str, attr: Vec<(&str, String)>| {
+        let poly = match poly {
+            Some(s) => s.clone(),
+            None => return,
+        };
+        let mut attributes = attr
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<BTreeMap<_, _>>();
+        attributes.insert("seite".to_string(), seite.to_string());
+        polys.push(TaggedPolygon {
+            poly: crate::reproject_poly_back_into_latlon(poly, crs),
+            attributes,
+        });
+    };
+
+    for p in diff.na_polygone_neu.iter() {
+        let attr = || {
+            vec![
+                ("typ", "PolyNeu".to_string()),
+                ("id", p.id.clone()),
+                ("art", p.art.get_name().to_string()),
+                ("nutzung_alt", p.nutzung_alt.clone().unwrap_or_default()),
+                ("nutzung_neu", p.nutzung_neu.clone().unwrap_or_default()),
+                ("flaeche_delta_m2", format!("{:.2}", p.flaeche_delta_m2)),
+            ]
+        };
+        push(&p.poly_alt, "alt", attr());
+        push(&p.poly_neu, "neu", attr());
+    }
+
+    for r in diff.risse.iter() {
+        let attr = || {
+            vec![
+                ("typ", "Riss".to_string()),
+                ("id", r.id.clone()),
+                ("art", r.art.get_name().to_string()),
+                ("felder", r.felder.join(", ")),
+            ]
+        };
+        push(&r.rissgebiet_alt, "alt", attr());
+        push(&r.rissgebiet_neu, "neu", attr());
+    }
+
+    crate::nas::tagged_polys_to_featurecollection(&polys)
+}
+
+pub fn diff_to_xlsx(diff: &AenderungenDiff, antragsnr: &str) -> Vec<u8> {
+    let mut zeilen = Vec::new();
+
+    for p in diff.na_polygone_neu.iter() {
+        zeilen.push(AenderungsberichtZeile {
+            bereich: "Nutzung neu".to_string(),
+            art: p.art.get_name().to_string(),
+            id: p.id.clone(),
+            alt: p.nutzung_alt.clone().unwrap_or_default(),
+            neu: p.nutzung_neu.clone().unwrap_or_default(),
+            flaeche_alt_m2: p.poly_alt.as_ref().map(|_| p.flaeche_alt_m2),
+            flaeche_neu_m2: p.poly_neu.as_ref().map(|_| p.flaeche_neu_m2),
+            flaeche_delta_m2: Some(p.flaeche_delta_m2),
+        });
+    }
+
+    for n in diff.na_definiert.iter() {
+        zeilen.push(AenderungsberichtZeile {
+            bereich: "Nutzungsart".to_string(),
+            art: n.art.get_name().to_string(),
+            id: n.id.clone(),
+            alt: n.alt.clone().unwrap_or_default(),
+            neu: n.neu.clone().unwrap_or_default(),
+            ..Default::default()
+        });
+    }
+
+    for g in diff.gebaeude_loeschen.iter() {
+        let (alt, neu) = match g.art {
+            DiffArt::Hinzugefuegt => ("", "löschen"),
+            _ => ("löschen", ""),
+        };
+        zeilen.push(AenderungsberichtZeile {
+            bereich: "Gebäude".to_string(),
+            art: g.art.get_name().to_string(),
+            id: g.gebaeude_id.clone(),
+            alt: alt.to_string(),
+            neu: neu.to_string(),
+            ..Default::default()
+        });
+    }
+
+    for r in diff.risse.iter() {
+        zeilen.push(AenderungsberichtZeile {
+            bereich: "Riss".to_string(),
+            art: r.art.get_name().to_string(),
+            id: r.id.clone(),
+            neu: r.felder.join(", "),
+            ..Default::default()
+        });
+    }
+
+    crate::xml_templates::generate_aenderungsbericht_xlsx(&AenderungsberichtInfo {
+        header: format!("Änderungen seit letzter Version: {antragsnr}"),
+        zeilen,
+    })
+}
+
+#[test]
+fn test_diff_aenderungen() {
+    use crate::{
+        test_utils::test_poly_neu,
+        ui::GebaeudeLoeschen,
+    };
+
+    let wald = |x: f64, groesse: f64| test_poly_neu(x, groesse, Some("WALD"));
+
+    let mut alt = Aenderungen::default();
+    alt.na_polygone_neu.insert("a".to_string(), wald(0.0, 10.0));
+    alt.na_polygone_neu.insert("b".to_string(), wald(100.0, 10.0));
+    alt.na_polygone_neu.insert("c".to_string(), wald(200.0, 10.0));
+    alt.na_definiert.insert("1-1".to_string(), "GR".to_string());
+    alt.gebaeude_loeschen.insert(
+        "g_1".to_string(),
+        GebaeudeLoeschen {
+            gebaeude_id: "DE_1".to_string(),
+            flst_id: Vec::new(),
+        },
+    );
+
+    let mut neu = alt.clone();
+    neu.na_polygone_neu.remove("a");
+    neu.na_polygone_neu.insert("b".to_string(), wald(100.0, 20.0));
+    neu.na_polygone_neu.insert("d".to_string(), wald(300.0, 10.0));
+    neu.na_definiert.insert("1-1".to_string(), "WALD".to_string());
+    // same Gebäude under a different key: no change
+    let g = neu.gebaeude_loeschen.remove("g_1").unwrap();
+    neu.gebaeude_loeschen.insert("g_2".to_string(), g);
+
+    let diff = diff_aenderungen(&alt, &neu);
+    let arten = diff
+        .na_polygone_neu
+        .iter()
+        .map(|p| (p.id.as_str(), p.art))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        arten,
+        vec![
+            ("a", DiffArt::Entfernt),
+            ("b", DiffArt::Verschoben),
+            ("d", DiffArt::Hinzugefuegt),
+        ]
+    );
+    assert!((diff.na_polygone_neu[1].flaeche_delta_m2 - 300.0).abs() < 0.01);
+    assert_eq!(diff.na_definiert.len(), 1);
+    assert_eq!(diff.na_definiert[0].art, DiffArt::Geaendert);
+    assert!(diff.gebaeude_loeschen.is_empty());
+}
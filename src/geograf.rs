@@ -1,8 +1,10 @@
 use crate::{
+    anschlussrisse::AnschlussrissMarker,
     csv::{
         CsvDataType,
         Status,
     },
+    crs::CrsInfo,
     nas::{
         self,
         NasXMLFile,
@@ -11,7 +13,7 @@ use crate::{
         SvgPoint,
         SvgPolygonInner, TaggedPolygon,
     },
-    optimize::{OptimizeConfig, OptimizedTextPlacement},
+    optimize::{OptimizeConfig, OptimizeResult, OptimizedTextPlacement},
     pdf::{
         get_fluren, get_flurstuecke, get_gebaeude, get_mini_nas_xml, reproject_aenderungen_into_target_space, HintergrundCache, Konfiguration, ProjektInfo, RissConfig, RissExtentReprojected, Risse
     },
@@ -30,6 +32,8 @@ use crate::{
     xml_templates::{
         AntragsbegleitblattInfo,
         BearbeitungslisteInfo,
+        BenachrichtigungZeile,
+        EigentuemerbenachrichtigungInfo,
         FortfuehrungsbelegInfo,
     },
     zip::write_files_to_zip,
@@ -57,22 +61,17 @@ use std::{
     path::PathBuf,
 };
 
-fn update_dxf_x(zone: usize, pos: f64) -> f64 {
-    format!("{zone}{pos}").parse().unwrap_or_default()
-}
-
 /// Returns the dxf bytes
-pub fn texte_zu_dxf_datei(texte: &[TextPlacement]) -> Vec<u8> {
+pub fn texte_zu_dxf_datei(texte: &[TextPlacement], crs: &CrsInfo) -> Vec<u8> {
     use dxf::{
         entities::*,
         Drawing,
     };
 
     let mut drawing = Drawing::new();
-    let zone = 33;
 
     for text in texte {
-        let newx = update_dxf_x(zone, text.pos.x);
+        let newx = crs.dxf_x(text.pos.x);
         let location = dxf::Point {
             x: newx,
             y: text.pos.y,
@@ -120,7 +119,7 @@ pub fn lines_to_points(lines: &[SvgLine]) -> Vec<(SvgPoint, AngleDegrees)> {
     crate::process::generate_points_along_lines(config, &lines_joined)
 }
 
-pub fn lines_to_points_dxf(lines: &[SvgLine]) -> Vec<u8> {
+pub fn lines_to_points_dxf(lines: &[SvgLine], crs: &CrsInfo) -> Vec<u8> {
     use dxf::{
         entities::*,
         Drawing,
@@ -129,10 +128,9 @@ pub fn lines_to_points_dxf(lines: &[SvgLine]) -> Vec<u8> {
     let points = lines_to_points(lines);
 
     let mut drawing = Drawing::new();
-    let zone = 33;
 
     for (location, angle) in points.iter() {
-        let newx = update_dxf_x(zone, location.x);
+        let newx = crs.dxf_x(location.x);
         let entity = Entity::new(EntityType::ModelPoint(ModelPoint {
             location: dxf::Point {
                 x: newx,
@@ -151,20 +149,19 @@ pub fn lines_to_points_dxf(lines: &[SvgLine]) -> Vec<u8> {
     buf.into_inner().unwrap_or_default()
 }
 
-pub fn lines_to_dxf(lines: &[SvgLine]) -> Vec<u8> {
+pub fn lines_to_dxf(lines: &[SvgLine], crs: &CrsInfo) -> Vec<u8> {
     use dxf::{
         entities::*,
         Drawing,
     };
 
     let mut drawing = Drawing::new();
-    let zone = 33;
 
     for l in lines.iter() {
         let entity = Entity::new(EntityType::Polyline({
             let mut m = Polyline::default();
             for pos in l.points.iter() {
-                let newx = update_dxf_x(zone, pos.x);
+                let newx = crs.dxf_x(pos.x);
                 let location = dxf::Point {
                     x: newx,
                     y: pos.y,
@@ -191,7 +188,7 @@ pub struct ShpReturn {
     pub cpg: Vec<u8>,
 }
 
-pub fn lines_to_shp(lines: &[SvgLine]) -> ShpReturn {
+pub fn lines_to_shp(lines: &[SvgLine], crs: &CrsInfo) -> ShpReturn {
     use shapefile::{
         dbase::Record,
         record::polyline::Polyline,
@@ -228,8 +225,7 @@ pub fn lines_to_shp(lines: &[SvgLine]) -> ShpReturn {
 
     let _ = shape_writer.write_shapes_and_records(shapes_ref.into_iter());
 
-    // https://www.geoportal-mv.de/portal/downloads/prj/25833.prj
-    let prj = include_str!("./25833.prj");
+    let prj = &crs.prj_wkt;
 
     ShpReturn {
         shp: shp_dest.into_inner(),
@@ -240,6 +236,185 @@ pub fn lines_to_shp(lines: &[SvgLine]) -> ShpReturn {
     }
 }
 
+/// Attributes of a Splitfläche for the SHP / GeoJSON export
+struct SplitflaecheAttribute {
+    alt: String,
+    neu: String,
+    flst_id: String,
+    flst_id_part: String,
+    flaeche_m2: f64,
+    wia_alt: String,
+    wia_neu: String,
+    status: &'static str,
+    gebaeude: bool,
+}
+
+fn get_splitflaechen_attribute(
+    splitflaechen: &[AenderungenIntersection],
+    gebaeude_flst: &[FlstIdParsedNumber],
+) -> Vec<SplitflaecheAttribute> {
+    let mut status_cache = BTreeMap::new();
+    splitflaechen
+        .iter()
+        .map(|sf| {
+            let status = *status_cache.entry(sf.flst_id.clone()).or_insert_with(|| {
+                AenderungenIntersection::get_auto_status(splitflaechen, gebaeude_flst, &sf.flst_id)
+            });
+            let (status, gebaeude) = match status {
+                Status::Bleibt(g) => ("Bleibt", g),
+                Status::AenderungKeineBenachrichtigung(g) => ("AenderungKeineBenachrichtigung", g),
+                Status::AenderungMitBenachrichtigung(g) => ("AenderungMitBenachrichtigung", g),
+            };
+            SplitflaecheAttribute {
+                alt: sf.alt.clone(),
+                neu: sf.neu.clone(),
+                flst_id: sf.flst_id.clone(),
+                flst_id_part: sf.flst_id_part.clone(),
+                flaeche_m2: (sf.poly_cut.area_m2().abs() * 100.0).round() / 100.0,
+                wia_alt: TaggedPolygon::get_wirtschaftsart(&sf.alt).unwrap_or_default(),
+                wia_neu: TaggedPolygon::get_wirtschaftsart(&sf.neu).unwrap_or_default(),
+                status,
+                gebaeude,
+            }
+        })
+        .collect()
+}
+
+/// Splitflächen as polygon Shapefile (in the project CRS) for QA in QGIS
+pub fn splitflaechen_to_shp(
+    splitflaechen: &[AenderungenIntersection],
+    gebaeude_flst: &[FlstIdParsedNumber],
+    crs: &CrsInfo,
+) -> ShpReturn {
+    use dbase::{
+        FieldName,
+        FieldValue,
+    };
+    use shapefile::{
+        dbase::Record,
+        Point,
+        Polygon,
+        PolygonRing,
+    };
+    use std::io::Cursor;
+
+    let mut shp_dest = Cursor::new(Vec::<u8>::new());
+    let mut shx_dest = Cursor::new(Vec::<u8>::new());
+    let mut dbf_dest = Cursor::new(Vec::<u8>::new());
+
+    let shape_writer = shapefile::ShapeWriter::with_shx(&mut shp_dest, &mut shx_dest);
+
+    let field = |s: &str| FieldName::try_from(s).expect("DBF-Feldname mit max. 10 ASCII-Zeichen");
+    let dbase_writer = dbase::TableWriterBuilder::new()
+        .add_character_field(field("ALT"), 20)
+        .add_character_field(field("NEU"), 20)
+        .add_character_field(field("FLST_ID"), 50)
+        .add_character_field(field("FLST_PART"), 100)
+        .add_numeric_field(field("FLAECHE_M2"), 15, 2)
+        .add_character_field(field("WIA_ALT"), 10)
+        .add_character_field(field("WIA_NEU"), 10)
+        .add_character_field(field("STATUS"), 40)
+        .add_logical_field(field("GEBAEUDE"))
+        .build_with_dest(&mut dbf_dest);
+
+    let shape_writer = shapefile::Writer::new(shape_writer, dbase_writer);
+
+    let attribute = get_splitflaechen_attribute(splitflaechen, gebaeude_flst);
+    let ring = |l: &SvgLine| l.points.iter().map(|p| Point::new(p.x, p.y)).collect::<Vec<_>>();
+
+    let mut shapes = Vec::new();
+    for (sf, a) in splitflaechen.iter().zip(attribute.into_iter()) {
+        if sf.poly_cut.outer_ring.points.is_empty() {
+            continue;
+        }
+
+        let mut rings = vec![PolygonRing::Outer(ring(&sf.poly_cut.outer_ring))];
+        rings.extend(sf.poly_cut.inner_rings.iter().map(|l| PolygonRing::Inner(ring(l))));
+
+        let mut record = Record::default();
+        record.insert("ALT".to_string(), FieldValue::Character(Some(a.alt)));
+        record.insert("NEU".to_string(), FieldValue::Character(Some(a.neu)));
+        record.insert("FLST_ID".to_string(), FieldValue::Character(Some(a.flst_id)));
+        record.insert("FLST_PART".to_string(), FieldValue::Character(Some(a.flst_id_part)));
+        record.insert("FLAECHE_M2".to_string(), FieldValue::Numeric(Some(a.flaeche_m2)));
+        record.insert("WIA_ALT".to_string(), FieldValue::Character(Some(a.wia_alt)));
+        record.insert("WIA_NEU".to_string(), FieldValue::Character(Some(a.wia_neu)));
+        record.insert("STATUS".to_string(), FieldValue::Character(Some(a.status.to_string())));
+        record.insert("GEBAEUDE".to_string(), FieldValue::Logical(Some(a.gebaeude)));
+
+        shapes.push((Polygon::with_rings(rings), record));
+    }
+
+    let shapes_ref = shapes.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+
+    let _ = shape_writer.write_shapes_and_records(shapes_ref.into_iter());
+
+    ShpReturn {
+        shp: shp_dest.into_inner(),
+        shx: shx_dest.into_inner(),
+        dbf: dbf_dest.into_inner(),
+        prj: crs.prj_wkt.as_bytes().to_vec(),
+        cpg: "UTF-8".as_bytes().to_vec(),
+    }
+}
+
+/// Splitflächen as GeoJSON FeatureCollection (WGS84, RFC 7946: exterior
+/// rings counterclockwise, holes clockwise)
+pub fn splitflaechen_to_geojson(
+    splitflaechen: &[AenderungenIntersection],
+    gebaeude_flst: &[FlstIdParsedNumber],
+    crs: &str,
+) -> String {
+    let attribute = get_splitflaechen_attribute(splitflaechen, gebaeude_flst);
+    let ring = |l: &SvgLine, ccw: bool| {
+        let mut r = l.points.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>();
+        let doppelte_flaeche = r
+            .iter()
+            .zip(r.iter().cycle().skip(1))
+            .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+            .sum::<f64>();
+        if (doppelte_flaeche > 0.0) != ccw {
+            r.reverse();
+        }
+        r
+    };
+    let features = splitflaechen
+        .iter()
+        .zip(attribute.into_iter())
+        .filter(|(sf, _)| !sf.poly_cut.outer_ring.points.is_empty())
+        .map(|(sf, a)| {
+            let poly = crate::reproject_poly_back_into_latlon(sf.poly_cut.clone(), crs);
+            let rings = std::iter::once(ring(&poly.outer_ring, true))
+                .chain(poly.inner_rings.iter().map(|l| ring(l, false)))
+                .collect::<Vec<_>>();
+            serde_json::json!({
+                "type": "Feature",
+                "properties": {
+                    "alt": a.alt,
+                    "neu": a.neu,
+                    "flst_id": a.flst_id,
+                    "flst_id_part": a.flst_id_part,
+                    "flaeche_m2": a.flaeche_m2,
+                    "wia_alt": a.wia_alt,
+                    "wia_neu": a.wia_neu,
+                    "status": a.status,
+                    "gebaeude": a.gebaeude,
+                },
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": rings,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string(&serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+    .unwrap_or_default()
+}
+
 pub async fn export_aenderungen_geograf(
     split_nas: &SplitNasXml, // original projection
     nas_xml: &NasXMLFile,    // original projection
@@ -252,6 +427,7 @@ pub async fn export_aenderungen_geograf(
     use_dgm: bool,
 ) -> Vec<u8> {
     let mut files = Vec::new();
+    let crs = crate::crs::get_crs_by_proj_or_default(&split_nas.crs);
 
     let aenderungen = match reproject_aenderungen_into_target_space(&aenderungen, &split_nas.crs) {
         Ok(o) => o,
@@ -295,13 +471,40 @@ pub async fn export_aenderungen_geograf(
             &mut files,
             &format!("Riss{i}"),
             Some("Plotboxen".to_string()),
-            lines_to_shp(&[line]),
+            lines_to_shp(&[line], &crs),
         );
     }
 
     log_status("Berechne Splitflächen...");
     let splitflaechen = calc_splitflaechen(&aenderungen, split_nas, nas_xml, &csv_data);
     log_status(&format!("OK: {} Splitflächen", splitflaechen.0.len()));
+
+    append_shp(
+        &mut files,
+        "Splitflaechen",
+        Some("Splitflaechen".to_string()),
+        splitflaechen_to_shp(&splitflaechen.0, &gebaeude_flst, &crs),
+    );
+    files.push((
+        Some("Splitflaechen".to_string()),
+        "Splitflaechen.geojson".into(),
+        splitflaechen_to_geojson(&splitflaechen.0, &gebaeude_flst, &split_nas.crs).into_bytes(),
+    ));
+
+    let mut pruefprotokoll = crate::pruefung::pruefe_aenderungen(
+        &aenderungen,
+        nas_xml,
+        &splitflaechen,
+        csv_data,
+        &konfiguration.pruefung,
+    );
+    crate::pruefung::pruefe_beschriftungen(risse, &splitflaechen, &mut pruefprotokoll);
+    log_status(&pruefprotokoll.get_zusammenfassung());
+    files.push((
+        None,
+        format!("{antragsnr}.Pruefprotokoll.txt").into(),
+        pruefprotokoll.to_text().as_bytes().to_vec(),
+    ));
     let main_gemarkung = crate::get_main_gemarkung(&csv_data);
     let eigentuemer_map = splitflaechen_eigentuemer_map(&csv_data, &splitflaechen, &gebaeude_flst);
     let splitflaechen_xlsx =
@@ -369,6 +572,27 @@ pub async fn export_aenderungen_geograf(
     ));
     log_status(&format!("OK: Fortführungsbeleg erstellt"));
 
+    let benachrichtigungen =
+        get_eigentuemerbenachrichtigungen(&eigentuemer_map_modified, &splitflaechen.0, projekt_info);
+    let mut briefe = Vec::new();
+    for (i, b) in benachrichtigungen.into_iter().enumerate() {
+        let datei = get_eigentuemerbenachrichtigung_dateiname(i + 1, &b);
+        files.push((
+            Some("Eigentuemerbenachrichtigungen".to_string()),
+            datei.clone().into(),
+            crate::xml_templates::generate_eigentuemerbenachrichtigung_docx(&b),
+        ));
+        briefe.push((datei, b));
+    }
+    if !briefe.is_empty() {
+        files.push((
+            Some("Eigentuemerbenachrichtigungen".to_string()),
+            "Briefe.csv".into(),
+            eigentuemerbenachrichtigungen_to_csv(&briefe).into_bytes(),
+        ));
+    }
+    log_status(&format!("OK: {} Eigentümerbenachrichtigungen erstellt", briefe.len()));
+
     let lq_flurstuecke = nas_xml.get_linien_quadtree();
     let lq_flurstuecke_und_nutzungsarten = split_nas.get_linien_quadtree();
 
@@ -427,10 +651,12 @@ pub async fn export_aenderungen_geograf(
             &lq_flurstuecke,
             &lq_flurstuecke_und_nutzungsarten,
             &mut hintergrund_cache,
+            &[],
         ) {
             grafbat_map.insert(id, s);
         }
     } else {
+        let anschlussrisse = crate::anschlussrisse::get_anschlussrisse(risse, &split_nas.crs);
         for (i, (_, r)) in risse.iter().enumerate() {
             if let Ok((id, s)) = export_splitflaechen(
                 &mut files,
@@ -448,6 +674,7 @@ pub async fn export_aenderungen_geograf(
                 &lq_flurstuecke,
                 &lq_flurstuecke_und_nutzungsarten,
                 &mut hintergrund_cache,
+                anschlussrisse.get(i).map(|a| a.as_slice()).unwrap_or(&[]),
             ) {
                 grafbat_map.insert(id, s);
             }
@@ -585,7 +812,13 @@ impl EigentuemerClean {
             .split(",")
             .map(|q| q.trim().to_string())
             .collect::<Vec<_>>();
-        if comma_split.iter().any(|s| s.contains("Herr")) {
+        // only the explicit markers, "Herrmann" or "Frauke" are names
+        let hat_marker = |marker: &str| {
+            comma_split
+                .iter()
+                .any(|s| s.split_whitespace().any(|w| w == marker))
+        };
+        if hat_marker("(Herr)") {
             let vorname = comma_split
                 .get(1)
                 .map(|s| s.replace("(Herr)", "").trim().to_string())
@@ -595,7 +828,7 @@ impl EigentuemerClean {
                 .map(|s| s.replace("(Herr)", "").trim().to_string())
                 .unwrap_or_default();
             Self::Herr { vorname, nachname }
-        } else if comma_split.iter().any(|s| s.contains("Frau")) {
+        } else if hat_marker("(Frau)") {
             let vorname = comma_split
                 .get(1)
                 .map(|s| s.replace("(Frau)", "").trim().to_string())
@@ -604,7 +837,7 @@ impl EigentuemerClean {
                 .get(0)
                 .map(|s| s.replace("(Frau)", "").trim().to_string())
                 .unwrap_or_default();
-            Self::Herr { vorname, nachname }
+            Self::Frau { vorname, nachname }
         } else if comma_split.iter().any(|s| s.contains("Firma")) {
             Self::Firma {
                 name: s.replace("(Firma)", "").trim().to_string(),
@@ -718,6 +951,116 @@ pub fn get_eigentuemer(
     target2
 }
 
+/// One Eigentümerbenachrichtigung per owner of a Flurstück with status
+/// "AenderungMitBenachrichtigung", sorted like the Antragsbegleitblatt
+pub fn get_eigentuemerbenachrichtigungen(
+    eigentuemer_map: &BTreeMap<FlstIdParsedNumber, FlstEigentuemer>,
+    splitflaechen: &[AenderungenIntersection],
+    projekt_info: &ProjektInfo,
+) -> Vec<EigentuemerbenachrichtigungInfo> {
+    let katalog = crate::get_nutzungsartenkatalog();
+    let nutzung = |k: &str| match katalog.get(k) {
+        Some(s) => format!("{k} ({})", s.bez),
+        None => k.to_string(),
+    };
+
+    let mut target = BTreeMap::new();
+    for (k, v) in eigentuemer_map.iter() {
+        if !matches!(v.status, Status::AenderungMitBenachrichtigung(_)) {
+            continue;
+        }
+        let zeilen = AenderungenIntersection::get_splitflaechen_fuer_flst_veraendert(
+            splitflaechen,
+            &k.format_nice(),
+        )
+        .iter()
+        .map(|sf| BenachrichtigungZeile {
+            alt: nutzung(&sf.alt),
+            wia_alt: TaggedPolygon::get_wirtschaftsart(&sf.alt).unwrap_or_default(),
+            neu: nutzung(&sf.neu),
+            wia_neu: TaggedPolygon::get_wirtschaftsart(&sf.neu).unwrap_or_default(),
+            flaeche_m2: sf.poly_cut.area_m2().abs(),
+        })
+        .collect::<Vec<_>>();
+        if zeilen.is_empty() {
+            continue;
+        }
+        let flst = match k.flst_nenner {
+            Some(n) => format!("Fl. {}, {}/{n}", k.flur, k.flst_zaehler),
+            None => format!("Fl. {}, {}", k.flur, k.flst_zaehler),
+        };
+        for e in v.eigentuemer.iter() {
+            target
+                .entry(e.clone())
+                .or_insert_with(|| Vec::new())
+                .push((flst.clone(), zeilen.clone()));
+        }
+    }
+
+    target
+        .into_iter()
+        .map(|(eigentuemer, flurstuecke)| EigentuemerbenachrichtigungInfo {
+            datum: projekt_info.bearbeitung_beendet_am.clone(),
+            antragsnr: projekt_info.antragsnr.trim().to_string(),
+            gemarkung: projekt_info.gemarkung.trim().to_string(),
+            gemeinde: projekt_info.gemeinde.trim().to_string(),
+            vermessungsstelle: projekt_info.vermessungsstelle.trim().to_string(),
+            erstellt_durch: projekt_info.erstellt_durch.trim().to_string(),
+            eigentuemer,
+            flurstuecke,
+        })
+        .collect()
+}
+
+fn get_eigentuemerbenachrichtigung_dateiname(nr: usize, info: &EigentuemerbenachrichtigungInfo) -> String {
+    let name = info
+        .eigentuemer
+        .format()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    let name = name.trim_matches('_').chars().take(40).collect::<String>();
+    format!("{nr:03}_{name}.docx")
+}
+
+/// Index of the generated letters (the LiKa export has no addresses, so this
+/// is a list for checking, not a mail merge source): one line per letter,
+/// separated by ';' and with UTF-8 BOM, so that it opens directly in Excel
+pub fn eigentuemerbenachrichtigungen_to_csv(briefe: &[(String, EigentuemerbenachrichtigungInfo)]) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    let mut zeilen = vec![
+        "\u{FEFF}Nr;Datei;Anrede;Vorname;Name;Flurstuecke;Antragsnr".to_string(),
+    ];
+    for (i, (datei, b)) in briefe.iter().enumerate() {
+        let (vorname, name) = match &b.eigentuemer {
+            EigentuemerClean::Herr { vorname, nachname }
+            | EigentuemerClean::Frau { vorname, nachname } => (vorname.clone(), nachname.clone()),
+            EigentuemerClean::Firma { name } | EigentuemerClean::Sonstige { name } => {
+                (String::new(), name.trim().to_string())
+            }
+        };
+        let flst = b
+            .flurstuecke
+            .iter()
+            .map(|(f, _)| f.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        zeilen.push(
+            [
+                (i + 1).to_string(),
+                quote(datei),
+                quote(&b.get_anrede()),
+                quote(&vorname),
+                quote(&name),
+                quote(&flst),
+                quote(&b.antragsnr),
+            ]
+            .join(";"),
+        );
+    }
+    zeilen.join("\r\n")
+}
+
 pub fn join_modified_fluren(
     modified: &BTreeMap<usize, Vec<FlstIdParsedNumber>>,
 ) -> BTreeMap<String, String> {
@@ -739,12 +1082,12 @@ pub fn generate_grafbat_out(
     let mut lid = 1_usize;
     let mut txid = 1_usize;
 
-    let zone = 33;
+    let crs = crate::crs::get_crs_by_proj_or_default(&default_extent.crs);
 
     let bbox = format!(
         "{min_x}.000000,{min_y}.000000,{max_x}.000000,{max_y}.000000", 
-        min_x = update_dxf_x(zone, default_extent.min_x.floor()),
-        max_x = update_dxf_x(zone, default_extent.max_x.ceil()),
+        min_x = crs.dxf_x(default_extent.min_x.floor()),
+        max_x = crs.dxf_x(default_extent.max_x.ceil()),
         min_y = default_extent.min_y.floor(),
         max_y = default_extent.max_y.ceil(),
     );
@@ -772,11 +1115,11 @@ pub fn generate_grafbat_out(
             txid += 1;
             header.push(format!(
                 "TE{txid}: ,1600.9104.4140,{xcoord},{ycoord},{xcoord2},{ycoord2},{gon},0,0,0,0,,0,,,,,,,j,,,", 
-                xcoord = update_dxf_x(zone, alt.optimized.pos.x),
+                xcoord = crs.dxf_x(alt.optimized.pos.x),
                 ycoord = alt.optimized.pos.y,
-                xcoord2 = if alt.needs_bezug() { update_dxf_x(zone, alt.optimized.ref_pos.x).to_string() } else { String::new() },
+                xcoord2 = if alt.needs_bezug() { crs.dxf_x(alt.optimized.ref_pos.x).to_string() } else { String::new() },
                 ycoord2 = if alt.needs_bezug() { alt.optimized.ref_pos.y.to_string() } else { String::new() },
-                gon = 100.0,
+                gon = winkel_to_gon(alt.winkel),
             ));
             header.push(format!("  TX{txid}: {}", alt.optimized.kuerzel));
             txtid_textalt.insert(txid);
@@ -788,11 +1131,11 @@ pub fn generate_grafbat_out(
             txid += 1;
             header.push(format!(
                 "TE{txid}: ,1600.9104.4140,{xcoord},{ycoord},{xcoord2},{ycoord2},{gon},0,0,0,0,,0,,,,,,,n,,,0000ff", 
-                xcoord = update_dxf_x(zone, neu.optimized.pos.x),
+                xcoord = crs.dxf_x(neu.optimized.pos.x),
                 ycoord = neu.optimized.pos.y,
-                xcoord2 = if neu.needs_bezug() { update_dxf_x(zone, neu.optimized.ref_pos.x).to_string() } else { String::new() },
+                xcoord2 = if neu.needs_bezug() { crs.dxf_x(neu.optimized.ref_pos.x).to_string() } else { String::new() },
                 ycoord2 = if neu.needs_bezug() { neu.optimized.ref_pos.y.to_string() } else { String::new() },
-                gon = 100.0,
+                gon = winkel_to_gon(neu.winkel),
             ));
             header.push(format!("  TX{txid}: {}", neu.optimized.kuerzel));
             txtid_textneu.insert(txid);
@@ -804,11 +1147,11 @@ pub fn generate_grafbat_out(
             txid += 1;
             header.push(format!(
                 "TE{txid}: ,1600.9104.4140,{xcoord},{ycoord},{xcoord2},{ycoord2},{gon},0,0,0,0,,0,,,,,,,n,,,010101", 
-                xcoord = update_dxf_x(zone, bleibt.optimized.pos.x),
+                xcoord = crs.dxf_x(bleibt.optimized.pos.x),
                 ycoord = bleibt.optimized.pos.y,
-                xcoord2 = if bleibt.needs_bezug() { update_dxf_x(zone, bleibt.optimized.ref_pos.x).to_string() } else { String::new() },
+                xcoord2 = if bleibt.needs_bezug() { crs.dxf_x(bleibt.optimized.ref_pos.x).to_string() } else { String::new() },
                 ycoord2 = if bleibt.needs_bezug() { bleibt.optimized.ref_pos.y.to_string() } else { String::new() },
-                gon = 100.0,
+                gon = winkel_to_gon(bleibt.winkel),
             ));
             header.push(format!("  TX{txid}: {}", bleibt.optimized.kuerzel));
             txtid_textbleibt.insert(txid);
@@ -820,9 +1163,9 @@ pub fn generate_grafbat_out(
             txid += 1;
             header.push(format!(
                 "TE{txid}, ,0: ,1600.9102.4111,{xcoord},{ycoord},{xcoord2},{ycoord2},{gon},0,0,4,0,,0,,,,,,,n,,,", 
-                xcoord = update_dxf_x(zone, flst.pos.x),
+                xcoord = crs.dxf_x(flst.pos.x),
                 ycoord = flst.pos.y,
-                xcoord2 = if flst.needs_bezug() { update_dxf_x(zone, flst.ref_pos.x).to_string() } else { String::new() },
+                xcoord2 = if flst.needs_bezug() { crs.dxf_x(flst.ref_pos.x).to_string() } else { String::new() },
                 ycoord2 = if flst.needs_bezug() { flst.ref_pos.y.to_string() } else { String::new() },
                 gon = 100.0,
             ));
@@ -836,7 +1179,7 @@ pub fn generate_grafbat_out(
             txid += 1;
             header.push(format!(
                 "TE{txid}, ,0: ,1600.9103.4200,{xcoord},{ycoord},,,{gon},0,0,4,0,,0,,,,,,,n,,,", 
-                xcoord = update_dxf_x(zone, fl.pos.x),
+                xcoord = crs.dxf_x(fl.pos.x),
                 ycoord = fl.pos.y,
                 gon = 100.0,
             ));
@@ -845,26 +1188,40 @@ pub fn generate_grafbat_out(
             riss_items.push(format!("TE={txid}"));
         }
 
+        for a in outconf.anschlussriss_texte.iter() {
+            txid += 1;
+            let pos = a.get_text_pos();
+            header.push(format!(
+                "TE{txid}: ,{objektart},{xcoord},{ycoord},,,{gon},0,0,0,0,,0,,,,,,,n,,,",
+                objektart = crate::grafbat::OBJEKTART_ANSCHLUSSRISS_TEXT,
+                xcoord = crs.dxf_x(pos.x),
+                ycoord = pos.y,
+                gon = if a.vert { 0.0 } else { 100.0 },
+            ));
+            header.push(format!("  TX{txid}: {}", a.get_text()));
+            riss_items.push(format!("TE={txid}"));
+        }
+
         let mut txtid_linien_rot = BTreeSet::new();
         for (pid_start, pid_end) in outconf.aenderungen_rote_linien.iter().flat_map(l_to_points).collect::<Vec<_>>() {
             
             pid += 1;
             let pid_start_save = pid;
-            let pid_start_id = format!("{x},{y}", x = update_dxf_x(zone, pid_start.x), y = pid_start.y);
+            let pid_start_id = format!("{x},{y}", x = crs.dxf_x(pid_start.x), y = pid_start.y);
             let num_instances = existing_lines_and_points.entry(pid_start_id).or_insert_with(|| 0);
             let diffadd = (*num_instances) as f64 / 100.0;
             *num_instances = *num_instances + 1;
-            header.push(format!("PK{pid}: ,1600.9101.0,{x},{y},,,0,0,,,,1005,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,", x = update_dxf_x(zone, pid_start.x + diffadd), y = pid_start.y + diffadd));
+            header.push(format!("PK{pid}: ,1600.9101.0,{x},{y},,,0,0,,,,1005,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,", x = crs.dxf_x(pid_start.x + diffadd), y = pid_start.y + diffadd));
             riss_items.push(format!("PK={pid}"));
             txtid_linien_rot.insert(format!("PK={pid}"));
 
             pid += 1;
             let pid_end_save = pid;
-            let pid_end_id = format!("{x},{y}", x = update_dxf_x(zone, pid_end.x), y = pid_end.y);
+            let pid_end_id = format!("{x},{y}", x = crs.dxf_x(pid_end.x), y = pid_end.y);
             let num_instances = existing_lines_and_points.entry(pid_end_id).or_insert_with(|| 0);
             let diffadd = (*num_instances) as f64 / 100.0;
             *num_instances = *num_instances + 1;
-            header.push(format!("PK{pid}: ,1600.9101.0,{x},{y},,,0,0,,,,1005,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,", x = update_dxf_x(zone, pid_end.x + diffadd), y = pid_end.y + diffadd));
+            header.push(format!("PK{pid}: ,1600.9101.0,{x},{y},,,0,0,,,,1005,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,", x = crs.dxf_x(pid_end.x + diffadd), y = pid_end.y + diffadd));
             riss_items.push(format!("PK={pid}"));
             txtid_linien_rot.insert(format!("PK={pid}"));
 
@@ -878,11 +1235,11 @@ pub fn generate_grafbat_out(
         for (p, angle) in lines_to_points(&outconf.aenderungen_nutzungsarten_linien) {
             pid += 1;
             let ang = Into::<angular_units::Gon<f64>>::into(angular_units::Deg(angle - 45.0));
-            let pid_start_id = format!("{x},{y}", x = update_dxf_x(zone, p.x), y = p.y);
+            let pid_start_id = format!("{x},{y}", x = crs.dxf_x(p.x), y = p.y);
             let num_instances = existing_lines_and_points.entry(pid_start_id).or_insert_with(|| 0);
             let diffadd = (*num_instances) as f64 / 100.0;
             *num_instances = *num_instances + 1;
-            header.push(format!("PK{pid}: ,1600.401.20,{x},{y},,{gon},0,0,,,,1007,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,", x = update_dxf_x(zone, p.x + diffadd), y = p.y + diffadd, gon = ang.0));
+            header.push(format!("PK{pid}: ,1600.401.20,{x},{y},,{gon},0,0,,,,1007,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,", x = crs.dxf_x(p.x + diffadd), y = p.y + diffadd, gon = ang.0));
             riss_items.push(format!("PK={pid}"));
             punkte_id_untergehend.insert(format!("PK={pid}"));
         }
@@ -901,8 +1258,8 @@ pub fn generate_grafbat_out(
         header.push(
             format!(
                 "PB{riss_id}: Riss{riss_id},1600.873.0,{min_x},{min_y},{max_x},{min_y},{hoehe},0", 
-                min_x = update_dxf_x(zone, outconf.extent.min_x), 
-                max_x = update_dxf_x(zone, outconf.extent.max_x),
+                min_x = crs.dxf_x(outconf.extent.min_x), 
+                max_x = crs.dxf_x(outconf.extent.max_x),
                 min_y = outconf.extent.min_y, 
                 hoehe = outconf.extent.height_m()
             )
@@ -912,6 +1269,22 @@ pub fn generate_grafbat_out(
     header.join("\r\n")
 }
 
+/// Text direction in gon (clockwise from north, 100 = horizontal) from the
+/// counterclockwise rotation in degrees
+pub(crate) fn winkel_to_gon(winkel: AngleDegrees) -> f64 {
+    (100.0 - winkel / 0.9).rem_euclid(400.0)
+}
+
+/// Inverse of [winkel_to_gon], the result is in (-180, 180]
+pub(crate) fn gon_to_winkel(gon: f64) -> AngleDegrees {
+    let winkel = ((100.0 - gon) * 0.9).rem_euclid(360.0);
+    if winkel > 180.0 {
+        winkel - 360.0
+    } else {
+        winkel
+    }
+}
+
 fn join_flst(v: &Vec<FlstIdParsedNumber>) -> Option<String> {
     let mut v = v.clone();
     v.sort_by(|a, b| a.get_comma_f32().total_cmp(&b.get_comma_f32()));
@@ -1063,6 +1436,8 @@ pub fn get_default_riss_extent(
             + 10.0,
         scale: SCALE as f32,
         rissgebiet: None,
+        seed: 0,
+        beschriftungen: Vec::new(),
     })
 }
 
@@ -1108,8 +1483,10 @@ pub fn export_splitflaechen(
     lq_flurstuecke: &LinienQuadTree,
     lq_flurstuecke_und_nutzungsarten: &LinienQuadTree,
     hintergrund_cache: &mut HintergrundCache,
+    anschlussrisse: &[AnschlussrissMarker],
 ) -> Result<(usize, GrafbatOutConfig), ()> {
     let pdir_name = parent_dir.as_deref().unwrap_or("Aenderungen");
+    let crs = crate::crs::get_crs_by_proj_or_default(&split_nas.crs);
 
     let default_riss_config = match get_default_riss_extent(splitflaechen, &gebaeude, &split_nas.crs) {
         Some(s) => s,
@@ -1153,25 +1530,7 @@ pub fn export_splitflaechen(
         HeaderCalcConfig::from_csv(&split_nas, csv, &Some(riss_extent_cutpoly_noborder.clone()));
 
     let riss_rect = riss_extent_reprojected.get_rect();
-    let splitflaechen2 = splitflaechen
-        .iter()
-        .filter_map(|s| {
-            if s.poly_cut.get_rect().overlaps_rect(&riss_rect) {
-                if let Some(rg) = riss_extent_reprojected.rissgebiet.as_ref() {
-                    if s.poly_cut.overlaps(&rg) || rg.overlaps(&s.poly_cut) {
-                        Some(s)
-                    } else {
-                        None
-                    }
-                } else {
-                    Some(s)
-                }
-            } else {
-                None
-            }
-        })
-        .cloned()
-        .collect::<Vec<_>>();
+    let splitflaechen = get_splitflaechen_fuer_riss(splitflaechen, &riss_extent_reprojected);
 
     let gebaeude = gebaeude
     .iter()
@@ -1193,23 +1552,6 @@ pub fn export_splitflaechen(
     .cloned()
     .collect::<Vec<_>>();
 
-    // TODO: accurate?
-    let alle_flurstuecke = splitflaechen2
-        .iter()
-        .map(|s| s.flst_id.clone())
-        .collect::<BTreeSet<_>>();
-    let splitflaechen = splitflaechen
-        .iter()
-        .filter_map(|s| {
-            if alle_flurstuecke.contains(&s.flst_id) {
-                Some(s)
-            } else {
-                None
-            }
-        })
-        .cloned()
-        .collect::<Vec<_>>();
-
     log_status(&format!(
         "[{num_riss} / {total_risse}] Export {} Teilflächen",
         splitflaechen.len()
@@ -1236,7 +1578,7 @@ pub fn export_splitflaechen(
         files.push((
             Some("Punkte".to_string()),
             format!("Punkte_NAGrenze_Untergehend_{pdir_name}.dxf").into(),
-            lines_to_points_dxf(&aenderungen_nutzungsarten_linien),
+            lines_to_points_dxf(&aenderungen_nutzungsarten_linien, &crs),
         ));
     }
     log_status(&format!(
@@ -1252,7 +1594,7 @@ pub fn export_splitflaechen(
         files.push((
             Some("Linien".to_string()),
             format!("Linien_Rot_{pdir_name}.dxf").into(),
-            lines_to_dxf(&aenderungen_rote_linien),
+            lines_to_dxf(&aenderungen_rote_linien, &crs),
         ));
     }
     log_status(&format!(
@@ -1285,13 +1627,14 @@ pub fn export_splitflaechen(
             pos: fl.pos,
             ref_pos: fl.pos,
             poly: SvgPolygonInner::default(),
+            splitflaeche: None,
         })
         .collect::<Vec<_>>();
     if !flur_texte.is_empty() {
         files.push((
             Some("Texte".to_string()),
             format!("Flur_Texte_{pdir_name}.dxf").into(),
-            texte_zu_dxf_datei(&flur_texte),
+            texte_zu_dxf_datei(&flur_texte, &crs),
         ));
     }
     log_status(&format!(
@@ -1304,7 +1647,7 @@ pub fn export_splitflaechen(
         files.push((
             Some("Texte".to_string()),
             format!("Flurstueck_Texte_{pdir_name}.dxf").into(),
-            texte_zu_dxf_datei(&flurstueck_texte),
+            texte_zu_dxf_datei(&flurstueck_texte, &crs),
         ));
     }
     log_status(&format!(
@@ -1316,14 +1659,23 @@ pub fn export_splitflaechen(
         "[{num_riss} / {total_risse}] Optimiere Beschriftungen... {:?}",
         riss_von
     ));
-    let aenderungen_texte_optimized = crate::optimize::optimize_labels(
+    let optimized = crate::optimize::optimize_labels(
         &mini_split_nas,
         &splitflaechen,
         &gebaeude,
         &[],
         &aenderungen_texte,
-        &OptimizeConfig::new(&riss, &riss_extent_reprojected, 0.5 /* mm */),
+        &OptimizeConfig::new(
+            &riss,
+            &riss_extent_reprojected,
+            konfiguration.pdf.get_beschriftung_schriftgroesse(),
+        ),
     );
+    log_status(&format!(
+        "[{num_riss} / {total_risse}] Beschriftungen: Kosten {:.1}, {} nicht platziert, {} fixiert (Seed {})",
+        optimized.kosten, optimized.nicht_platziert, optimized.fixiert, riss.seed
+    ));
+    let aenderungen_texte_optimized = optimized.texte;
 
     let beschriftungen_optimized_linien = aenderungen_texte_optimized
         .iter()
@@ -1336,7 +1688,7 @@ pub fn export_splitflaechen(
         files.push((
             Some("Texte".to_string()),
             format!("Beschriftung_Linien_{pdir_name}.dxf").into(),
-            lines_to_dxf(&beschriftungen_optimized_linien),
+            lines_to_dxf(&beschriftungen_optimized_linien, &crs),
         ));
     }
     log_status(&format!(
@@ -1353,7 +1705,7 @@ pub fn export_splitflaechen(
     files.push((
         Some("Texte".to_string()),
         format!("Texte_Bleibt_{pdir_name}.dxf").into(),
-        texte_zu_dxf_datei(&aenderungen_texte_bleibt),
+        texte_zu_dxf_datei(&aenderungen_texte_bleibt, &crs),
     ));
     log_status(&format!(
         "[{num_riss} / {total_risse}] {} Texte: bleibende Kürzel",
@@ -1369,7 +1721,7 @@ pub fn export_splitflaechen(
     files.push((
         Some("Texte".to_string()),
         format!("Texte_Alt_{pdir_name}.dxf").into(),
-        texte_zu_dxf_datei(&aenderungen_texte_alt),
+        texte_zu_dxf_datei(&aenderungen_texte_alt, &crs),
     ));
     log_status(&format!(
         "[{num_riss} / {total_risse}] {} Texte: alte Kürzel",
@@ -1385,7 +1737,7 @@ pub fn export_splitflaechen(
     files.push((
         Some("Texte".to_string()),
         format!("Texte_Neu_{pdir_name}.dxf").into(),
-        texte_zu_dxf_datei(&aenderungen_texte_neu),
+        texte_zu_dxf_datei(&aenderungen_texte_neu, &crs),
     ));
     log_status(&format!(
         "[{num_riss} / {total_risse}] {} Texte: neue Kürzel",
@@ -1410,6 +1762,7 @@ pub fn export_splitflaechen(
         &fluren,
         &flst,
         &gebaeude,
+        anschlussrisse,
     );
 
     files.push((
@@ -1425,19 +1778,33 @@ pub fn export_splitflaechen(
         "[{num_riss} / {total_risse}] OK: PDF Vorschau generiert."
     ));
 
-    let splitflaechen = AenderungenIntersections(splitflaechen.to_vec()).get_future_flaechen();
+    let future = AenderungenIntersections(splitflaechen.to_vec()).get_future_flaechen();
+    let riss_future = RissConfig {
+        beschriftungen: crate::optimize::get_future_overrides(&riss.beschriftungen, &splitflaechen, &future.0),
+        ..riss.clone()
+    };
+    let splitflaechen = future;
     let split_nas = split_nas.migrate_future(&splitflaechen.0);
     let aenderungen_texte =
         AenderungenIntersections::get_texte(&splitflaechen.0, &riss_extent_cutpoly_noborder);
     let mini_split_nas = get_mini_nas_xml(&split_nas, &riss_extent_reprojected);
-    let aenderungen_texte_optimized_new = crate::optimize::optimize_labels(
+    let optimized_new = crate::optimize::optimize_labels(
         &mini_split_nas,
         &splitflaechen.0,
         &gebaeude,
         &[],
         &aenderungen_texte,
-        &OptimizeConfig::new(&riss, &riss_extent_reprojected, 0.5 /* mm */),
+        &OptimizeConfig::new(
+            &riss_future,
+            &riss_extent_reprojected,
+            konfiguration.pdf.get_beschriftung_schriftgroesse(),
+        ),
     );
+    log_status(&format!(
+        "[{num_riss} / {total_risse}] Beschriftungen (neu): Kosten {:.1}, {} nicht platziert",
+        optimized_new.kosten, optimized_new.nicht_platziert
+    ));
+    let aenderungen_texte_optimized_new = optimized_new.texte;
 
     log_status(&format!(
         "[{num_riss} / {total_risse}] Generiere Hintergrund-Vorschau..."
@@ -1464,6 +1831,7 @@ pub fn export_splitflaechen(
         &fluren,
         &flst,
         &gebaeude,
+        anschlussrisse,
     );
 
     files.push((
@@ -1508,9 +1876,95 @@ pub fn export_splitflaechen(
         aenderungen_texte_bleibt: aenderungen_texte_bleibt_2.clone(),
         flurstueck_texte: flurstueck_texte.clone(),
         flur_texte: flur_texte.clone(),
+        anschlussriss_texte: anschlussrisse.to_vec(),
     }))
 }
 
+/// Splitflächen of all Flurstücke that are (partially) visible in the Riss
+pub fn get_splitflaechen_fuer_riss(
+    splitflaechen: &[AenderungenIntersection],
+    riss_extent_reprojected: &RissExtentReprojected,
+) -> Vec<AenderungenIntersection> {
+    let riss_rect = riss_extent_reprojected.get_rect();
+    let splitflaechen2 = splitflaechen
+        .iter()
+        .filter_map(|s| {
+            if s.poly_cut.get_rect().overlaps_rect(&riss_rect) {
+                if let Some(rg) = riss_extent_reprojected.rissgebiet.as_ref() {
+                    if s.poly_cut.overlaps(&rg) || rg.overlaps(&s.poly_cut) {
+                        Some(s)
+                    } else {
+                        None
+                    }
+                } else {
+                    Some(s)
+                }
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // TODO: accurate?
+    let alle_flurstuecke = splitflaechen2
+        .iter()
+        .map(|s| s.flst_id.clone())
+        .collect::<BTreeSet<_>>();
+    splitflaechen
+        .iter()
+        .filter_map(|s| {
+            if alle_flurstuecke.contains(&s.flst_id) {
+                Some(s)
+            } else {
+                None
+            }
+        })
+        .cloned()
+        .collect::<Vec<_>>()
+}
+
+/// Initial (not optimized) Änderungs-Texte of a Riss, as used by [export_splitflaechen]
+pub fn get_riss_texte(
+    splitflaechen: &[AenderungenIntersection],
+    riss: &RissConfig,
+    crs: &str,
+) -> Option<Vec<TextPlacement>> {
+    let riss_extent_reprojected = riss.get_extent(crs, 0.0)?.reproject(crs)?;
+    let splitflaechen = get_splitflaechen_fuer_riss(splitflaechen, &riss_extent_reprojected);
+    Some(AenderungenIntersections::get_texte(
+        &splitflaechen,
+        &riss_extent_reprojected.get_poly(),
+    ))
+}
+
+/// Änderungs-Texte of a Riss as placed by [export_splitflaechen], with the
+/// manual overrides of the Riss pinned
+pub fn get_riss_texte_optimiert(
+    splitflaechen: &[AenderungenIntersection],
+    split_nas: &SplitNasXml,
+    nas_xml: &NasXMLFile,
+    riss: &RissConfig,
+    konfiguration: &Konfiguration,
+) -> Option<OptimizeResult> {
+    let riss_extent_reprojected = riss.get_extent(&split_nas.crs, 0.0)?.reproject(&split_nas.crs)?;
+    let splitflaechen = get_splitflaechen_fuer_riss(splitflaechen, &riss_extent_reprojected);
+    let texte = AenderungenIntersections::get_texte(&splitflaechen, &riss_extent_reprojected.get_poly());
+    let mini_split_nas = get_mini_nas_xml(split_nas, &riss_extent_reprojected);
+    let gebaeude = get_gebaeude(nas_xml, &riss_extent_reprojected);
+    Some(crate::optimize::optimize_labels(
+        &mini_split_nas,
+        &splitflaechen,
+        &gebaeude,
+        &[],
+        &texte,
+        &OptimizeConfig::new(
+            riss,
+            &riss_extent_reprojected,
+            konfiguration.pdf.get_beschriftung_schriftgroesse(),
+        ),
+    ))
+}
+
 pub struct GrafbatOutConfig {
     extent: RissExtentReprojected,
     aenderungen_rote_linien: Vec<SvgLine>,
@@ -1520,6 +1974,7 @@ pub struct GrafbatOutConfig {
     aenderungen_texte_bleibt: Vec<OptimizedTextPlacement>,
     flurstueck_texte: Vec<TextPlacement>,
     flur_texte: Vec<TextPlacement>,
+    anschlussriss_texte: Vec<AnschlussrissMarker>,
 }
 
 
@@ -1933,7 +2388,7 @@ pub fn get_linecoords(p: &SvgPolygonInner) -> BTreeSet<((u64, u64), (u64, u64))>
         .collect()
 }
 
-fn calc_text_width_pt(text: &String, font_scale: f32, font: &dyn ab_glyph::Font) -> Pt {
+pub fn calc_text_width_pt(text: &String, font_scale: f32, font: &dyn ab_glyph::Font) -> Pt {
     // vertical scale of one text box
     let vert_scale = font.height_unscaled();
 
@@ -1946,6 +2401,43 @@ fn calc_text_width_pt(text: &String, font_scale: f32, font: &dyn ab_glyph::Font)
     Pt(sum_width as f32 / (vert_scale as f32 / font_scale))
 }
 
+/// Advance widths of Helvetica-Bold (standard PDF font of the Beschriftungen
+/// and Anschlussriss-Verweise) in 1/1000 em, for the characters ' '..='~'
+const HELVETICA_BOLD_BREITEN: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+/// Ascender / descender of Helvetica-Bold in em
+pub const HELVETICA_BOLD_OBERLAENGE: f32 = 0.718;
+pub const HELVETICA_BOLD_UNTERLAENGE: f32 = 0.207;
+
+/// Width of `text` in Helvetica-Bold, umlauts are measured like their base
+/// letter, other characters as wide as "M"
+pub fn calc_text_width_helvetica_bold_pt(text: &str, font_scale: f32) -> Pt {
+    let breite = |c: char| match c {
+        'Ä' => 'A',
+        'Ö' => 'O',
+        'Ü' => 'U',
+        'ä' => 'a',
+        'ö' => 'o',
+        'ü' => 'u',
+        'ß' => 'p',
+        c => c,
+    };
+    let sum_width: u32 = text
+        .chars()
+        .map(|c| {
+            let i = (breite(c) as u32).wrapping_sub(' ' as u32) as usize;
+            HELVETICA_BOLD_BREITEN.get(i).copied().unwrap_or(833) as u32
+        })
+        .sum();
+    Pt(sum_width as f32 / 1000.0 * font_scale)
+}
+
 pub fn generate_anschlussriss_pdf(num: usize, total: usize, vert: bool) -> Vec<u8> {
     use ab_glyph::FontRef;
 
@@ -2258,3 +2750,198 @@ pub fn append_shp(
         shp_file.cpg,
     ));
 }
+
+#[test]
+fn test_splitflaechen_shp_geojson() {
+    use crate::test_utils::test_quadrat;
+    use std::io::Cursor;
+
+    // clockwise
+    let quadrat = |min_x: f64, groesse: f64| test_quadrat(min_x, 5700000.0, groesse).outer_ring;
+    let splitflaechen = vec![
+        AenderungenIntersection {
+            alt: "GR".to_string(),
+            neu: "WALD".to_string(),
+            flst_id: "12345600100001______".to_string(),
+            flst_id_part: "12345600100001______:0".to_string(),
+            poly_cut: SvgPolygonInner {
+                outer_ring: quadrat(400000.0, 20.0),
+                inner_rings: vec![quadrat(400005.0, 10.0).reverse()],
+            },
+        },
+        AenderungenIntersection {
+            alt: "GR".to_string(),
+            neu: "GR".to_string(),
+            flst_id: "12345600100002______".to_string(),
+            flst_id_part: "12345600100002______:0".to_string(),
+            poly_cut: SvgPolygonInner {
+                outer_ring: quadrat(400100.0, 10.0),
+                inner_rings: Vec::new(),
+            },
+        },
+    ];
+    let proj = crate::nas::default_etrs33();
+    let crs = crate::crs::get_crs_by_proj_or_default(&proj);
+
+    let shp = splitflaechen_to_shp(&splitflaechen, &[], &crs);
+    let records = dbase::Reader::new(Cursor::new(shp.dbf)).and_then(|mut r| r.read()).unwrap();
+    assert_eq!(records.len(), 2);
+    let felder = std::collections::HashMap::<String, dbase::FieldValue>::from(records[0].clone());
+    let text = |k: &str| match felder.get(k) {
+        Some(dbase::FieldValue::Character(Some(s))) => s.trim().to_string(),
+        o => panic!("{k}: {o:?}"),
+    };
+    assert_eq!(text("ALT"), "GR");
+    assert_eq!(text("NEU"), "WALD");
+    assert_eq!(text("FLST_ID"), "12345600100001______");
+    let felder = std::collections::HashMap::<String, dbase::FieldValue>::from(records[1].clone());
+    match felder.get("FLAECHE_M2") {
+        Some(dbase::FieldValue::Numeric(Some(f))) => assert!((f - 100.0).abs() < 0.01),
+        o => panic!("FLAECHE_M2: {o:?}"),
+    }
+
+    let geojson = serde_json::from_str::<serde_json::Value>(&splitflaechen_to_geojson(&splitflaechen, &[], &proj)).unwrap();
+    let features = geojson["features"].as_array().unwrap();
+    assert_eq!(features.len(), 2);
+    assert_eq!(features[0]["properties"]["alt"], "GR");
+    assert_eq!(features[0]["properties"]["neu"], "WALD");
+    assert_eq!(features[1]["properties"]["neu"], "GR");
+    let doppelte_flaeche = |ring: &serde_json::Value| {
+        let p = ring
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| (p[0].as_f64().unwrap(), p[1].as_f64().unwrap()))
+            .collect::<Vec<_>>();
+        p.windows(2).map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1).sum::<f64>()
+    };
+    let rings = features[0]["geometry"]["coordinates"].as_array().unwrap();
+    assert_eq!(rings.len(), 2);
+    assert!(doppelte_flaeche(&rings[0]) > 0.0);
+    assert!(doppelte_flaeche(&rings[1]) < 0.0);
+}
+
+#[test]
+fn test_eigentuemer_from_str() {
+    assert_eq!(
+        EigentuemerClean::from_str("Müller, Anna (Frau)"),
+        EigentuemerClean::Frau {
+            vorname: "Anna".to_string(),
+            nachname: "Müller".to_string(),
+        }
+    );
+    assert_eq!(
+        EigentuemerClean::from_str("Schulze, Peter (Herr)"),
+        EigentuemerClean::Herr {
+            vorname: "Peter".to_string(),
+            nachname: "Schulze".to_string(),
+        }
+    );
+    assert_eq!(
+        EigentuemerClean::from_str("Agrar GmbH (Firma)"),
+        EigentuemerClean::Firma {
+            name: "Agrar GmbH".to_string(),
+        }
+    );
+    assert_eq!(
+        EigentuemerClean::from_str("Herrmann, Anna (Frau)"),
+        EigentuemerClean::Frau {
+            vorname: "Anna".to_string(),
+            nachname: "Herrmann".to_string(),
+        }
+    );
+    assert_eq!(
+        EigentuemerClean::from_str("Frauenfeld, Peter (Herr)"),
+        EigentuemerClean::Herr {
+            vorname: "Peter".to_string(),
+            nachname: "Frauenfeld".to_string(),
+        }
+    );
+    assert_eq!(
+        EigentuemerClean::from_str("Frauke Herr"),
+        EigentuemerClean::Sonstige {
+            name: "Frauke Herr".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_get_eigentuemerbenachrichtigungen() {
+    let flst = |zaehler: usize| FlstIdParsedNumber {
+        land: 12,
+        gemarkung: 1180,
+        flur: 3,
+        flst_zaehler: zaehler,
+        flst_nenner: None,
+    };
+    let splitflaeche = |zaehler: usize, neu: &str| AenderungenIntersection {
+        alt: "GR".to_string(),
+        neu: neu.to_string(),
+        flst_id: format!("121180003{zaehler:05}0000__"),
+        flst_id_part: format!("121180003{zaehler:05}0000__:{neu}"),
+        poly_cut: SvgPolygonInner {
+            outer_ring: SvgLine {
+                points: vec![
+                    SvgPoint { x: 0.0, y: 0.0 },
+                    SvgPoint { x: 10.0, y: 0.0 },
+                    SvgPoint { x: 10.0, y: 10.0 },
+                    SvgPoint { x: 0.0, y: 10.0 },
+                    SvgPoint { x: 0.0, y: 0.0 },
+                ],
+            },
+            inner_rings: Vec::new(),
+        },
+    };
+    let mueller = EigentuemerClean::from_str("Müller, Anna (Frau)");
+    let schulze = EigentuemerClean::from_str("Schulze, Peter (Herr)");
+    let eintrag = |status: Status, eigentuemer: Vec<EigentuemerClean>| FlstEigentuemer {
+        nutzung: String::new(),
+        status,
+        eigentuemer,
+        notiz: String::new(),
+        auto_notiz: String::new(),
+    };
+
+    let mut eigentuemer_map = BTreeMap::new();
+    eigentuemer_map.insert(
+        flst(1),
+        eintrag(Status::AenderungMitBenachrichtigung(false), vec![mueller.clone(), schulze.clone()]),
+    );
+    eigentuemer_map.insert(
+        flst(2),
+        eintrag(Status::AenderungKeineBenachrichtigung(false), vec![EigentuemerClean::from_str("Agrar GmbH (Firma)")]),
+    );
+    eigentuemer_map.insert(flst(3), eintrag(Status::AenderungMitBenachrichtigung(false), vec![mueller.clone()]));
+    let splitflaechen = vec![
+        splitflaeche(1, "WALD"),
+        splitflaeche(1, "GR"),
+        splitflaeche(2, "WALD"),
+        splitflaeche(3, "WALD"),
+    ];
+    let info = ProjektInfo {
+        antragsnr: " 2024-12 ".to_string(),
+        ..Default::default()
+    };
+
+    let briefe = get_eigentuemerbenachrichtigungen(&eigentuemer_map, &splitflaechen, &info);
+    assert_eq!(briefe.len(), 2);
+    assert!(briefe.iter().all(|b| b.antragsnr == "2024-12"));
+
+    let brief_mueller = briefe.iter().find(|b| b.eigentuemer == mueller).unwrap();
+    let flurstuecke = brief_mueller.flurstuecke.iter().map(|(f, _)| f.as_str()).collect::<Vec<_>>();
+    assert_eq!(flurstuecke, vec!["Fl. 3, 1", "Fl. 3, 3"]);
+    // only the changed Splitfläche, not the one that stays "GR"
+    let zeilen = &brief_mueller.flurstuecke[0].1;
+    assert_eq!(zeilen.len(), 1);
+    assert!(zeilen[0].alt.starts_with("GR"));
+    assert!(zeilen[0].neu.starts_with("WALD"));
+    assert!((zeilen[0].flaeche_m2 - 100.0).abs() < 0.01);
+    assert_eq!(brief_mueller.get_anrede(), "Sehr geehrte Frau Müller,");
+
+    let brief_schulze = briefe.iter().find(|b| b.eigentuemer == schulze).unwrap();
+    assert_eq!(brief_schulze.flurstuecke.len(), 1);
+
+    let csv = eigentuemerbenachrichtigungen_to_csv(&[("001_Anna_M_ller.docx".to_string(), brief_mueller.clone())]);
+    assert!(csv.starts_with("\u{FEFF}Nr;Datei;Anrede"));
+    assert!(csv.contains("\"Sehr geehrte Frau Müller,\";\"Anna\";\"Müller\";\"Fl. 3, 1; Fl. 3, 3\";\"2024-12\""));
+}
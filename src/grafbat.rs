@@ -0,0 +1,457 @@
+//! Parser for the `*.GRAFBAT.out` written by [crate::geograf::generate_grafbat_out]:
+//! texts that were moved in GEOgraf are matched back to the Änderungs-Texte
+//! of their Riss and stored as [BeschriftungOverride], so that the next
+//! GEOgraf / PDF export keeps the manual corrections.
+//!
+//! Only texts are read back: points (PK) and lines (LI) are skipped, the red
+//! lines are derived from the Änderungen and regenerated on every export.
+
+use crate::{
+    crs::CrsInfo,
+    nas::{
+        point_is_in_polygon,
+        SvgPoint,
+    },
+    optimize::BeschriftungOverride,
+    pdf::Risse,
+    process::AngleDegrees,
+    ui::{
+        AenderungenIntersection,
+        TextStatus,
+    },
+};
+use serde_derive::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+/// Objektart of the Nutzungsarten-Kürzel (alt / neu / bleibt)
+pub const OBJEKTART_NUTZUNG_TEXT: &str = "1600.9104.4140";
+/// Objektart of the Anschlussriss-Verweise ("s. Anschlussriss (X / Y)")
+pub const OBJEKTART_ANSCHLUSSRISS_TEXT: &str = "1600.9105.4100";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrafbatText {
+    pub id: usize,
+    pub objektart: String,
+    pub pos: SvgPoint,
+    /// End point of the Bezugslinie, if the text has one
+    pub ref_pos: Option<SvgPoint>,
+    /// Converted from the text direction in gon
+    pub winkel: AngleDegrees,
+    pub farbe: String,
+    pub text: String,
+}
+
+impl GrafbatText {
+    /// Status as encoded by `generate_grafbat_out` in the text color,
+    /// None for Flurstücks- / Flur-Texte
+    pub fn get_status(&self) -> Option<TextStatus> {
+        if self.objektart != OBJEKTART_NUTZUNG_TEXT {
+            return None;
+        }
+        Some(match self.farbe.as_str() {
+            "0000ff" => TextStatus::New,
+            "010101" => TextStatus::StaysAsIs,
+            _ => TextStatus::Old,
+        })
+    }
+}
+
+/// Menge ("MA"), e.g. "RISS001-GESAMT" with its elements ("TE=5", "PK=12", ...),
+/// only the texts are used
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrafbatMenge {
+    pub name: String,
+    pub elemente: Vec<String>,
+}
+
+impl GrafbatMenge {
+    /// Riss number (starting at 1) for "RISS001-GESAMT"
+    pub fn get_riss_nr(&self) -> Option<usize> {
+        let rest = self.name.trim().strip_prefix("RISS")?;
+        rest.chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    pub fn get_text_ids(&self) -> Vec<usize> {
+        self.get_ids("TE=")
+    }
+
+    fn get_ids(&self, prefix: &str) -> Vec<usize> {
+        self.elemente
+            .iter()
+            .filter_map(|e| e.trim().strip_prefix(prefix)?.trim().parse().ok())
+            .collect()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrafbatOut {
+    pub texte: BTreeMap<usize, GrafbatText>,
+    pub mengen: Vec<GrafbatMenge>,
+}
+
+fn parse_id(s: &str) -> Option<usize> {
+    s.chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+/// GEOgraf writes the file in Windows-1252, the export writes UTF-8
+pub fn decode_grafbat_out(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => encoding_rs::WINDOWS_1252.decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+/// Parses texts (TE / TX) and Mengen (MA / MR), all other lines are skipped,
+/// coordinates are converted back from the GEOgraf eastings (see [CrsInfo::dxf_x])
+pub fn parse_grafbat_out(s: &str, crs: &CrsInfo) -> Result<GrafbatOut, String> {
+    let mut out = GrafbatOut::default();
+    let mut menge: Option<GrafbatMenge> = None;
+
+    for (zeile, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("TE") {
+            let id = parse_id(rest).ok_or_else(|| format!("Zeile {}: ungültige Text-ID", zeile + 1))?;
+            let (_, felder) = rest
+                .split_once(':')
+                .ok_or_else(|| format!("Zeile {}: TE{id} ohne ':'", zeile + 1))?;
+            let felder = felder.split(',').map(str::trim).collect::<Vec<_>>();
+            let zahl = |i: usize| felder.get(i).and_then(|f| f.parse::<f64>().ok());
+            let pos = match (zahl(2), zahl(3)) {
+                (Some(x), Some(y)) => SvgPoint {
+                    x: crs.from_dxf_x(x),
+                    y,
+                },
+                _ => return Err(format!("Zeile {}: TE{id} ohne Koordinaten", zeile + 1)),
+            };
+            let ref_pos = match (zahl(4), zahl(5)) {
+                (Some(x), Some(y)) => Some(SvgPoint {
+                    x: crs.from_dxf_x(x),
+                    y,
+                }),
+                _ => None,
+            };
+            let text = out.texte.entry(id).or_default();
+            text.id = id;
+            text.objektart = felder.get(1).unwrap_or(&"").to_string();
+            text.pos = pos;
+            text.ref_pos = ref_pos;
+            text.winkel = zahl(6).map(crate::geograf::gon_to_winkel).unwrap_or_default();
+            text.farbe = felder.last().unwrap_or(&"").to_lowercase();
+        } else if let Some(rest) = line.strip_prefix("TX") {
+            let id = parse_id(rest).ok_or_else(|| format!("Zeile {}: ungültige Text-ID", zeile + 1))?;
+            let text = rest.split_once(':').map(|s| s.1.trim()).unwrap_or_default();
+            let t = out.texte.entry(id).or_default();
+            t.id = id;
+            t.text = text.to_string();
+        } else if let Some(rest) = line.strip_prefix("MA") {
+            let name = rest.split_once(':').map(|s| s.1.trim()).unwrap_or_default();
+            if let Some(m) = menge.take() {
+                out.mengen.push(m);
+            }
+            if !name.is_empty() {
+                menge = Some(GrafbatMenge {
+                    name: name.split(',').next().unwrap_or_default().to_string(),
+                    elemente: Vec::new(),
+                });
+            }
+        } else if let Some(rest) = line.strip_prefix("MR:") {
+            if let Some(m) = menge.as_mut() {
+                m.elemente.push(rest.trim().to_string());
+            }
+        }
+    }
+
+    if let Some(m) = menge.take() {
+        out.mengen.push(m);
+    }
+
+    Ok(out)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrafbatImportBericht {
+    /// Number of texts stored as override
+    pub zugeordnet: usize,
+    /// Texts that could not be matched to an Änderungs-Text
+    pub nicht_zugeordnet: Vec<String>,
+}
+
+/// Matches the Nutzungsarten-Texte of each "RISS..." Menge to the initial
+/// texts of the Riss (same Kürzel and status; the text whose polygon contains
+/// the Bezugspunkt or whose position is nearest wins) and stores the positions
+/// from the GRAFBAT file as overrides. `splitflaechen` and positions are in `crs`.
+pub fn import_grafbat_beschriftungen(
+    grafbat: &GrafbatOut,
+    risse: &Risse,
+    splitflaechen: &[AenderungenIntersection],
+    crs: &str,
+) -> Result<(Risse, GrafbatImportBericht), String> {
+    if risse.is_empty() {
+        return Err(
+            "Projekt hat keine Risse: Beschriftungen können nur für angelegte Risse gespeichert werden"
+                .to_string(),
+        );
+    }
+
+    let mut risse = risse.clone();
+    let mut bericht = GrafbatImportBericht::default();
+    let riss_ids = risse.keys().cloned().collect::<Vec<_>>();
+
+    for menge in grafbat.mengen.iter() {
+        let riss_nr = match menge.get_riss_nr() {
+            Some(s) => s,
+            None => continue,
+        };
+
+        let texte = menge
+            .get_text_ids()
+            .into_iter()
+            .filter_map(|id| grafbat.texte.get(&id))
+            .filter_map(|t| Some((t, t.get_status()?)))
+            .collect::<Vec<_>>();
+
+        let riss = match riss_nr.checked_sub(1).and_then(|i| riss_ids.get(i)).and_then(|id| risse.get_mut(id)) {
+            Some(s) => s,
+            None => {
+                bericht.nicht_zugeordnet.extend(
+                    texte
+                        .iter()
+                        .map(|(t, _)| format!("TE{} \"{}\": Riss {riss_nr} existiert nicht", t.id, t.text)),
+                );
+                continue;
+            }
+        };
+
+        let kandidaten = match crate::geograf::get_riss_texte(splitflaechen, riss, crs) {
+            Some(s) => s,
+            None => {
+                bericht
+                    .nicht_zugeordnet
+                    .push(format!("{}: Ausdehnung des Risses ungültig", menge.name));
+                continue;
+            }
+        };
+
+        let mut vergeben = BTreeSet::new();
+        for (t, status) in texte {
+            let bezugspunkt = t.ref_pos.unwrap_or(t.pos);
+            let best = kandidaten
+                .iter()
+                .enumerate()
+                .filter(|(i, k)| !vergeben.contains(i) && k.kuerzel == t.text && k.status == status)
+                .map(|(i, k)| {
+                    let dst = if point_is_in_polygon(&bezugspunkt, &k.poly) {
+                        0.0
+                    } else {
+                        bezugspunkt.dist(&k.pos)
+                    };
+                    (i, k, dst)
+                })
+                .min_by(|a, b| a.2.total_cmp(&b.2));
+
+            let (i, k) = match best {
+                Some((i, k, _)) => (i, k),
+                None => {
+                    bericht.nicht_zugeordnet.push(format!(
+                        "TE{} \"{}\" ({}): kein passender Text in Riss {riss_nr}",
+                        t.id, t.text, menge.name
+                    ));
+                    continue;
+                }
+            };
+            vergeben.insert(i);
+
+            let o = BeschriftungOverride {
+                splitflaeche: k.splitflaeche.clone(),
+                kuerzel: k.kuerzel.clone(),
+                status: k.status,
+                anker: k.pos,
+                pos: t.pos,
+                ref_pos: t.ref_pos,
+                winkel: t.winkel,
+            };
+            riss.beschriftungen.retain(|b| !b.matches(k));
+            riss.beschriftungen.push(o);
+            bericht.zugeordnet += 1;
+        }
+    }
+
+    Ok((risse, bericht))
+}
+
+#[test]
+fn test_parse_grafbat_out() {
+    let crs = crate::crs::get_crs_by_proj_or_default(&crate::nas::default_etrs33());
+    let s = [
+        "TE2: ,1600.9104.4140,33412345.5,5712345.25,33412350,5712340,100,0,0,0,0,,0,,,,,,,n,,,0000ff",
+        "  TX2: GR",
+        "TE3, ,0: ,1600.9102.4111,33412300,5712300,,,100,0,0,4,0,,0,,,,,,,n,,,",
+        "  TX3: 12/3",
+        "PK4: ,1600.9101.0,33412345,5712345,,,0,0,,,,1005,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,",
+        "PK5: ,1600.9101.0,33412355,5712345,,,0,0,,,,1005,09.10.24,0,,0,,0,0,,1,0,0,0,,,,,,",
+        "LI2: PK=4,PK=5,1600.9101.1,,,,0,0,,,,",
+        "MA2: RISS001-GESAMT,,\"\",date:08.10.24,depend:1,neu:1",
+        "  MR: LI=2",
+        "  MR: PK=4",
+        "  MR: PK=5",
+        "  MR: TE=2",
+        "  MR: TE=3",
+        "MA2:",
+    ]
+    .join("\r\n");
+    let g = parse_grafbat_out(&s, &crs).unwrap();
+    assert_eq!(g.mengen.len(), 1);
+    assert_eq!(g.mengen[0].get_riss_nr(), Some(1));
+    assert_eq!(g.mengen[0].get_text_ids(), vec![2, 3]);
+    let t = g.texte.get(&2).unwrap();
+    assert_eq!(t.text, "GR");
+    assert_eq!(t.get_status(), Some(TextStatus::New));
+    assert_eq!(t.pos, SvgPoint { x: 412345.5, y: 5712345.25 });
+    assert_eq!(t.ref_pos, Some(SvgPoint { x: 412350.0, y: 5712340.0 }));
+    assert_eq!(t.winkel, 0.0);
+    assert_eq!(g.texte.get(&3).unwrap().get_status(), None);
+    assert_eq!(g.texte.len(), 2);
+
+    let umlaut = decode_grafbat_out(b"TX4: Stra\xdfe \x80");
+    assert_eq!(umlaut, "TX4: Straße €");
+}
+
+#[test]
+fn test_import_grafbat_beschriftungen() {
+    use crate::{
+        nas::{
+            SvgLine,
+            SvgPolygonInner,
+        },
+        pdf::RissConfig,
+    };
+
+    let crs = crate::nas::default_etrs33();
+    let splitflaeche = |min_x: f64, flst_id_part: &str| {
+        let (max_x, min_y, max_y) = (min_x + 40.0, 5700000.0, 5700040.0);
+        AenderungenIntersection {
+            alt: "GR".to_string(),
+            neu: "WALD".to_string(),
+            flst_id: "12345600100001______".to_string(),
+            flst_id_part: flst_id_part.to_string(),
+            poly_cut: SvgPolygonInner {
+                outer_ring: SvgLine {
+                    points: vec![
+                        SvgPoint { x: min_x, y: min_y },
+                        SvgPoint { x: max_x, y: min_y },
+                        SvgPoint { x: max_x, y: max_y },
+                        SvgPoint { x: min_x, y: max_y },
+                        SvgPoint { x: min_x, y: min_y },
+                    ],
+                },
+                inner_rings: Vec::new(),
+            },
+        }
+    };
+    let splitflaechen = vec![splitflaeche(400000.0, "a"), splitflaeche(400060.0, "b")];
+
+    let mut risse = Risse::new();
+    risse.insert(
+        "riss_1".to_string(),
+        RissConfig {
+            lat: 5700020.0,
+            lon: 400050.0,
+            crs: crs.clone(),
+            width_mm: 200.0,
+            height_mm: 200.0,
+            scale: 1000.0,
+            ..Default::default()
+        },
+    );
+
+    let text = |id: usize, kuerzel: &str, farbe: &str, pos: SvgPoint, ref_pos: Option<SvgPoint>| GrafbatText {
+        id,
+        objektart: OBJEKTART_NUTZUNG_TEXT.to_string(),
+        pos,
+        ref_pos,
+        winkel: 0.0,
+        farbe: farbe.to_string(),
+        text: kuerzel.to_string(),
+    };
+    let mut grafbat = GrafbatOut::default();
+    for t in [
+        // both in Splitfläche "a": the first one gets it, the second the remaining "b"
+        text(2, "WALD", "0000ff", SvgPoint { x: 400030.0, y: 5700030.0 }, None),
+        text(3, "WALD", "0000ff", SvgPoint { x: 400010.0, y: 5700010.0 }, None),
+        // no "WALD" left
+        text(4, "WALD", "0000ff", SvgPoint { x: 400020.0, y: 5700020.0 }, None),
+        // outside, but the Bezugslinie points into "b"
+        text(5, "GR", "", SvgPoint { x: 400200.0, y: 5700020.0 }, Some(SvgPoint { x: 400080.0, y: 5700020.0 })),
+        // Riss 2 does not exist
+        text(6, "GR", "", SvgPoint { x: 400020.0, y: 5700020.0 }, None),
+    ] {
+        grafbat.texte.insert(t.id, t);
+    }
+    grafbat.texte.get_mut(&2).unwrap().winkel = 30.0;
+    grafbat.mengen = vec![
+        GrafbatMenge {
+            name: "RISS001-GESAMT".to_string(),
+            elemente: vec!["TE=2", "TE=3", "TE=4", "TE=5", "PK=7", "PK=8", "LI=2"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        },
+        GrafbatMenge {
+            name: "RISS002-GESAMT".to_string(),
+            elemente: vec!["TE=6".to_string()],
+        },
+    ];
+
+    let (risse, bericht) = import_grafbat_beschriftungen(&grafbat, &risse, &splitflaechen, &crs).unwrap();
+    assert_eq!(bericht.zugeordnet, 3);
+    assert_eq!(bericht.nicht_zugeordnet.len(), 2);
+    assert!(bericht.nicht_zugeordnet[0].starts_with("TE4 "));
+    assert!(bericht.nicht_zugeordnet[1].contains("Riss 2 existiert nicht"));
+
+    let b = &risse.get("riss_1").unwrap().beschriftungen;
+    let finde = |teil: &str, status: TextStatus| {
+        b.iter()
+            .find(|o| o.splitflaeche.as_ref().map(|s| s.flst_id_part.as_str()) == Some(teil) && o.status == status)
+            .unwrap()
+    };
+    assert_eq!(finde("a", TextStatus::New).pos, SvgPoint { x: 400030.0, y: 5700030.0 });
+    assert_eq!(finde("b", TextStatus::New).pos, SvgPoint { x: 400010.0, y: 5700010.0 });
+    assert_eq!(finde("a", TextStatus::New).winkel, 30.0);
+    let alt = finde("b", TextStatus::Old);
+    assert_eq!(alt.pos, SvgPoint { x: 400200.0, y: 5700020.0 });
+    assert_eq!(alt.ref_pos, Some(SvgPoint { x: 400080.0, y: 5700020.0 }));
+}
+
+#[test]
+fn test_grafbat_winkel() {
+    use crate::geograf::{
+        gon_to_winkel,
+        winkel_to_gon,
+    };
+
+    let crs = crate::crs::get_crs_by_proj_or_default(&crate::nas::default_etrs33());
+    for winkel in [0.0, 35.5, -30.0, 90.0, -89.9, 180.0] {
+        assert!((gon_to_winkel(winkel_to_gon(winkel)) - winkel).abs() < 1e-9);
+
+        let s = format!(
+            "TE2: ,1600.9104.4140,33412345.5,5712345.25,,,{gon},0,0,0,0,,0,,,,,,,n,,,0000ff",
+            gon = winkel_to_gon(winkel)
+        );
+        let g = parse_grafbat_out(&s, &crs).unwrap();
+        assert!((g.texte.get(&2).unwrap().winkel - winkel).abs() < 1e-9);
+    }
+}
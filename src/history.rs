@@ -0,0 +1,253 @@
+//! Undo / redo for `Aenderungen`: every edit (cleaning stage, fixup_polyline,
+//! lock / unlock, deleting or adding polygons, ...) is stored as the list of
+//! changed entries instead of a full copy of the `Aenderungen`.
+
+use crate::ui::{
+    Aenderungen,
+    FlstPartId,
+    GebaeudeLoeschen,
+    Kuerzel,
+    NewPolyId,
+    PolyNeu,
+};
+use serde_derive::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::BTreeMap;
+
+/// Number of steps kept, older steps are dropped
+pub const MAX_HISTORY_LEN: usize = 100;
+
+/// Change of a single entry: `alt == None` means the entry was added,
+/// `neu == None` means the entry was removed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "typ")]
+pub enum AenderungOp {
+    PolyNeu {
+        id: NewPolyId,
+        alt: Option<PolyNeu>,
+        neu: Option<PolyNeu>,
+    },
+    NaDefiniert {
+        id: FlstPartId,
+        alt: Option<Kuerzel>,
+        neu: Option<Kuerzel>,
+    },
+    GebaeudeLoeschen {
+        id: String,
+        alt: Option<GebaeudeLoeschen>,
+        neu: Option<GebaeudeLoeschen>,
+    },
+}
+
+impl AenderungOp {
+    fn invert(&self) -> Self {
+        match self {
+            AenderungOp::PolyNeu { id, alt, neu } => AenderungOp::PolyNeu {
+                id: id.clone(),
+                alt: neu.clone(),
+                neu: alt.clone(),
+            },
+            AenderungOp::NaDefiniert { id, alt, neu } => AenderungOp::NaDefiniert {
+                id: id.clone(),
+                alt: neu.clone(),
+                neu: alt.clone(),
+            },
+            AenderungOp::GebaeudeLoeschen { id, alt, neu } => AenderungOp::GebaeudeLoeschen {
+                id: id.clone(),
+                alt: neu.clone(),
+                neu: alt.clone(),
+            },
+        }
+    }
+
+    fn apply(&self, aenderungen: &mut Aenderungen) {
+        fn set<K: Ord + Clone, V: Clone>(map: &mut BTreeMap<K, V>, id: &K, neu: &Option<V>) {
+            match neu {
+                Some(v) => {
+                    map.insert(id.clone(), v.clone());
+                }
+                None => {
+                    map.remove(id);
+                }
+            }
+        }
+        match self {
+            AenderungOp::PolyNeu { id, neu, .. } => set(&mut aenderungen.na_polygone_neu, id, neu),
+            AenderungOp::NaDefiniert { id, neu, .. } => set(&mut aenderungen.na_definiert, id, neu),
+            AenderungOp::GebaeudeLoeschen { id, neu, .. } => {
+                set(&mut aenderungen.gebaeude_loeschen, id, neu)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Shown in the UI, e.g. "Änderungen bereinigen (Stufe 3)"
+    pub beschreibung: String,
+    pub ops: Vec<AenderungOp>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AenderungenHistory {
+    #[serde(default)]
+    pub undo: Vec<HistoryEntry>,
+    #[serde(default)]
+    pub redo: Vec<HistoryEntry>,
+}
+
+fn diff_map<K: Ord + Clone, V: Clone + PartialEq>(
+    alt: &BTreeMap<K, V>,
+    neu: &BTreeMap<K, V>,
+    op: impl Fn(K, Option<V>, Option<V>) -> AenderungOp,
+    ops: &mut Vec<AenderungOp>,
+) {
+    for (k, a) in alt.iter() {
+        match neu.get(k) {
+            Some(n) if n == a => {}
+            n => ops.push(op(k.clone(), Some(a.clone()), n.cloned())),
+        }
+    }
+    for (k, n) in neu.iter() {
+        if !alt.contains_key(k) {
+            ops.push(op(k.clone(), None, Some(n.clone())));
+        }
+    }
+}
+
+impl AenderungenHistory {
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty() && self.redo.is_empty()
+    }
+
+    /// Changed entries between two states of the `Aenderungen`
+    pub fn diff(alt: &Aenderungen, neu: &Aenderungen) -> Vec<AenderungOp> {
+        let mut ops = Vec::new();
+        diff_map(
+            &alt.na_polygone_neu,
+            &neu.na_polygone_neu,
+            |id, alt, neu| AenderungOp::PolyNeu { id, alt, neu },
+            &mut ops,
+        );
+        diff_map(
+            &alt.na_definiert,
+            &neu.na_definiert,
+            |id, alt, neu| AenderungOp::NaDefiniert { id, alt, neu },
+            &mut ops,
+        );
+        diff_map(
+            &alt.gebaeude_loeschen,
+            &neu.gebaeude_loeschen,
+            |id, alt, neu| AenderungOp::GebaeudeLoeschen { id, alt, neu },
+            &mut ops,
+        );
+        ops
+    }
+
+    /// Records the step `alt -> neu`, returns false if nothing changed.
+    /// A new step discards the redo stack.
+    pub fn record(&mut self, beschreibung: &str, alt: &Aenderungen, neu: &Aenderungen) -> bool {
+        let ops = Self::diff(alt, neu);
+        if ops.is_empty() {
+            return false;
+        }
+        self.undo.push(HistoryEntry {
+            beschreibung: beschreibung.to_string(),
+            ops,
+        });
+        if self.undo.len() > MAX_HISTORY_LEN {
+            let zu_viel = self.undo.len() - MAX_HISTORY_LEN;
+            self.undo.drain(..zu_viel);
+        }
+        self.redo.clear();
+        true
+    }
+
+    /// Reverts the last step, None if there is nothing to undo
+    pub fn undo(&mut self, aenderungen: &Aenderungen) -> Option<Aenderungen> {
+        let entry = self.undo.pop()?;
+        let mut a = aenderungen.clone();
+        for op in entry.ops.iter().rev() {
+            op.invert().apply(&mut a);
+        }
+        self.redo.push(entry);
+        Some(a)
+    }
+
+    /// Re-applies the last undone step, None if there is nothing to redo
+    pub fn redo(&mut self, aenderungen: &Aenderungen) -> Option<Aenderungen> {
+        let entry = self.redo.pop()?;
+        let mut a = aenderungen.clone();
+        for op in entry.ops.iter() {
+            op.apply(&mut a);
+        }
+        self.undo.push(entry);
+        Some(a)
+    }
+
+    /// Applies `f` to all polygons stored in the history, used to reproject
+    /// the history together with the `Aenderungen` when saving / loading
+    pub fn map_aenderungen(&self, f: impl Fn(&Aenderungen) -> Aenderungen) -> Self {
+        let map_entry = |e: &HistoryEntry| HistoryEntry {
+            beschreibung: e.beschreibung.clone(),
+            ops: e
+                .ops
+                .iter()
+                .map(|op| match op {
+                    AenderungOp::PolyNeu { id, alt, neu } => {
+                        let a = Aenderungen {
+                            na_polygone_neu: alt
+                                .iter()
+                                .map(|p| ("alt".to_string(), p.clone()))
+                                .chain(neu.iter().map(|p| ("neu".to_string(), p.clone())))
+                                .collect(),
+                            ..Default::default()
+                        };
+                        let mut a = f(&a);
+                        AenderungOp::PolyNeu {
+                            id: id.clone(),
+                            alt: a.na_polygone_neu.remove("alt"),
+                            neu: a.na_polygone_neu.remove("neu"),
+                        }
+                    }
+                    o => o.clone(),
+                })
+                .collect(),
+        };
+        Self {
+            undo: self.undo.iter().map(map_entry).collect(),
+            redo: self.redo.iter().map(map_entry).collect(),
+        }
+    }
+}
+
+#[test]
+fn test_aenderungen_history() {
+    let a0 = Aenderungen::default();
+    let mut a1 = a0.clone();
+    a1.na_definiert.insert("1-2".to_string(), "WALD".to_string());
+    a1.gebaeude_loeschen.insert(
+        "DE_1".to_string(),
+        GebaeudeLoeschen {
+            gebaeude_id: "DE_1".to_string(),
+            flst_id: vec!["1".to_string()],
+        },
+    );
+    let mut a2 = a1.clone();
+    a2.na_definiert.insert("1-2".to_string(), "GR".to_string());
+    a2.gebaeude_loeschen.clear();
+
+    let mut h = AenderungenHistory::default();
+    assert!(h.record("a", &a0, &a1));
+    assert!(h.record("b", &a1, &a2));
+    assert!(!h.record("c", &a2, &a2));
+
+    assert_eq!(h.undo(&a2), Some(a1.clone()));
+    assert_eq!(h.undo(&a1), Some(a0.clone()));
+    assert_eq!(h.undo(&a0), None);
+    assert_eq!(h.redo(&a0), Some(a1.clone()));
+    assert!(h.record("d", &a1, &a0));
+    assert_eq!(h.redo(&a0), None);
+}
@@ -0,0 +1,617 @@
+//! Import of already digitised change polygons (QGIS, GEOgraf, ...) into
+//! `Aenderungen.na_polygone_neu`: polygon Shapefiles, GeoJSON and closed DXF polylines.
+
+use crate::{
+    crs::CrsInfo,
+    nas::{
+        SvgLine,
+        SvgPoint,
+        SvgPolygon,
+        SvgPolygonInner,
+        UseRadians,
+        LATLON_STRING,
+    },
+    ui::{
+        Aenderungen,
+        Kuerzel,
+        NewPolyId,
+        PolyNeu,
+    },
+};
+use serde_derive::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Name of the DXF "attribute" that can be mapped to the Nutzung
+pub const DXF_LAYER_ATTRIBUT: &str = "LAYER";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportConfig {
+    /// Attribute (DBF column, GeoJSON property or "LAYER" for DXF) that
+    /// contains the Nutzung, as Kürzel or as Bezeichnung
+    #[serde(default)]
+    pub nutzung_spalte: Option<String>,
+    /// CRS of the imported data (proj string, EPSG code or AdV id), overrides
+    /// the .prj / GeoJSON "crs". Without both, Shapefile and DXF are assumed to
+    /// be in the project CRS and GeoJSON in WGS84 (RFC 7946).
+    #[serde(default)]
+    pub quell_crs: Option<String>,
+    /// Prefix for the ids of the new polygons ("np_1234" -> "np_1234_0", "np_1234_1", ...)
+    #[serde(default = "default_id_prefix")]
+    pub id_prefix: String,
+}
+
+fn default_id_prefix() -> String {
+    "np_import".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportMeldung {
+    pub datei: String,
+    /// Index of the feature / entity in the file
+    pub feature: usize,
+    pub grund: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportErgebnis {
+    pub aenderungen: Aenderungen,
+    pub importiert: Vec<NewPolyId>,
+    /// Features that were not imported
+    pub abgelehnt: Vec<ImportMeldung>,
+    /// Features that were imported, but e.g. without Nutzung
+    pub warnungen: Vec<ImportMeldung>,
+}
+
+impl ImportErgebnis {
+    pub fn get_zusammenfassung(&self) -> String {
+        format!(
+            "{} Polygone importiert, {} abgelehnt, {} Warnungen",
+            self.importiert.len(),
+            self.abgelehnt.len(),
+            self.warnungen.len()
+        )
+    }
+}
+
+/// Polygon as read from the file, before reprojection
+struct ImportFeature {
+    index: usize,
+    poly: Result<SvgPolygonInner, String>,
+    attribute: BTreeMap<String, String>,
+}
+
+struct ImportDatei {
+    name: String,
+    /// proj string of the data, None = unknown (project CRS is assumed)
+    crs: Option<String>,
+    features: Vec<ImportFeature>,
+}
+
+/// Imports all polygons from `dateien` (file name, contents) into the
+/// `aenderungen`, which must already be in the project CRS `target_crs`.
+/// Shapefiles are grouped by file stem (.shp + .dbf + .prj), ZIP files are unpacked.
+pub fn import_aenderungen(
+    dateien: &[(String, Vec<u8>)],
+    aenderungen: &Aenderungen,
+    target_crs: &str,
+    config: &ImportConfig,
+) -> Result<ImportErgebnis, String> {
+    let target = crate::crs::get_crs_by_proj_or_default(target_crs);
+    let quell_crs = match config.quell_crs.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(s) => Some(resolve_crs(s).ok_or_else(|| format!("Unbekanntes Koordinatensystem {s:?}"))?),
+    };
+
+    let mut ergebnis = ImportErgebnis {
+        aenderungen: aenderungen.clone(),
+        ..Default::default()
+    };
+
+    let dateien = unpack_zip_files(dateien);
+    let mut gelesen = Vec::new();
+    for (name, bytes) in dateien.iter() {
+        let lower = name.to_lowercase();
+        let datei = if lower.ends_with(".shp") {
+            let stem = &name[..name.len() - 4];
+            let find = |ext: &str| {
+                dateien
+                    .iter()
+                    .find(|(n, _)| n.len() == stem.len() + 4 && n.starts_with(stem) && n.to_lowercase().ends_with(ext))
+                    .map(|(_, b)| b.as_slice())
+            };
+            read_shp(name, bytes, find(".dbf"), find(".prj"))
+        } else if lower.ends_with(".geojson") || lower.ends_with(".json") {
+            read_geojson(name, bytes)
+        } else if lower.ends_with(".dxf") {
+            read_dxf(name, bytes)
+        } else {
+            continue;
+        };
+        match datei {
+            Ok(o) => gelesen.push(o),
+            Err(e) => ergebnis.abgelehnt.push(ImportMeldung {
+                datei: name.clone(),
+                feature: 0,
+                grund: e,
+            }),
+        }
+    }
+
+    if gelesen.is_empty() && ergebnis.abgelehnt.is_empty() {
+        return Err("Keine .shp, .geojson oder .dxf Datei gefunden".to_string());
+    }
+
+    let katalog = crate::get_nutzungsartenkatalog();
+    let mut next_id = 0;
+    for datei in gelesen {
+        let source = quell_crs.clone().or(datei.crs.clone());
+        for f in datei.features {
+            let meldung = |grund: String| ImportMeldung {
+                datei: datei.name.clone(),
+                feature: f.index,
+                grund,
+            };
+            let poly = match f.poly.and_then(|p| reproject_into_target(p, source.as_deref(), &target)) {
+                Ok(o) => o,
+                Err(e) => {
+                    ergebnis.abgelehnt.push(meldung(e));
+                    continue;
+                }
+            };
+
+            let mut nutzung = None;
+            if let Some(spalte) = config.nutzung_spalte.as_deref() {
+                let wert = f
+                    .attribute
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(spalte))
+                    .map(|(_, v)| v.trim())
+                    .unwrap_or_default();
+                nutzung = get_kuerzel(wert, &katalog);
+                if nutzung.is_none() {
+                    ergebnis.warnungen.push(meldung(format!(
+                        "Nutzung {wert:?} ({spalte}) nicht im Nutzungsartenkatalog, ohne Nutzung importiert"
+                    )));
+                }
+            }
+
+            let mut id = format!("{}_{next_id}", config.id_prefix);
+            while ergebnis.aenderungen.na_polygone_neu.contains_key(&id) {
+                next_id += 1;
+                id = format!("{}_{next_id}", config.id_prefix);
+            }
+            next_id += 1;
+
+            ergebnis.aenderungen.na_polygone_neu.insert(
+                id.clone(),
+                PolyNeu {
+                    poly: SvgPolygon::Old(poly),
+                    nutzung,
+                    locked: false,
+                },
+            );
+            ergebnis.importiert.push(id);
+        }
+    }
+
+    Ok(ergebnis)
+}
+
+/// proj string for a proj string, "EPSG:25833" / "25833" or AdV id, LATLON_STRING for 4326
+fn resolve_crs(s: &str) -> Option<String> {
+    let s = s.trim();
+    if s.starts_with('+') {
+        return Some(s.to_string());
+    }
+    let code = s
+        .rsplit(|c| c == ':' || c == '/')
+        .next()
+        .unwrap_or(s)
+        .trim();
+    match code.parse::<u32>() {
+        Ok(4326) | Ok(4258) => Some(LATLON_STRING.to_string()),
+        Ok(epsg) => crate::crs::get_crs_by_epsg(epsg).map(|c| c.proj),
+        Err(_) if s.to_uppercase().contains("CRS84") => Some(LATLON_STRING.to_string()),
+        Err(_) => crate::crs::get_crs_by_adv_id(s).map(|c| c.proj),
+    }
+}
+
+fn reproject_into_target(
+    poly: SvgPolygonInner,
+    source: Option<&str>,
+    target: &CrsInfo,
+) -> Result<SvgPolygonInner, String> {
+    if poly.outer_ring.points.len() < 4 {
+        return Err("Polygon hat weniger als 3 Punkte".to_string());
+    }
+    if poly.get_all_points().iter().any(|p| !p.x.is_finite() || !p.y.is_finite()) {
+        return Err("Ungültige Koordinaten".to_string());
+    }
+
+    let looks_projected = poly.outer_ring.points.iter().any(|p| p.x.abs() > 1000.0 || p.y.abs() > 1000.0);
+    let source = match source {
+        // GeoJSON without "crs" but with metric coordinates: same as project CRS
+        Some(LATLON_STRING) if looks_projected => None,
+        Some(s) if s == target.proj => None,
+        o => o,
+    };
+    // GEOgraf writes UTM eastings with the zone number in front (33412345.6)
+    let source = match source {
+        None => match target.with_zone_prefix() {
+            Some(prefixed) if poly.outer_ring.points.iter().all(|p| p.x > 1_000_000.0) => Some(prefixed.proj),
+            _ => None,
+        },
+        Some(s) => Some(s.to_string()),
+    };
+
+    let mut poly = match source {
+        None => poly,
+        Some(s) => {
+            let source_proj = proj4rs::Proj::from_proj_string(&s).map_err(|e| format!("{s}: {e}"))?;
+            let target_proj =
+                proj4rs::Proj::from_proj_string(&target.proj).map_err(|e| format!("{}: {e}", target.proj))?;
+            let use_radians = if s == LATLON_STRING {
+                UseRadians::ForSourceAndTarget
+            } else {
+                UseRadians::ForTarget
+            };
+            let reprojected = crate::nas::reproject_poly(&poly, &source_proj, &target_proj, use_radians, true);
+            if reprojected.outer_ring.points.len() != poly.outer_ring.points.len() {
+                return Err("Polygon konnte nicht umprojiziert werden".to_string());
+            }
+            reprojected
+        }
+    };
+
+    poly.correct_winding_order();
+    if poly.is_zero_area() {
+        return Err("Polygon hat keine Fläche".to_string());
+    }
+    Ok(poly)
+}
+
+fn get_kuerzel(wert: &str, katalog: &BTreeMap<String, crate::search::NutzungsArt>) -> Option<Kuerzel> {
+    if wert.is_empty() {
+        return None;
+    }
+    if let Some((k, _)) = katalog.iter().find(|(k, _)| k.eq_ignore_ascii_case(wert)) {
+        return Some(k.clone());
+    }
+    katalog
+        .iter()
+        .find(|(_, v)| v.bez.trim().eq_ignore_ascii_case(wert))
+        .map(|(k, _)| k.clone())
+}
+
+fn unpack_zip_files(dateien: &[(String, Vec<u8>)]) -> Vec<(String, Vec<u8>)> {
+    let mut v = Vec::new();
+    for (name, bytes) in dateien {
+        if !name.to_lowercase().ends_with(".zip") {
+            v.push((name.clone(), bytes.clone()));
+            continue;
+        }
+        for (dir, file, b) in crate::zip::read_files_from_zip(bytes, false, &[]) {
+            let file = match dir {
+                Some(d) => format!("{d}/{}", file.display()),
+                None => file.display().to_string(),
+            };
+            v.push((file, b));
+        }
+    }
+    v
+}
+
+fn closed_line(mut points: Vec<SvgPoint>) -> SvgLine {
+    if let (Some(first), Some(last)) = (points.first().copied(), points.last().copied()) {
+        if !first.equals(&last) {
+            points.push(first);
+        }
+    }
+    SvgLine { points }
+}
+
+fn read_shp(name: &str, shp: &[u8], dbf: Option<&[u8]>, prj: Option<&[u8]>) -> Result<ImportDatei, String> {
+    use dbase::FieldValue;
+    use shapefile::{
+        PolygonRing,
+        Shape,
+    };
+    use std::io::Cursor;
+
+    let crs = match prj {
+        None => None,
+        Some(prj) => {
+            let wkt = String::from_utf8_lossy(prj);
+            if wkt.trim_start().starts_with("GEOGCS") {
+                Some(LATLON_STRING.to_string())
+            } else {
+                Some(
+                    crate::crs::get_crs_by_prj_wkt(&wkt)
+                        .ok_or_else(|| format!("Koordinatensystem aus .prj nicht erkannt: {wkt}"))?
+                        .proj,
+                )
+            }
+        }
+    };
+
+    let shapes = shapefile::ShapeReader::new(Cursor::new(shp))
+        .and_then(|r| r.read())
+        .map_err(|e| format!("Fehler beim Lesen der .shp: {e}"))?;
+
+    let records = match dbf {
+        None => Vec::new(),
+        Some(dbf) => dbase::Reader::new(Cursor::new(dbf))
+            .and_then(|mut r| r.read())
+            .map_err(|e| format!("Fehler beim Lesen der .dbf: {e}"))?,
+    };
+
+    fn rings_to_polys<P: Copy>(rings: &[PolygonRing<P>], xy: impl Fn(&P) -> SvgPoint) -> Vec<SvgPolygonInner> {
+        let mut polys: Vec<SvgPolygonInner> = Vec::new();
+        for ring in rings {
+            let line = closed_line(ring.points().iter().map(&xy).collect());
+            match ring {
+                PolygonRing::Outer(_) => polys.push(SvgPolygonInner {
+                    outer_ring: line,
+                    inner_rings: Vec::new(),
+                }),
+                PolygonRing::Inner(_) => {
+                    if let Some(last) = polys.last_mut() {
+                        last.inner_rings.push(line);
+                    }
+                }
+            }
+        }
+        polys
+    }
+
+    let mut features = Vec::new();
+    for (index, shape) in shapes.iter().enumerate() {
+        let attribute = records
+            .get(index)
+            .map(|r| {
+                std::collections::HashMap::<String, FieldValue>::from(r.clone())
+                    .into_iter()
+                    .filter_map(|(k, v)| {
+                        let v = match v {
+                            FieldValue::Character(Some(s)) => s,
+                            FieldValue::Memo(s) => s,
+                            FieldValue::Numeric(Some(n)) => n.to_string(),
+                            FieldValue::Integer(i) => i.to_string(),
+                            _ => return None,
+                        };
+                        Some((k, v))
+                    })
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default();
+
+        let polys = match shape {
+            Shape::Polygon(p) => rings_to_polys(p.rings(), |p| SvgPoint { x: p.x, y: p.y }),
+            Shape::PolygonM(p) => rings_to_polys(p.rings(), |p| SvgPoint { x: p.x, y: p.y }),
+            Shape::PolygonZ(p) => rings_to_polys(p.rings(), |p| SvgPoint { x: p.x, y: p.y }),
+            other => {
+                features.push(ImportFeature {
+                    index,
+                    poly: Err(format!("Keine Fläche ({:?})", other.shapetype())),
+                    attribute,
+                });
+                continue;
+            }
+        };
+
+        for poly in polys {
+            features.push(ImportFeature {
+                index,
+                poly: Ok(poly),
+                attribute: attribute.clone(),
+            });
+        }
+    }
+
+    Ok(ImportDatei {
+        name: name.to_string(),
+        crs,
+        features,
+    })
+}
+
+fn read_geojson(name: &str, bytes: &[u8]) -> Result<ImportDatei, String> {
+    let json = serde_json::from_slice::<Value>(bytes).map_err(|e| format!("Ungültiges GeoJSON: {e}"))?;
+
+    // "crs" was removed in RFC 7946, but QGIS still writes it for non-WGS84 data
+    let crs = match json.pointer("/crs/properties/name").and_then(Value::as_str) {
+        Some(s) => Some(resolve_crs(s).ok_or_else(|| format!("Unbekanntes Koordinatensystem {s:?}"))?),
+        None => Some(LATLON_STRING.to_string()),
+    };
+
+    let features = match json.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => json
+            .get("features")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default(),
+        Some("Feature") => vec![json.clone()],
+        Some(_) => vec![serde_json::json!({ "type": "Feature", "geometry": json.clone() })],
+        None => return Err("GeoJSON ohne \"type\"".to_string()),
+    };
+
+    fn ring(v: &Value) -> Option<SvgLine> {
+        let points = v
+            .as_array()?
+            .iter()
+            .map(|c| {
+                let c = c.as_array()?;
+                Some(SvgPoint {
+                    x: c.first()?.as_f64()?,
+                    y: c.get(1)?.as_f64()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(closed_line(points))
+    }
+
+    fn polygon(v: &Value) -> Result<SvgPolygonInner, String> {
+        let rings = v
+            .as_array()
+            .ok_or_else(|| "Ungültige Koordinaten".to_string())?
+            .iter()
+            .map(|r| ring(r).ok_or_else(|| "Ungültige Koordinaten".to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut rings = rings.into_iter();
+        Ok(SvgPolygonInner {
+            outer_ring: rings.next().ok_or_else(|| "Polygon ohne Ring".to_string())?,
+            inner_rings: rings.collect(),
+        })
+    }
+
+    fn geometry(g: &Value) -> Vec<Result<SvgPolygonInner, String>> {
+        let coords = g.get("coordinates").unwrap_or(&Value::Null);
+        match g.get("type").and_then(Value::as_str) {
+            Some("Polygon") => vec![polygon(coords)],
+            Some("MultiPolygon") => match coords.as_array() {
+                Some(a) => a.iter().map(polygon).collect(),
+                None => vec![Err("Ungültige Koordinaten".to_string())],
+            },
+            Some("GeometryCollection") => g
+                .get("geometries")
+                .and_then(Value::as_array)
+                .map(|a| a.iter().flat_map(geometry).collect())
+                .unwrap_or_default(),
+            Some(t) => vec![Err(format!("Keine Fläche ({t})"))],
+            None => vec![Err("Feature ohne Geometrie".to_string())],
+        }
+    }
+
+    let mut result = Vec::new();
+    for (index, f) in features.iter().enumerate() {
+        let attribute = f
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|p| {
+                p.iter()
+                    .filter_map(|(k, v)| {
+                        let v = match v {
+                            Value::String(s) => s.clone(),
+                            Value::Number(n) => n.to_string(),
+                            _ => return None,
+                        };
+                        Some((k.clone(), v))
+                    })
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default();
+        for poly in geometry(f.get("geometry").unwrap_or(&Value::Null)) {
+            result.push(ImportFeature {
+                index,
+                poly,
+                attribute: attribute.clone(),
+            });
+        }
+    }
+
+    Ok(ImportDatei {
+        name: name.to_string(),
+        crs,
+        features: result,
+    })
+}
+
+fn read_dxf(name: &str, bytes: &[u8]) -> Result<ImportDatei, String> {
+    use dxf::entities::EntityType;
+
+    let drawing = dxf::Drawing::load(&mut std::io::Cursor::new(bytes))
+        .map_err(|e| format!("Fehler beim Lesen der DXF: {e}"))?;
+
+    let mut features = Vec::new();
+    for (index, entity) in drawing.entities().enumerate() {
+        let (points, closed) = match &entity.specific {
+            EntityType::LwPolyline(p) => (
+                p.vertices.iter().map(|v| SvgPoint { x: v.x, y: v.y }).collect::<Vec<_>>(),
+                p.is_closed(),
+            ),
+            EntityType::Polyline(p) => (
+                p.vertices()
+                    .map(|v| SvgPoint {
+                        x: v.location.x,
+                        y: v.location.y,
+                    })
+                    .collect::<Vec<_>>(),
+                p.is_closed(),
+            ),
+            // Texte, Punkte, Linien usw. gehören nicht zu den Änderungen
+            _ => continue,
+        };
+
+        let closed = closed
+            || match (points.first(), points.last()) {
+                (Some(a), Some(b)) => points.len() > 2 && a.equals(b),
+                _ => false,
+            };
+
+        let poly = if closed {
+            Ok(SvgPolygonInner {
+                outer_ring: closed_line(points),
+                inner_rings: Vec::new(),
+            })
+        } else {
+            Err("Polylinie ist nicht geschlossen".to_string())
+        };
+
+        features.push(ImportFeature {
+            index,
+            poly,
+            attribute: [(DXF_LAYER_ATTRIBUT.to_string(), entity.common.layer.clone())]
+                .into_iter()
+                .collect(),
+        });
+    }
+
+    Ok(ImportDatei {
+        name: name.to_string(),
+        // DXF has no CRS, GEOgraf / CAD data is in the project CRS
+        crs: None,
+        features,
+    })
+}
+
+#[test]
+fn test_import_geojson() {
+    let target = crate::nas::default_etrs33();
+    let geojson = r#"{
+        "type": "FeatureCollection",
+        "crs": { "type": "name", "properties": { "name": "urn:ogc:def:crs:EPSG::25833" } },
+        "features": [
+            { "type": "Feature", "properties": { "NUTZUNG": "xxx" },
+              "geometry": { "type": "Polygon", "coordinates": [[[400000, 5700000], [400010, 5700000], [400010, 5700010], [400000, 5700010]]] } },
+            { "type": "Feature", "properties": {},
+              "geometry": { "type": "LineString", "coordinates": [[400000, 5700000], [400010, 5700000]] } },
+            { "type": "Feature", "properties": {},
+              "geometry": { "type": "Polygon", "coordinates": [[[400000, 5700000], [400010, 5700000], [400020, 5700000]]] } }
+        ]
+    }"#;
+    let config = ImportConfig {
+        nutzung_spalte: Some("nutzung".to_string()),
+        quell_crs: None,
+        id_prefix: "np_test".to_string(),
+    };
+    let ergebnis = import_aenderungen(
+        &[("test.geojson".to_string(), geojson.as_bytes().to_vec())],
+        &Aenderungen::default(),
+        &target,
+        &config,
+    )
+    .unwrap();
+
+    assert_eq!(ergebnis.importiert, vec!["np_test_0".to_string()]);
+    assert_eq!(ergebnis.abgelehnt.len(), 2);
+    assert_eq!(ergebnis.abgelehnt[0].feature, 1);
+    assert_eq!(ergebnis.warnungen.len(), 1);
+
+    let p = ergebnis.aenderungen.na_polygone_neu.get("np_test_0").unwrap().poly.get_inner();
+    assert_eq!(p.outer_ring.points.len(), 5);
+    assert!((p.area_m2().abs() - 100.0).abs() < 0.01);
+}
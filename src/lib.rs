@@ -3,7 +3,7 @@ use crate::{
     ui::UiData,
 };
 use nas::{
-    default_etrs33, parse_nas_xml, NasXMLFile, NasXmlObjects, SplitNasXml, SvgLine, SvgPoint, SvgPolygon, SvgPolygonInner, TaggedPolygon
+    default_etrs33, NasXMLFile, NasXmlObjects, NasXmlQuadTree, SplitNasXml, SplitNasXmlQuadTree, SvgLine, SvgPoint, SvgPolygon, SvgPolygonInner, TaggedPolygon
 };
 use pdf::{
     reproject_aenderungen_back_into_latlon,
@@ -20,6 +20,12 @@ use serde_derive::{
     Deserialize,
     Serialize,
 };
+use history::AenderungenHistory;
+use optimize::BeschriftungOverride;
+use session::{
+    Session,
+    SessionHandle,
+};
 use std::collections::{
     BTreeMap,
     BTreeSet,
@@ -34,16 +40,26 @@ use uuid_wasm::{
 };
 use wasm_bindgen::prelude::*;
 use xlsx::FlstIdParsed;
-use xml::XmlNode;
 
+pub mod anschlussrisse;
+pub mod crs;
 pub mod csv;
 pub mod david;
+pub mod diff;
 pub mod geograf;
+pub mod grafbat;
+pub mod history;
+pub mod import;
 pub mod nas;
+pub mod oid;
 pub mod optimize;
 pub mod pdf;
 pub mod process;
+pub mod pruefung;
+pub mod rissplanung;
+pub mod runtime;
 pub mod search;
+pub mod session;
 pub mod ui;
 pub mod uuid_wasm;
 pub mod xlsx;
@@ -51,6 +67,8 @@ pub mod xml;
 pub mod xml_templates;
 pub mod zip;
 pub mod ops;
+#[cfg(test)]
+mod test_utils;
 
 pub const ARIAL_TTF: &[u8] = include_bytes!("./Arial.ttf");
 
@@ -60,13 +78,34 @@ pub fn get_new_poly_id() -> String {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct SaveFile {
+pub struct SaveFile {
     #[serde(skip_serializing_if = "Option::is_none", default)]
-    crs: Option<String>,
-    info: ProjektInfo,
-    risse: Risse,
-    csv: CsvDataType,
-    aenderungen: Aenderungen,
+    pub crs: Option<String>,
+    pub info: ProjektInfo,
+    pub risse: Risse,
+    pub csv: CsvDataType,
+    pub aenderungen: Aenderungen,
+    #[serde(skip_serializing_if = "AenderungenHistory::is_empty", default)]
+    pub history: AenderungenHistory,
+}
+
+impl SaveFile {
+    /// Reprojects Risse and Änderungen of the savefile back into LatLon
+    pub fn migrate_old(&self) -> Self {
+        let source_crs = self.crs.clone().unwrap_or(default_etrs33());
+        SaveFile {
+            crs: self.crs.clone(),
+            info: self.info.clone(),
+            csv: self.csv.clone(),
+            risse: self
+                .risse
+                .iter()
+                .map(|(k, v)| (k.clone(), v.migrate_old(&source_crs)))
+                .collect(),
+            aenderungen: self.aenderungen.migrate_old(&source_crs),
+            history: self.history.map_aenderungen(|a| a.migrate_old(&source_crs)),
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -75,19 +114,7 @@ pub fn lib_parse_savefile(savefile: String) -> String {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
-    let source_crs = sf.crs.clone().unwrap_or(default_etrs33());
-    serde_json::to_string(&SaveFile {
-        crs: sf.crs.clone(),
-        info: sf.info,
-        csv: sf.csv.clone(),
-        risse: sf
-            .risse
-            .iter()
-            .map(|(k, v)| (k.clone(), v.migrate_old(&source_crs)))
-            .collect(),
-        aenderungen: sf.aenderungen.migrate_old(&source_crs),
-    })
-    .unwrap_or_default()
+    serde_json::to_string(&sf.migrate_old()).unwrap_or_default()
 }
 
 #[wasm_bindgen]
@@ -97,6 +124,7 @@ pub fn format_savefile(
     csv: Option<String>,
     aenderungen: Option<String>,
     target_crs: Option<String>,
+    history: Option<String>,
 ) -> String {
     let info = serde_json::from_str::<ProjektInfo>(info.as_str()).unwrap_or_default();
     let risse = serde_json::from_str::<Risse>(&risse.unwrap_or_default()).unwrap_or_default();
@@ -108,7 +136,15 @@ pub fn format_savefile(
     .and_then(|s| reproject_aenderungen_into_target_space(&aenderungen, &s).ok())
     .unwrap_or(aenderungen)
     .migrate_new();
-    
+    let history = serde_json::from_str::<AenderungenHistory>(&history.unwrap_or_default())
+        .unwrap_or_default()
+        .map_aenderungen(|a| {
+            target_crs.as_deref()
+            .and_then(|s| reproject_aenderungen_into_target_space(a, s).ok())
+            .unwrap_or(a.clone())
+            .migrate_new()
+        });
+
     let savefile = SaveFile {
         crs: target_crs.clone(),
         info,
@@ -118,11 +154,452 @@ pub fn format_savefile(
             .collect(),
         csv: csv.migrate_new(),
         aenderungen: aenderungen,
+        history,
     };
 
     serde_json::to_string_pretty(&savefile).unwrap_or_default()
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DiffResult {
+    pub diff: crate::diff::AenderungenDiff,
+    pub geojson: String,
+}
+
+fn parse_diff_savefiles(
+    alt: &str,
+    neu: &str,
+    target_crs: &str,
+) -> Result<crate::diff::AenderungenDiff, String> {
+    let alt = serde_json::from_str::<SaveFile>(alt).map_err(|e| format!("alt: {e}"))?;
+    let neu = serde_json::from_str::<SaveFile>(neu).map_err(|e| format!("neu: {e}"))?;
+    crate::diff::diff_savefiles(&alt, &neu, target_crs)
+}
+
+/// Compares two savefiles, returns `{ diff, geojson }`
+#[wasm_bindgen]
+pub fn lib_diff_savefiles(alt: String, neu: String, target_crs: String) -> String {
+    let diff = match parse_diff_savefiles(&alt, &neu, &target_crs) {
+        Ok(o) => o,
+        Err(e) => return e,
+    };
+    let geojson = crate::diff::diff_to_geojson(&diff, &target_crs);
+    serde_json::to_string(&DiffResult { diff, geojson }).unwrap_or_default()
+}
+
+#[wasm_bindgen]
+pub fn lib_diff_savefiles_xlsx(alt: String, neu: String, target_crs: String) -> Vec<u8> {
+    let antragsnr = serde_json::from_str::<SaveFile>(&neu)
+        .map(|s| s.info.antragsnr)
+        .unwrap_or_default();
+    match parse_diff_savefiles(&alt, &neu, &target_crs) {
+        Ok(diff) => crate::diff::diff_to_xlsx(&diff, &antragsnr),
+        Err(e) => {
+            log_status(&e);
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GrafbatImportResult {
+    pub risse: Risse,
+    pub bericht: crate::grafbat::GrafbatImportBericht,
+}
+
+fn import_grafbat_internal(
+    grafbat: &[u8],
+    aenderungen: &Aenderungen,
+    risse: &Risse,
+    split_nas: &SplitNasXml,
+    nas_xml: &NasXMLFile,
+    csv: &CsvDataType,
+) -> Result<GrafbatImportResult, String> {
+    let crs = crate::crs::get_crs_by_proj_or_default(&split_nas.crs);
+    let grafbat = crate::grafbat::parse_grafbat_out(&crate::grafbat::decode_grafbat_out(grafbat), &crs)?;
+    let aenderungen = reproject_aenderungen_into_target_space(aenderungen, &split_nas.crs)?;
+    let splitflaechen = crate::geograf::calc_splitflaechen(&aenderungen, split_nas, nas_xml, csv);
+    let (risse, bericht) =
+        crate::grafbat::import_grafbat_beschriftungen(&grafbat, risse, &splitflaechen.0, &split_nas.crs)?;
+    log_status(&format!(
+        "GRAFBAT: {} Beschriftungen übernommen, {} nicht zugeordnet",
+        bericht.zugeordnet,
+        bericht.nicht_zugeordnet.len()
+    ));
+    Ok(GrafbatImportResult { risse, bericht })
+}
+
+/// Reads the texts corrected in GEOgraf back from a `*.GRAFBAT.out`,
+/// returns `{ risse, bericht }` with the overrides stored in the Risse
+#[wasm_bindgen]
+pub fn lib_import_grafbat(
+    grafbat: Vec<u8>,
+    aenderungen: String,
+    risse: String,
+    split_nas: String,
+    nas_xml: String,
+    csv_data: String,
+) -> String {
+    let aenderungen = match serde_json::from_str::<Aenderungen>(&aenderungen) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let risse = serde_json::from_str::<Risse>(&risse).unwrap_or_default();
+    let split_nas = match serde_json::from_str::<SplitNasXml>(&split_nas) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let nas_xml = match serde_json::from_str::<NasXMLFile>(&nas_xml) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let csv_data = serde_json::from_str::<CsvDataType>(&csv_data).unwrap_or_default();
+    match import_grafbat_internal(&grafbat, &aenderungen, &risse, &split_nas, &nas_xml, &csv_data) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+#[wasm_bindgen]
+pub fn session_import_grafbat(
+    session: SessionHandle,
+    grafbat: Vec<u8>,
+    risse: String,
+) -> String {
+    let risse = serde_json::from_str::<Risse>(&risse).unwrap_or_default();
+    let r = with_session(session, |s| {
+        import_grafbat_internal(
+            &grafbat,
+            &s.aenderungen,
+            &risse,
+            &s.nas_cut_original,
+            &s.nas_original,
+            &s.csv,
+        )
+    });
+    match r.and_then(|r| r) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RissBeschriftung {
+    /// Override for the current position, sent back to
+    /// [riss_beschriftung_verschieben] when the label is moved
+    pub beschriftung: BeschriftungOverride,
+    pub lat: f64,
+    pub lon: f64,
+    pub fixiert: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RissBeschriftungenResult {
+    pub texte: Vec<RissBeschriftung>,
+    /// Overrides of the Riss whose Splitfläche no longer exists
+    pub verwaist: Vec<String>,
+}
+
+fn get_riss_beschriftungen_internal(
+    riss: &RissConfig,
+    aenderungen: &Aenderungen,
+    split_nas: &SplitNasXml,
+    nas_xml: &NasXMLFile,
+    csv: &CsvDataType,
+    konfiguration: &Konfiguration,
+) -> Result<RissBeschriftungenResult, String> {
+    let aenderungen = reproject_aenderungen_into_target_space(aenderungen, &split_nas.crs)?;
+    let splitflaechen = crate::geograf::calc_splitflaechen(&aenderungen, split_nas, nas_xml, csv);
+    let optimized = crate::geograf::get_riss_texte_optimiert(
+        &splitflaechen.0,
+        split_nas,
+        nas_xml,
+        riss,
+        konfiguration,
+    )
+    .ok_or_else(|| "Ausdehnung des Risses ungültig".to_string())?;
+
+    let texte = optimized
+        .texte
+        .iter()
+        .map(|t| {
+            let latlon = reproject_point_back_into_latlon(&t.optimized.pos, &split_nas.crs)?;
+            Ok(RissBeschriftung {
+                beschriftung: BeschriftungOverride {
+                    splitflaeche: t.original.splitflaeche.clone(),
+                    kuerzel: t.original.kuerzel.clone(),
+                    status: t.original.status,
+                    anker: t.original.pos,
+                    pos: t.optimized.pos,
+                    ref_pos: None,
+                    winkel: t.winkel,
+                },
+                lat: latlon.y,
+                lon: latlon.x,
+                fixiert: riss.beschriftungen.iter().any(|o| o.matches(&t.original)),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let verwaist = crate::optimize::get_verwaiste_overrides(&riss.beschriftungen, &splitflaechen.0)
+        .iter()
+        .map(|o| o.get_beschreibung())
+        .collect();
+
+    Ok(RissBeschriftungenResult { texte, verwaist })
+}
+
+/// Optimized labels of one Riss for the label preview,
+/// returns `{ texte, verwaist }` with the positions in latlon
+#[wasm_bindgen]
+pub fn lib_get_riss_beschriftungen(
+    riss: String,
+    aenderungen: String,
+    split_nas: String,
+    nas_xml: String,
+    csv_data: String,
+    konfiguration: String,
+) -> String {
+    let riss = match serde_json::from_str::<RissConfig>(&riss) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let aenderungen = match serde_json::from_str::<Aenderungen>(&aenderungen) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let split_nas = match serde_json::from_str::<SplitNasXml>(&split_nas) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let nas_xml = match serde_json::from_str::<NasXMLFile>(&nas_xml) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let csv_data = serde_json::from_str::<CsvDataType>(&csv_data).unwrap_or_default();
+    let konfiguration = serde_json::from_str::<Konfiguration>(&konfiguration).unwrap_or_default();
+    match get_riss_beschriftungen_internal(
+        &riss,
+        &aenderungen,
+        &split_nas,
+        &nas_xml,
+        &csv_data,
+        &konfiguration,
+    ) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+#[wasm_bindgen]
+pub fn session_get_riss_beschriftungen(session: SessionHandle, riss: String) -> String {
+    let riss = match serde_json::from_str::<RissConfig>(&riss) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let r = with_session(session, |s| {
+        get_riss_beschriftungen_internal(
+            &riss,
+            &s.aenderungen,
+            &s.nas_cut_original,
+            &s.nas_original,
+            &s.csv,
+            &s.konfiguration,
+        )
+    });
+    match r.and_then(|r| r) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+/// Pins the label `beschriftung` (from [lib_get_riss_beschriftungen]) at
+/// `lat` / `lon`, returns the changed `RissConfig` as JSON
+#[wasm_bindgen]
+pub fn riss_beschriftung_verschieben(
+    riss: String,
+    beschriftung: String,
+    lat: f64,
+    lon: f64,
+    utm_crs: Option<String>,
+) -> String {
+    let utm_crs = utm_crs.unwrap_or_else(crate::nas::default_etrs33);
+    let mut riss = match serde_json::from_str::<RissConfig>(&riss) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let mut beschriftung = match serde_json::from_str::<BeschriftungOverride>(&beschriftung) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    beschriftung.pos = match crate::pdf::reproject_point_into_latlon(&SvgPoint { x: lon, y: lat }, &utm_crs) {
+        Ok(o) => o,
+        Err(e) => return e,
+    };
+    beschriftung.ref_pos = None;
+    riss.beschriftungen.retain(|b| !b.gleiches_label(&beschriftung));
+    riss.beschriftungen.push(beschriftung);
+    serde_json::to_string(&riss).unwrap_or_default()
+}
+
+/// Removes the override of the label `beschriftung`, so that it is
+/// placed by the optimizer again, returns the changed `RissConfig` as JSON
+#[wasm_bindgen]
+pub fn riss_beschriftung_zuruecksetzen(riss: String, beschriftung: String) -> String {
+    let mut riss = match serde_json::from_str::<RissConfig>(&riss) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let beschriftung = match serde_json::from_str::<BeschriftungOverride>(&beschriftung) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    riss.beschriftungen.retain(|b| !b.gleiches_label(&beschriftung));
+    serde_json::to_string(&riss).unwrap_or_default()
+}
+
+fn plane_risse_internal(
+    aenderungen: &Aenderungen,
+    split_nas: &SplitNasXml,
+    nas_xml: &NasXMLFile,
+    csv: &CsvDataType,
+    konfiguration: &Konfiguration,
+) -> Result<Vec<RissConfig>, String> {
+    let aenderungen = reproject_aenderungen_into_target_space(aenderungen, &split_nas.crs)?;
+    let splitflaechen = crate::geograf::calc_splitflaechen(&aenderungen, split_nas, nas_xml, csv);
+    let veraendert = splitflaechen
+        .0
+        .into_iter()
+        .filter(|s| s.alt != s.neu)
+        .collect::<Vec<_>>();
+    let gebaeude_ids = aenderungen
+        .gebaeude_loeschen
+        .values()
+        .map(|s| s.gebaeude_id.clone())
+        .collect::<BTreeSet<_>>();
+    let gebaeude = nas_xml
+        .ebenen
+        .get("AX_Gebaeude")
+        .map(|v| v.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|tp| tp.attributes.get("id").is_some_and(|id| gebaeude_ids.contains(id)))
+        .cloned()
+        .collect::<Vec<_>>();
+    let risse = crate::rissplanung::plane_risse(
+        &veraendert,
+        &gebaeude,
+        &split_nas.crs,
+        &konfiguration.rissplanung,
+    )?;
+    log_status(&format!("Rissplanung: {} Risse", risse.len()));
+    Ok(risse)
+}
+
+/// Plans the Risse for all changes (see [crate::rissplanung]),
+/// returns the list of `RissConfig` as JSON
+#[wasm_bindgen]
+pub fn lib_plane_risse(
+    aenderungen: String,
+    split_nas: String,
+    nas_xml: String,
+    csv_data: String,
+    konfiguration: String,
+) -> String {
+    let aenderungen = match serde_json::from_str::<Aenderungen>(&aenderungen) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let split_nas = match serde_json::from_str::<SplitNasXml>(&split_nas) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let nas_xml = match serde_json::from_str::<NasXMLFile>(&nas_xml) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let csv_data = serde_json::from_str::<CsvDataType>(&csv_data).unwrap_or_default();
+    let konfiguration = serde_json::from_str::<Konfiguration>(&konfiguration).unwrap_or_default();
+    match plane_risse_internal(&aenderungen, &split_nas, &nas_xml, &csv_data, &konfiguration) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+#[wasm_bindgen]
+pub fn session_plane_risse(session: SessionHandle) -> String {
+    let r = with_session(session, |s| {
+        plane_risse_internal(
+            &s.aenderungen,
+            &s.nas_cut_original,
+            &s.nas_original,
+            &s.csv,
+            &s.konfiguration,
+        )
+    });
+    match r.and_then(|r| r) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryResult {
+    pub aenderungen: Aenderungen,
+    pub history: AenderungenHistory,
+}
+
+/// Records the step `alt -> neu` and returns the new history
+#[wasm_bindgen]
+pub fn aenderungen_history_record(
+    history: String,
+    beschreibung: String,
+    alt: String,
+    neu: String,
+) -> String {
+    let mut history = serde_json::from_str::<AenderungenHistory>(&history).unwrap_or_default();
+    let alt = match serde_json::from_str::<Aenderungen>(&alt) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let neu = match serde_json::from_str::<Aenderungen>(&neu) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    history.record(&beschreibung, &alt, &neu);
+    serde_json::to_string(&history).unwrap_or_default()
+}
+
+fn aenderungen_undo_redo(history: &str, aenderungen: &str, redo: bool) -> String {
+    let mut history = serde_json::from_str::<AenderungenHistory>(history).unwrap_or_default();
+    let aenderungen = match serde_json::from_str::<Aenderungen>(aenderungen) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let neu = if redo {
+        history.redo(&aenderungen)
+    } else {
+        history.undo(&aenderungen)
+    };
+    serde_json::to_string(&HistoryResult {
+        aenderungen: neu.unwrap_or(aenderungen),
+        history,
+    })
+    .unwrap_or_default()
+}
+
+/// Returns `{ aenderungen, history }`, unchanged if there is nothing to undo
+#[wasm_bindgen]
+pub fn aenderungen_undo(history: String, aenderungen: String) -> String {
+    aenderungen_undo_redo(&history, &aenderungen, false)
+}
+
+/// Returns `{ aenderungen, history }`, unchanged if there is nothing to redo
+#[wasm_bindgen]
+pub fn aenderungen_redo(history: String, aenderungen: String) -> String {
+    aenderungen_undo_redo(&history, &aenderungen, true)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CleanStageResult {
     pub aenderungen: Aenderungen,
@@ -149,7 +626,8 @@ pub fn get_main_gemarkung(csv: &CsvDataType) -> usize {
 }
 
 #[wasm_bindgen]
-pub fn get_rissgebiet_geojson(poly: String, target_crs: String) -> String {
+pub fn get_rissgebiet_geojson(poly: String, target_crs: Option<String>) -> String {
+    let target_crs = target_crs.unwrap_or_else(crate::nas::default_etrs33);
     let s1 = serde_json::from_str::<SvgPolygonInner>(&poly.trim()).unwrap_or_default();
     let s1 = reproject_poly_back_into_latlon(s1, &target_crs);
     let v1 = vec![TaggedPolygon {
@@ -187,8 +665,9 @@ fn reproject_poly_back_into_latlon(rissgebiet: SvgPolygonInner, crs: &str) -> Sv
 }
 
 #[wasm_bindgen]
-pub fn get_problem_geojson() -> String {
-    let proj = "+proj=utm +ellps=GRS80 +units=m +no_defs +zone=33";
+pub fn get_problem_geojson(target_crs: Option<String>) -> String {
+    let target_crs = target_crs.unwrap_or_else(crate::nas::default_etrs33);
+    let proj = target_crs.as_str();
 
     let poly_string1: &str = "";
     let poly_string2: &str = "";
@@ -350,6 +829,61 @@ pub async fn export_pdf_overview(
         }
     };
     let aenderungen = serde_json::from_str::<Aenderungen>(&aenderungen.unwrap_or_default()).unwrap_or_default();
+    export_pdf_overview_internal(
+        &konfiguration,
+        &nas_original,
+        split_nas_xml,
+        &aenderungen,
+        &csv_data,
+        use_dgm,
+        use_background,
+    )
+    .await
+}
+
+#[wasm_bindgen]
+pub async fn session_export_pdf_overview(
+    session: SessionHandle,
+    use_dgm: bool,
+    use_background: bool,
+) -> Vec<u8> {
+    // cloned, because the session can't be borrowed across the await
+    let (konfiguration, nas_original, split_nas_xml, aenderungen, csv_data) = match with_session(session, |s| {
+        (
+            s.konfiguration.clone(),
+            s.nas_original.clone(),
+            s.nas_cut_original.clone(),
+            s.aenderungen.clone(),
+            s.csv.clone(),
+        )
+    }) {
+        Ok(o) => o,
+        Err(e) => {
+            log_status(&e);
+            return Vec::new();
+        }
+    };
+    export_pdf_overview_internal(
+        &konfiguration,
+        &nas_original,
+        split_nas_xml,
+        &aenderungen,
+        &csv_data,
+        use_dgm,
+        use_background,
+    )
+    .await
+}
+
+pub async fn export_pdf_overview_internal(
+    konfiguration: &Konfiguration,
+    nas_original: &NasXMLFile,
+    split_nas_xml: SplitNasXml,
+    aenderungen: &Aenderungen,
+    csv_data: &CsvDataType,
+    use_dgm: bool,
+    use_background: bool,
+) -> Vec<u8> {
     let aenderungen = reproject_aenderungen_back_into_latlon(&aenderungen, &split_nas_xml.crs).unwrap_or_default();
     let nas_migrated = nas_original.fortfuehren(&aenderungen, &split_nas_xml, &csv_data);
     let split_nas = if aenderungen != Aenderungen::default() {
@@ -372,18 +906,15 @@ pub async fn export_pdf_overview(
 
 #[wasm_bindgen]
 pub fn get_header_coords(rc: String, utm_crs: Option<String>) -> String {
-    let utm_crs =
-        utm_crs.unwrap_or_else(|| "+proj=utm +ellps=GRS80 +units=m +no_defs +zone=33".to_string());
+    let utm_crs = utm_crs.unwrap_or_else(crate::nas::default_etrs33);
 
     let rc = match serde_json::from_str::<RissConfig>(rc.as_str()) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
 
-    let header_width_mm = 175.0;
-    let header_height_mm = 35.0;
-    let header_width_m = header_width_mm * rc.scale as f64 / 1000.0;
-    let header_height_m = header_height_mm * rc.scale as f64 / 1000.0;
+    let header_width_m = crate::rissplanung::BLATTKOPF_BREITE_MM * rc.scale as f64 / 1000.0;
+    let header_height_m = crate::rissplanung::BLATTKOPF_HOEHE_MM * rc.scale as f64 / 1000.0;
 
     let extent = match rc.get_extent_special(&utm_crs) {
         Some(o) => o,
@@ -471,14 +1002,6 @@ pub fn lib_nutzungen_saeubern(
     nas_original: String,
     konfiguration: String,
 ) -> String {
-    let id = id.and_then(|s| {
-        if s.is_empty() {
-            None
-        } else {
-            Some(s.trim().to_string())
-        }
-    });
-
     let aenderungen = match serde_json::from_str::<Aenderungen>(aenderungen.as_str()) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
@@ -499,6 +1022,47 @@ pub fn lib_nutzungen_saeubern(
         Err(e) => return e.to_string(),
     };
 
+    nutzungen_saeubern_internal(
+        id,
+        &aenderungen,
+        &split_nas_xml,
+        &split_nas_xml.create_quadtree(),
+        &nas_original.create_quadtree(),
+        &konfiguration,
+    )
+}
+
+#[wasm_bindgen]
+pub fn session_nutzungen_saeubern(session: SessionHandle, id: Option<String>) -> String {
+    crate::session::with_session(session, |s| {
+        nutzungen_saeubern_internal(
+            id,
+            &s.aenderungen,
+            &s.nas_cut_original,
+            &s.nas_cut_original_quadtree,
+            &s.nas_original_quadtree,
+            &s.konfiguration,
+        )
+    })
+    .unwrap_or_else(|e| e)
+}
+
+fn nutzungen_saeubern_internal(
+    id: Option<String>,
+    aenderungen: &Aenderungen,
+    split_nas_xml: &SplitNasXml,
+    split_nas_quadtree: &SplitNasXmlQuadTree,
+    nas_quadtree: &NasXmlQuadTree,
+    konfiguration: &Konfiguration,
+) -> String {
+    let id = id.and_then(|s| {
+        if s.is_empty() {
+            None
+        } else {
+            Some(s.trim().to_string())
+        }
+    });
+
     let aenderungen =
         match reproject_aenderungen_into_target_space(&aenderungen, &split_nas_xml.crs) {
             Ok(o) => o,
@@ -519,15 +1083,15 @@ pub fn lib_nutzungen_saeubern(
             false,
         )
         .clean_stage2(1.0, 1.0, 10.0, false)
-        .clean_stage3(
-            &split_nas_xml,
+        .clean_stage3_with_quadtree(
+            split_nas_quadtree,
             &mut log,
             konfiguration.merge.stage2_maxdst_point,
             konfiguration.merge.stage2_maxdst_line,
             false,
         )
-        .clean_stage4(
-            &nas_original,
+        .clean_stage4_with_quadtree(
+            nas_quadtree,
             &mut log,
             konfiguration.merge.stage3_maxdst_line,
             konfiguration.merge.stage3_maxdst_line2,
@@ -594,6 +1158,50 @@ pub fn lib_get_aenderungen_clean(
     let csv_data =
         serde_json::from_str::<CsvDataType>(&csv.unwrap_or_default()).unwrap_or_default();
 
+    get_aenderungen_clean_internal(
+        id,
+        &aenderungen,
+        &split_nas_xml,
+        &nas_original,
+        &konfiguration,
+        &csv_data,
+        force,
+    )
+}
+
+#[wasm_bindgen]
+pub fn session_get_aenderungen_clean(
+    session: SessionHandle,
+    id: Option<String>,
+    force: bool,
+) -> String {
+    if force {
+        log_status("WARNING: ignoring locked status Aenderungen!");
+    }
+
+    crate::session::with_session(session, |s| {
+        get_aenderungen_clean_internal(
+            id,
+            &s.aenderungen,
+            &s.nas_cut_original,
+            &s.nas_original,
+            &s.konfiguration,
+            &s.csv,
+            force,
+        )
+    })
+    .unwrap_or_else(|e| e)
+}
+
+fn get_aenderungen_clean_internal(
+    id: Option<String>,
+    aenderungen: &Aenderungen,
+    split_nas_xml: &SplitNasXml,
+    nas_original: &NasXMLFile,
+    konfiguration: &Konfiguration,
+    csv_data: &CsvDataType,
+    force: bool,
+) -> String {
     let aenderungen =
         match reproject_aenderungen_into_target_space(&aenderungen, &split_nas_xml.crs) {
             Ok(o) => o,
@@ -716,6 +1324,50 @@ pub async fn aenderungen_zu_geograf(
     .await
 }
 
+#[wasm_bindgen]
+pub async fn session_aenderungen_zu_geograf(
+    session: SessionHandle,
+    projekt_info: String,
+    risse: String,
+    render_hintergrund_vorschau: bool,
+    use_dgm: bool,
+) -> Vec<u8> {
+    log_status_clear();
+    log_status("Starte Export nach GEOgraf...");
+
+    let projekt_info =
+        serde_json::from_str::<ProjektInfo>(projekt_info.as_str()).unwrap_or_default();
+    let risse = serde_json::from_str::<Risse>(&risse).unwrap_or_default();
+    let (split_nas_xml, nas_xml, konfiguration, aenderungen, csv_data) = match with_session(session, |s| {
+        (
+            s.nas_cut_original.clone(),
+            s.nas_original.clone(),
+            s.konfiguration.clone(),
+            s.aenderungen.clone(),
+            s.csv.clone(),
+        )
+    }) {
+        Ok(o) => o,
+        Err(e) => {
+            log_status(&e);
+            return Vec::new();
+        }
+    };
+
+    crate::geograf::export_aenderungen_geograf(
+        &split_nas_xml,
+        &nas_xml,
+        &projekt_info,
+        &konfiguration,
+        &aenderungen,
+        &risse,
+        &csv_data,
+        render_hintergrund_vorschau,
+        use_dgm,
+    )
+    .await
+}
+
 #[wasm_bindgen]
 pub fn aenderungen_zu_nas_xml(
     aenderungen: String,
@@ -749,28 +1401,145 @@ pub fn aenderungen_zu_nas_xml(
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
-    crate::david::aenderungen_zu_nas_xml(&aenderungen, &nas_xml, &split_nas, &csv_data, &xml_objects)
+    crate::david::aenderungen_zu_nas_xml(&aenderungen, &nas_xml, &split_nas, &csv_data, &xml_objects)
+}
+
+#[wasm_bindgen]
+pub fn session_aenderungen_zu_nas_xml(session: SessionHandle) -> String {
+    log_status_clear();
+    let r = with_session(session, |s| {
+        let aenderungen = reproject_aenderungen_into_target_space(&s.aenderungen, &s.nas_original.crs)?;
+        Ok::<_, String>(crate::david::aenderungen_zu_nas_xml(
+            &aenderungen,
+            &s.nas_original,
+            &s.nas_cut_original,
+            &s.csv,
+            &s.xml_objects,
+        ))
+    });
+    match r.and_then(|r| r) {
+        Ok(o) => o,
+        Err(e) => e,
+    }
+}
+
+#[wasm_bindgen]
+pub fn aenderungen_zu_david(
+    datum: String,
+    aenderungen: String,
+    nas_xml: String,
+    split_nas: String,
+    xml_objects: String,
+    csv_data: String,
+    projekt_info: String,
+    konfiguration: String,
+) -> String {
+    log_status_clear();
+    let datum = match chrono::DateTime::parse_from_rfc3339(&datum) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let projekt_info = match serde_json::from_str::<ProjektInfo>(&projekt_info) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let konfiguration = match serde_json::from_str::<Konfiguration>(&konfiguration) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let aenderungen = match serde_json::from_str::<Aenderungen>(aenderungen.as_str()) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let nas_xml = match serde_json::from_str::<NasXMLFile>(&nas_xml) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let split_nas = match serde_json::from_str::<SplitNasXml>(&split_nas) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let aenderungen = match reproject_aenderungen_into_target_space(&aenderungen, &nas_xml.crs) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let xml_objects = match serde_json::from_str::<NasXmlObjects>(&xml_objects) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let csv_data = match serde_json::from_str::<CsvDataType>(&csv_data) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let antragskopf = match crate::david::Antragskopf::new(&projekt_info, &konfiguration.antrag, &nas_xml.crs, &datum) {
+        Ok(o) => o,
+        Err(e) => return e,
+    };
+    match crate::david::aenderungen_zu_fa_xml(
+        &aenderungen,
+        &nas_xml,
+        &split_nas,
+        &csv_data,
+        &xml_objects,
+        &antragskopf,
+        &konfiguration.signaturen,
+        &datum,
+    ) {
+        Ok(o) => o,
+        Err(e) => e,
+    }
 }
 
 #[wasm_bindgen]
-pub fn aenderungen_zu_david(
+pub fn session_aenderungen_zu_david(
+    session: SessionHandle,
     datum: String,
-    aenderungen: String,
-    nas_xml: String,
-    split_nas: String,
-    xml_objects: String,
-    csv_data: String,
+    projekt_info: String,
 ) -> String {
     log_status_clear();
     let datum = match chrono::DateTime::parse_from_rfc3339(&datum) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
-    let aenderungen = match serde_json::from_str::<Aenderungen>(aenderungen.as_str()) {
+    let projekt_info = match serde_json::from_str::<ProjektInfo>(&projekt_info) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
-    let nas_xml = match serde_json::from_str::<NasXMLFile>(&nas_xml) {
+    let r = with_session(session, |s| {
+        let aenderungen = reproject_aenderungen_into_target_space(&s.aenderungen, &s.nas_original.crs)?;
+        let antragskopf = crate::david::Antragskopf::new(
+            &projekt_info,
+            &s.konfiguration.antrag,
+            &s.nas_original.crs,
+            &datum,
+        )?;
+        crate::david::aenderungen_zu_fa_xml(
+            &aenderungen,
+            &s.nas_original,
+            &s.nas_cut_original,
+            &s.csv,
+            &s.xml_objects,
+            &antragskopf,
+            &s.konfiguration.signaturen,
+            &datum,
+        )
+    });
+    match r.and_then(|r| r) {
+        Ok(o) => o,
+        Err(e) => e,
+    }
+}
+
+/// Prüfprotokoll as JSON, run by the UI before the GEOgraf / DAVID export
+#[wasm_bindgen]
+pub fn lib_pruefe_aenderungen(
+    aenderungen: String,
+    split_nas: String,
+    nas_xml: String,
+    csv_data: String,
+    konfiguration: String,
+) -> String {
+    let aenderungen = match serde_json::from_str::<Aenderungen>(&aenderungen) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
@@ -778,23 +1547,101 @@ pub fn aenderungen_zu_david(
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
+    let nas_xml = match serde_json::from_str::<NasXMLFile>(&nas_xml) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let csv_data = serde_json::from_str::<CsvDataType>(&csv_data).unwrap_or_default();
+    let konfiguration = serde_json::from_str::<Konfiguration>(&konfiguration).unwrap_or_default();
     let aenderungen = match reproject_aenderungen_into_target_space(&aenderungen, &nas_xml.crs) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
-    let xml_objects = match serde_json::from_str::<NasXmlObjects>(&xml_objects) {
+    let pruefprotokoll = crate::pruefung::pruefe_vor_export(
+        &aenderungen,
+        &split_nas,
+        &nas_xml,
+        &csv_data,
+        &konfiguration.pruefung,
+    );
+    serde_json::to_string(&pruefprotokoll).unwrap_or_default()
+}
+
+#[wasm_bindgen]
+pub fn session_pruefe_aenderungen(session: SessionHandle) -> String {
+    let r = with_session(session, |s| {
+        let aenderungen = reproject_aenderungen_into_target_space(&s.aenderungen, &s.nas_original.crs)?;
+        Ok::<_, String>(crate::pruefung::pruefe_vor_export(
+            &aenderungen,
+            &s.nas_cut_original,
+            &s.nas_original,
+            &s.csv,
+            &s.konfiguration.pruefung,
+        ))
+    });
+    match r.and_then(|r| r) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+/// Imports Shapefile / GeoJSON / DXF polygons as new Änderungen.
+/// `dateien`: JSON list of `[name, [bytes]]`, returns the `ImportErgebnis` as JSON
+#[wasm_bindgen]
+pub fn lib_import_aenderungen(
+    dateien: String,
+    aenderungen: String,
+    target_crs: String,
+    config: String,
+) -> String {
+    let dateien = match serde_json::from_str::<Vec<(String, Vec<u8>)>>(&dateien) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
-    let csv_data = match serde_json::from_str::<CsvDataType>(&csv_data) {
+    let aenderungen = match serde_json::from_str::<Aenderungen>(&aenderungen) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let config = serde_json::from_str::<crate::import::ImportConfig>(&config).unwrap_or_default();
+    match import_aenderungen_internal(&dateien, &aenderungen, &target_crs, &config) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+#[wasm_bindgen]
+pub fn session_import_aenderungen(session: SessionHandle, dateien: String, config: String) -> String {
+    let dateien = match serde_json::from_str::<Vec<(String, Vec<u8>)>>(&dateien) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
-    crate::david::aenderungen_zu_fa_xml(&aenderungen, &nas_xml, &split_nas, &csv_data, &xml_objects, &datum)
+    let config = serde_json::from_str::<crate::import::ImportConfig>(&config).unwrap_or_default();
+    let r = with_session(session, |s| {
+        import_aenderungen_internal(&dateien, &s.aenderungen, &s.nas_cut_original.crs, &config)
+    });
+    match r.and_then(|r| r) {
+        Ok(o) => serde_json::to_string(&o).unwrap_or_default(),
+        Err(e) => e,
+    }
+}
+
+fn import_aenderungen_internal(
+    dateien: &[(String, Vec<u8>)],
+    aenderungen: &Aenderungen,
+    target_crs: &str,
+    config: &crate::import::ImportConfig,
+) -> Result<crate::import::ImportErgebnis, String> {
+    let aenderungen = reproject_aenderungen_into_target_space(aenderungen, target_crs)?;
+    let mut ergebnis = crate::import::import_aenderungen(dateien, &aenderungen, target_crs, config)?;
+    log_status(&format!("Import: {}", ergebnis.get_zusammenfassung()));
+    // the UI keeps the Änderungen in lat / lon
+    ergebnis.aenderungen = reproject_aenderungen_back_into_latlon(&ergebnis.aenderungen, target_crs)?;
+    Ok(ergebnis)
 }
 
 #[wasm_bindgen]
-pub fn get_geojson_fuer_neue_polygone(aenderungen: String, target_crs: String) -> String {
+pub fn get_geojson_fuer_neue_polygone(aenderungen: String, target_crs: Option<String>) -> String {
+    let target_crs = target_crs.unwrap_or_else(crate::nas::default_etrs33);
     #[derive(Debug, Clone, Serialize, Deserialize)]
     struct NeuePolygoneGeoJson {
         nutzung_definiert: bool,
@@ -850,13 +1697,23 @@ pub fn get_flurstuecke_in_extent(
     nas_xml: String,
 ) -> String {
 
-    let crs = default_etrs33();
     let nas = match serde_json::from_str::<NasXMLFile>(&nas_xml) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     };
+    flurstuecke_in_extent_internal(&nas, &map_bounds)
+}
+
+#[wasm_bindgen]
+pub fn session_get_flurstuecke_in_extent(session: SessionHandle, map_bounds: String) -> String {
+    crate::session::with_session(session, |s| {
+        flurstuecke_in_extent_internal(&s.nas_projected, &map_bounds)
+    })
+    .unwrap_or_else(|e| e)
+}
 
-    let s = match serde_json::from_str::<MapBounds>(&map_bounds) {
+fn flurstuecke_in_extent_internal(nas: &NasXMLFile, map_bounds: &str) -> String {
+    let s = match serde_json::from_str::<MapBounds>(map_bounds) {
         Ok(MapBounds { _northEast,_southWest }) => {
             quadtree_f32::Rect {
                 min_x: _southWest.lng,
@@ -878,11 +1735,32 @@ pub fn get_polyline_guides_in_current_bounds(
     map_bounds: String,
 ) -> String {
     let crs = crs.unwrap_or_else(|| default_etrs33());
-    let mut pl = Vec::new();
     let split_fs = serde_json::from_str::<SplitNasXml>(&split_flurstuecke).unwrap_or_default();
-    let aenderungen = serde_json::from_str::<Aenderungen>(&aenderungen).unwrap_or_default();
-    let aenderungen = reproject_aenderungen_back_into_latlon(&aenderungen, &crs).unwrap_or(aenderungen);
-    match serde_json::from_str::<MapBounds>(&map_bounds) {
+    polyline_guides_internal(&split_fs, &crs, &aenderungen, &map_bounds)
+}
+
+#[wasm_bindgen]
+pub fn session_get_polyline_guides_in_current_bounds(
+    session: SessionHandle,
+    aenderungen: String,
+    map_bounds: String,
+) -> String {
+    crate::session::with_session(session, |s| {
+        polyline_guides_internal(&s.nas_cut_projected, &s.nas_original.crs, &aenderungen, &map_bounds)
+    })
+    .unwrap_or_else(|e| e)
+}
+
+fn polyline_guides_internal(
+    split_fs: &SplitNasXml,
+    crs: &str,
+    aenderungen: &str,
+    map_bounds: &str,
+) -> String {
+    let mut pl = Vec::new();
+    let aenderungen = serde_json::from_str::<Aenderungen>(aenderungen).unwrap_or_default();
+    let aenderungen = reproject_aenderungen_back_into_latlon(&aenderungen, crs).unwrap_or(aenderungen);
+    match serde_json::from_str::<MapBounds>(map_bounds) {
         Ok(MapBounds {
             _northEast,
             _southWest,
@@ -928,7 +1806,8 @@ pub struct LatLng {
 }
 
 #[wasm_bindgen]
-pub fn fixup_polyline_rissgebiet(points: String, crs: String) -> String {
+pub fn fixup_polyline_rissgebiet(points: String, crs: Option<String>) -> String {
+    let crs = crs.unwrap_or_else(crate::nas::default_etrs33);
     let points = serde_json::from_str::<Vec<LatLng>>(&points).unwrap_or_default();
     match fixup_polyline_internal(&points).map(|s| project_poly_into_target_crs(s, &crs)) {
         Some(s) => serde_json::to_string(&s).unwrap_or_default(),
@@ -988,11 +1867,48 @@ pub fn fixup_polyline(
     let points = serde_json::from_str::<Vec<LatLng>>(&points).unwrap_or_default();
     let aenderungen = serde_json::from_str::<Aenderungen>(&aenderungen).unwrap_or_default();
     let konfiguration = serde_json::from_str::<Konfiguration>(&config).unwrap_or_default();
-    
+
+    fixup_polyline_aenderungen(
+        &points,
+        id,
+        aenderungen,
+        &split_fs.crs,
+        &split_fs.create_quadtree(),
+        &nas_xml.create_quadtree(),
+        &konfiguration,
+    )
+}
+
+#[wasm_bindgen]
+pub fn session_fixup_polyline(session: SessionHandle, points: String, id: String) -> String {
+    let points = serde_json::from_str::<Vec<LatLng>>(&points).unwrap_or_default();
+    crate::session::with_session(session, |s| {
+        fixup_polyline_aenderungen(
+            &points,
+            id,
+            s.aenderungen.clone(),
+            &s.nas_cut_original.crs,
+            &s.nas_cut_original_quadtree,
+            &s.nas_original_quadtree,
+            &s.konfiguration,
+        )
+    })
+    .unwrap_or_else(|e| e)
+}
+
+fn fixup_polyline_aenderungen(
+    points: &[LatLng],
+    id: String,
+    aenderungen: Aenderungen,
+    crs: &str,
+    split_nas_quadtree: &SplitNasXmlQuadTree,
+    nas_quadtree: &NasXmlQuadTree,
+    konfiguration: &Konfiguration,
+) -> String {
     let force = false;
-    if let Some(s) = fixup_polyline_internal(&points).map(|s| project_poly_into_target_crs(s, &split_fs.crs)) {
+    if let Some(s) = fixup_polyline_internal(&points).map(|s| project_poly_into_target_crs(s, crs)) {
         
-        let mut aenderungen = match reproject_aenderungen_into_target_space(&aenderungen, &split_fs.crs) {
+        let mut aenderungen = match reproject_aenderungen_into_target_space(&aenderungen, crs) {
             Ok(o) => o,
             Err(_) => return serde_json::to_string(&aenderungen).unwrap_or_default(),
         };
@@ -1016,16 +1932,16 @@ pub fn fixup_polyline(
 
         let aenderungen = aenderungen.clean_stage2(1.0, 1.0, 10.0, force);
 
-        let aenderungen = aenderungen.clean_stage3(
-            &split_fs,
+        let aenderungen = aenderungen.clean_stage3_with_quadtree(
+            split_nas_quadtree,
             &mut Vec::new(),
             konfiguration.merge.stage2_maxdst_point,
             konfiguration.merge.stage2_maxdst_line,
             force,
         );
 
-        let aenderungen = aenderungen.clean_stage4(
-            &nas_xml,
+        let aenderungen = aenderungen.clean_stage4_with_quadtree(
+            nas_quadtree,
             &mut Vec::new(),
             konfiguration.merge.stage3_maxdst_line,
             konfiguration.merge.stage3_maxdst_line2,
@@ -1147,6 +2063,32 @@ pub fn ui_render_project_content(
     }
 }
 
+#[wasm_bindgen]
+pub fn session_render_project_content(
+    session: SessionHandle,
+    projektinfo: String,
+    risse: String,
+    uidata: String,
+) -> String {
+    let projektinfo = serde_json::from_str::<ProjektInfo>(&projektinfo).unwrap_or_default();
+    let risse = serde_json::from_str::<Risse>(&risse).unwrap_or_default();
+    let uidata = UiData::from_string(&uidata);
+    crate::session::with_session(session, |s| {
+        if uidata.secondary_content.unwrap_or_default() {
+            return crate::ui::render_secondary_content(&s.aenderungen);
+        }
+        crate::ui::render_project_content(
+            &projektinfo,
+            &risse,
+            &s.csv,
+            &s.aenderungen,
+            &uidata,
+            &s.nas_cut_projected,
+        )
+    })
+    .unwrap_or_else(|e| e)
+}
+
 #[wasm_bindgen]
 pub fn get_geojson_polygon(s: String) -> String {
     let flst = match serde_json::from_str::<SvgPolygonInner>(&s) {
@@ -1160,13 +2102,13 @@ pub fn get_geojson_polygon(s: String) -> String {
 }
 
 #[wasm_bindgen]
-pub fn get_fit_bounds(s: String) -> String {
+pub fn get_fit_bounds(s: String, crs: Option<String>) -> String {
     let flst = match serde_json::from_str::<SvgPolygon>(&s) {
         Ok(o) => o,
         Err(e) => return e.to_string(),
     }.get_inner();
-    let crs = "+proj=utm +ellps=GRS80 +units=m +no_defs +zone=33";
-    let flst = reproject_poly_back_into_latlon(flst, crs);
+    let crs = crs.unwrap_or_else(crate::nas::default_etrs33);
+    let flst = reproject_poly_back_into_latlon(flst, &crs);
     let bounds = flst.get_fit_bounds();
     serde_json::to_string(&bounds).unwrap_or_default()
 }
@@ -1232,10 +2174,12 @@ pub fn search_for_gebauede(s: String, gebaeude_id: String) -> String {
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct LoadNasReturn {
     pub log: Vec<String>,
-    pub xml_parsed: Vec<XmlNode>,
-    pub xml_objects: NasXmlObjects,
-    pub nas_original: NasXMLFile,
-    pub nas_cut_original: SplitNasXml,
+    /// Handle of the [crate::session::Session] holding the same data in wasm memory
+    #[serde(default)]
+    pub session: SessionHandle,
+    /// CRS of the original NAS data, the original data itself stays in the session
+    #[serde(default)]
+    pub crs: String,
     pub nas_projected: NasXMLFile,
     pub nas_cut_projected: SplitNasXml,
 }
@@ -1261,14 +2205,8 @@ struct NasParseError {
     log: Vec<String>,
 }
 
-#[wasm_bindgen]
-pub fn load_nas_xml(s: String, style: String) -> String {
-    log_status("load_nas_xml 1");
-    let konfiguration = match serde_json::from_str::<Konfiguration>(&style) {
-        Ok(o) => o,
-        Err(e) => return e.to_string(),
-    };
-    log_status("konfiguration ok");
+/// Object types that are read from the NAS XML, everything else is skipped
+pub fn get_nas_ebenen_whitelist() -> BTreeSet<String> {
     let mut t = crate::get_nutzungsartenkatalog_ebenen().values().cloned().collect::<BTreeSet<_>>();
     t.insert("AX_BauRaumOderBodenordnungsrecht".to_string());
     t.insert("AX_Flurstueck".to_string());
@@ -1277,33 +2215,95 @@ pub fn load_nas_xml(s: String, style: String) -> String {
     t.insert("AX_BauwerkImVerkehrsbereich".to_string());
     t.insert("AX_SonstigesBauwerkOderSonstigeEinrichtung".to_string());
     t.insert("AX_BauwerkOderAnlageFuerIndustrieUndGewerbe".to_string());
+    t
+}
 
-    let mut log = Vec::new();
+#[wasm_bindgen]
+pub fn load_nas_xml(s: String, style: String) -> String {
+    log_status("load_nas_xml 1");
+    let konfiguration = match serde_json::from_str::<Konfiguration>(&style) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    log_status("konfiguration ok");
+    let t = get_nas_ebenen_whitelist();
     log_status(&format!("parsing XML: types = {t:?}"));
 
-    let (xml_parsed, xml_objects, nas_original) = match serde_json::from_str::<NasXMLFile>(&s) {
-        Ok(o) => (Vec::new(), NasXmlObjects::default(), o),
-        Err(_) => {
-            let xml_parsed = match crate::xml::parse_xml_string(&s, &mut log) {
-                Ok(o) => o,
-                Err(e) => {
-                    return serde_json::to_string(&NasParseError {
-                        error: format!("XML parse error: {e:?}"),
-                        log: log,
-                    })
-                    .unwrap_or_default()
-                }
-            };
-            log_status("xml parsed");
-            let xml_objects = crate::nas::parse_nas_xml_objects(&xml_parsed);
-            log_status("xml objects parsed");
-            let nas_original = match crate::nas::parse_nas_xml(xml_parsed.clone(), &t) {
-                Ok(o) => o,
-                Err(e) => {
-                    return serde_json::to_string(&NasParseError { error: e, log: log }).unwrap_or_default()
-                }
+    let parsed = match serde_json::from_str::<NasXMLFile>(&s) {
+        Ok(o) => Ok((o, NasXmlObjects::default())),
+        Err(_) => crate::nas::parse_nas_xml(&s, &t, konfiguration.nas.arc_chord_tolerance_m),
+    };
+    load_nas_xml_return(parsed, konfiguration)
+}
+
+/// NAS delivered as several XML files (one per Flur / tile), JSON `[[dateiname, xml], ...]`
+#[wasm_bindgen]
+pub fn load_nas_xml_files(files: String, style: String) -> String {
+    let konfiguration = match serde_json::from_str::<Konfiguration>(&style) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let files = match serde_json::from_str::<Vec<(String, String)>>(&files) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let t = get_nas_ebenen_whitelist();
+    let parsed = crate::nas::parse_nas_xml_files(&files, &t, konfiguration.nas.arc_chord_tolerance_m);
+    load_nas_xml_return(parsed, konfiguration)
+}
+
+/// NAS delivered as ZIP (e.g. from the GeoBroker), all .xml files in the ZIP are merged
+#[wasm_bindgen]
+pub fn load_nas_zip(zip: Vec<u8>, style: String) -> String {
+    let konfiguration = match serde_json::from_str::<Konfiguration>(&style) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    let files = read_nas_files_from_zip(&zip);
+    log_status(&format!("{} NAS-Dateien in ZIP", files.len()));
+    let t = get_nas_ebenen_whitelist();
+    let parsed = crate::nas::parse_nas_xml_files(&files, &t, konfiguration.nas.arc_chord_tolerance_m);
+    load_nas_xml_return(parsed, konfiguration)
+}
+
+/// JSON `[[dateiname, xml], ...]` of the NAS files in a ZIP, so that ZIP
+/// archives and single XML files can be passed to [load_nas_xml_files] together
+#[wasm_bindgen]
+pub fn read_nas_zip(zip: Vec<u8>) -> String {
+    serde_json::to_string(&read_nas_files_from_zip(&zip)).unwrap_or_default()
+}
+
+/// Returns (path in ZIP, decoded XML) of all .xml files, sorted by path
+pub fn read_nas_files_from_zip(zip: &[u8]) -> Vec<(String, String)> {
+    let mut files = crate::zip::read_files_from_zip(zip, false, &[])
+        .into_iter()
+        .filter(|(_, name, _)| {
+            name.extension()
+                .map(|e| e.to_string_lossy().eq_ignore_ascii_case("xml"))
+                .unwrap_or(false)
+        })
+        .map(|(dir, name, bytes)| {
+            let name = match dir {
+                Some(d) => format!("{d}/{}", name.display()),
+                None => name.display().to_string(),
             };
-            (xml_parsed, xml_objects, nas_original)
+            (name, decode(bytes))
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files
+}
+
+fn load_nas_xml_return(
+    parsed: Result<(NasXMLFile, NasXmlObjects), String>,
+    konfiguration: Konfiguration,
+) -> String {
+    let mut log = Vec::new();
+
+    let (nas_original, xml_objects) = match parsed {
+        Ok(o) => o,
+        Err(e) => {
+            return serde_json::to_string(&NasParseError { error: e, log: log }).unwrap_or_default()
         }
     };
 
@@ -1333,16 +2333,68 @@ pub fn load_nas_xml(s: String, style: String) -> String {
     log_status("nas cut projected ok");
     crate::nas::fixup_flst_groesse(&nas_cut_original, &mut nas_cut_projected);
     log_status("NAS XML ok!");
-    serde_json::to_string(&LoadNasReturn {
+    let session = crate::session::new_session_handle();
+    let ret = LoadNasReturn {
         log,
-        xml_parsed,
-        nas_original,
-        nas_cut_original,
+        session,
+        crs: nas_original.crs.clone(),
         nas_projected,
         nas_cut_projected,
-        xml_objects,
-    })
-    .unwrap_or_default()
+    };
+    let json = serde_json::to_string(&ret).unwrap_or_default();
+    crate::session::insert_session(
+        session,
+        Session::new(
+            konfiguration,
+            nas_original,
+            nas_cut_original,
+            ret.nas_projected,
+            ret.nas_cut_projected,
+            xml_objects,
+        ),
+    );
+    json
+}
+
+/// Replaces the Konfiguration of the session, the NAS data is kept
+#[wasm_bindgen]
+pub fn session_set_konfiguration(session: SessionHandle, konfiguration: String) -> String {
+    let konfiguration = match serde_json::from_str::<Konfiguration>(&konfiguration) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    match crate::session::with_session(session, |s| s.set_konfiguration(konfiguration)) {
+        Ok(()) => String::new(),
+        Err(e) => e,
+    }
+}
+
+/// Replaces the Änderungen (lat / lon) of the session, called after every edit
+#[wasm_bindgen]
+pub fn session_set_aenderungen(session: SessionHandle, aenderungen: String) -> String {
+    let aenderungen = match serde_json::from_str::<Aenderungen>(&aenderungen) {
+        Ok(o) => o,
+        Err(e) => return e.to_string(),
+    };
+    match crate::session::with_session(session, |s| s.set_aenderungen(aenderungen)) {
+        Ok(()) => String::new(),
+        Err(e) => e,
+    }
+}
+
+/// Replaces the CSV data of the session, called after import or editing
+#[wasm_bindgen]
+pub fn session_set_csv(session: SessionHandle, csv: String) -> String {
+    let csv = serde_json::from_str::<CsvDataType>(&csv).unwrap_or_default();
+    match crate::session::with_session(session, |s| s.set_csv(csv)) {
+        Ok(()) => String::new(),
+        Err(e) => e,
+    }
+}
+
+#[wasm_bindgen]
+pub fn session_free(session: SessionHandle) {
+    crate::session::remove_session(session);
 }
 
 #[wasm_bindgen]
@@ -1414,17 +2466,27 @@ pub fn parse_csv_dataset_to_json(
     delimiter: String,
     ignore_firstline: String,
 ) -> String {
-    let csv = decode(csv);
-    let csv_daten = match crate::csv::parse_csv(
-        &csv,
-        &id_col,
-        &nutzung_col,
-        &eigentuemer_col,
-        &delimiter,
-        ignore_firstline == "true",
-    ) {
+    let csv_daten = if csv.starts_with(b"PK\x03\x04") {
+        crate::csv::parse_xlsx(
+            &csv,
+            &id_col,
+            &nutzung_col,
+            &eigentuemer_col,
+            ignore_firstline == "true",
+        )
+    } else {
+        crate::csv::parse_csv(
+            &decode(csv),
+            &id_col,
+            &nutzung_col,
+            &eigentuemer_col,
+            &delimiter,
+            ignore_firstline == "true",
+        )
+    };
+    let csv_daten = match csv_daten {
         Ok(o) => o,
-        Err(e) => return e,
+        Err(e) => return serde_json::to_string(&e).unwrap_or_default(),
     };
     serde_json::to_string(&csv_daten).unwrap_or_default()
 }
@@ -1446,22 +2508,43 @@ struct KonfigurationLayerAlle {
 }
 
 #[wasm_bindgen]
-pub fn edit_konfiguration_layer_alle(konfiguration: String, xml_nas: String) -> String {
-    let mut config = serde_json::from_str::<Konfiguration>(&konfiguration).unwrap_or_default();
-    let nas_projected = serde_json::from_str::<Vec<XmlNode>>(&xml_nas).unwrap_or_default();
+pub fn edit_konfiguration_layer_alle(konfiguration: String, xml_objects: String) -> String {
+    let config = serde_json::from_str::<Konfiguration>(&konfiguration).unwrap_or_default();
+    let xml_objects = serde_json::from_str::<NasXmlObjects>(&xml_objects).unwrap_or_default();
+    konfiguration_layer_alle(config, &xml_objects)
+}
 
-    let mut log = Vec::new();
+#[wasm_bindgen]
+pub fn session_edit_konfiguration_layer_alle(session: SessionHandle, konfiguration: String) -> String {
+    let config = serde_json::from_str::<Konfiguration>(&konfiguration).unwrap_or_default();
+    match with_session(session, |s| konfiguration_layer_alle(config, &s.xml_objects)) {
+        Ok(o) => o,
+        Err(e) => e,
+    }
+}
 
-    let kuerzel = crate::xml::get_all_nodes_in_tree(&nas_projected)
-        .iter()
-        .filter(|n| n.node_type.starts_with("AX_"))
-        .map(|n| n.node_type.clone())
-        .collect::<BTreeSet<_>>();
+fn konfiguration_layer_alle(mut config: Konfiguration, xml_objects: &NasXmlObjects) -> String {
+    let mut log = Vec::new();
 
-    let nas_parsed_complete = match parse_nas_xml(nas_projected, &kuerzel) {
-        Ok(s) => s,
-        Err(_) => NasXMLFile::default(),
-    };
+    let mut nas_parsed_complete = NasXMLFile::default();
+    for (id, o) in xml_objects.objects.iter() {
+        if !o.member_type.starts_with("AX_") {
+            continue;
+        }
+        let mut attributes = o.extra_attribute.clone();
+        attributes.insert("id".to_string(), id.clone());
+        attributes.insert("AX_Ebene".to_string(), o.member_type.clone());
+        for p in o.poly.iter() {
+            nas_parsed_complete
+                .ebenen
+                .entry(o.member_type.clone())
+                .or_insert_with(|| Vec::new())
+                .push(TaggedPolygon {
+                    poly: p.clone(),
+                    attributes: attributes.clone(),
+                });
+        }
+    }
 
     let tp_count = nas_parsed_complete
         .ebenen
@@ -1531,6 +2614,29 @@ pub fn edit_konfiguration_layer_neu(konfiguration: String, layer_type: String) -
     serde_json::to_string(&config).unwrap_or_default()
 }
 
+/// Adds an empty signature for the Kürzel (if not yet in the catalog); an empty
+/// Kürzel only fills in the default catalog
+#[wasm_bindgen]
+pub fn edit_konfiguration_signatur_neu(konfiguration: String, kuerzel: String) -> String {
+    let mut config = serde_json::from_str::<Konfiguration>(&konfiguration).unwrap_or_default();
+    let kuerzel = kuerzel.trim();
+    if !kuerzel.is_empty() {
+        config
+            .signaturen
+            .0
+            .entry(kuerzel.to_string())
+            .or_insert_with(|| crate::pdf::SignaturStil {
+                typ: crate::pdf::SignaturTyp::Flaeche,
+                signaturnummer: String::new(),
+                art: String::new(),
+                positionierungsregel: None,
+                pdf_linien: Vec::new(),
+                pdf_abstand_mm: 25.0,
+            });
+    }
+    serde_json::to_string(&config).unwrap_or_default()
+}
+
 #[wasm_bindgen]
 pub fn edit_konfiguration_move_layer(
     konfiguration: String,
@@ -1549,6 +2655,9 @@ pub fn edit_konfiguration_move_layer(
                 config.pdf.nutzungsarten.remove(ebene_id.as_str());
                 config.pdf.layer_ordnung.retain(|s| *s != ebene_id);
             }
+            "signaturen" => {
+                config.signaturen.0.remove(ebene_id.as_str());
+            }
             _ => {}
         },
         "move-up" => match layer_type.as_str() {
@@ -1659,3 +2768,57 @@ pub fn decode(bytes: Vec<u8>) -> String {
     let _ = text_decoder.decode_to_string(&bytes[..], &mut decoded, true);
     decoded
 }
+
+#[test]
+fn test_import_aenderungen_bestehende_bleiben() {
+    let target = crate::nas::default_etrs33();
+    let points = vec![
+        SvgPoint { x: 13.5, y: 51.4 },
+        SvgPoint { x: 13.501, y: 51.4 },
+        SvgPoint { x: 13.501, y: 51.401 },
+        SvgPoint { x: 13.5, y: 51.401 },
+        SvgPoint { x: 13.5, y: 51.4 },
+    ];
+    let mut aenderungen = Aenderungen::default();
+    aenderungen.na_polygone_neu.insert(
+        "alt".to_string(),
+        PolyNeu {
+            poly: SvgPolygon::Old(SvgPolygonInner {
+                outer_ring: SvgLine { points: points.clone() },
+                inner_rings: Vec::new(),
+            }),
+            nutzung: Some("WALD".to_string()),
+            locked: false,
+        },
+    );
+
+    let geojson = r#"{
+        "type": "FeatureCollection",
+        "crs": { "type": "name", "properties": { "name": "urn:ogc:def:crs:EPSG::25833" } },
+        "features": [
+            { "type": "Feature", "properties": {},
+              "geometry": { "type": "Polygon", "coordinates": [[[400000, 5700000], [400010, 5700000], [400010, 5700010], [400000, 5700010]]] } }
+        ]
+    }"#;
+    let config = crate::import::ImportConfig {
+        nutzung_spalte: None,
+        quell_crs: None,
+        id_prefix: "np_test".to_string(),
+    };
+    let ergebnis = import_aenderungen_internal(
+        &[("test.geojson".to_string(), geojson.as_bytes().to_vec())],
+        &aenderungen,
+        &target,
+        &config,
+    )
+    .unwrap();
+
+    assert_eq!(ergebnis.importiert, vec!["np_test_0".to_string()]);
+    let alt = ergebnis.aenderungen.na_polygone_neu.get("alt").unwrap().poly.get_inner();
+    assert_eq!(alt.outer_ring.points.len(), points.len());
+    for (a, b) in alt.outer_ring.points.iter().zip(points.iter()) {
+        assert!((a.x - b.x).abs() < 1e-7 && (a.y - b.y).abs() < 1e-7, "{a:?} != {b:?}");
+    }
+    let neu = ergebnis.aenderungen.na_polygone_neu.get("np_test_0").unwrap().poly.get_inner();
+    assert!(neu.outer_ring.points.iter().all(|p| p.x.abs() < 180.0 && p.y.abs() < 90.0));
+}
@@ -0,0 +1,280 @@
+//! Native command-line interface: loads a project savefile and a NAS XML file
+//! and writes the GEOgraf / DAVID / NAS / Übersicht exports to disk without a browser.
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+use tnviewer::{
+    csv::CsvDataType,
+    nas::{
+        NasXMLFile,
+        NasXmlObjects,
+        SplitNasXml,
+    },
+    pdf::{
+        reproject_aenderungen_into_target_space,
+        Konfiguration,
+    },
+    uuid_wasm::log_status,
+    SaveFile,
+};
+
+const USAGE: &str = "\
+tnviewer - Export von Änderungen ohne Browser
+
+VERWENDUNG:
+    tnviewer --projekt <PROJEKT.json> --nas <NAS.xml|NAS.zip> [OPTIONEN]
+
+OPTIONEN:
+    --projekt <DATEI>         Projektdatei (gespeichert aus dem Browser)
+    --nas <DATEI>             NAS-XML-Datei oder ZIP mit mehreren NAS-Dateien,
+                              mehrfach angeben für mehrere Teile (Flure / Kacheln)
+    --konfiguration <DATEI>   Konfiguration als JSON (optional)
+    --out <ORDNER>            Zielordner (Standard: aktueller Ordner)
+    --datum <RFC3339>         Datum für den DAVID-Fortführungsauftrag (Standard: jetzt)
+    --nur <LISTE>             Nur bestimmte Exporte, kommagetrennt:
+                              geograf,david,nas,uebersicht (Standard: alle)
+    -h, --help                Diese Hilfe anzeigen
+";
+
+#[derive(Debug, Default)]
+struct CliArgs {
+    projekt: PathBuf,
+    nas: Vec<PathBuf>,
+    konfiguration: Option<PathBuf>,
+    out: PathBuf,
+    datum: Option<String>,
+    exporte: Vec<String>,
+}
+
+const ALLE_EXPORTE: &[&str] = &["geograf", "david", "nas", "uebersicht"];
+
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut projekt = None;
+    let mut nas = Vec::new();
+    let mut konfiguration = None;
+    let mut out = None;
+    let mut datum = None;
+    let mut exporte = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("Fehlender Wert für {arg}"))
+        };
+        match arg.as_str() {
+            "--projekt" => projekt = Some(PathBuf::from(value()?)),
+            "--nas" => nas.push(PathBuf::from(value()?)),
+            "--konfiguration" => konfiguration = Some(PathBuf::from(value()?)),
+            "--out" => out = Some(PathBuf::from(value()?)),
+            "--datum" => datum = Some(value()?),
+            "--nur" => {
+                let v = value()?
+                    .split(",")
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>();
+                if let Some(unbekannt) = v.iter().find(|s| !ALLE_EXPORTE.contains(&s.as_str())) {
+                    return Err(format!("Unbekannter Export: {unbekannt}"));
+                }
+                exporte = Some(v);
+            }
+            other => return Err(format!("Unbekanntes Argument: {other}")),
+        }
+    }
+
+    Ok(CliArgs {
+        projekt: projekt.ok_or_else(|| "--projekt fehlt".to_string())?,
+        nas: if nas.is_empty() {
+            return Err("--nas fehlt".to_string());
+        } else {
+            nas
+        },
+        konfiguration,
+        out: out.unwrap_or_else(|| PathBuf::from(".")),
+        datum,
+        exporte: exporte.unwrap_or_else(|| ALLE_EXPORTE.iter().map(|s| s.to_string()).collect()),
+    })
+}
+
+fn read_to_string(path: &Path) -> Result<String, String> {
+    std::fs::read(path)
+        .map(tnviewer::decode)
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn write_file(dir: &Path, name: &str, bytes: &[u8]) -> Result<(), String> {
+    let path = dir.join(name);
+    std::fs::write(&path, bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+    log_status(&format!("OK: {} geschrieben", path.display()));
+    Ok(())
+}
+
+struct NasGeladen {
+    nas_original: NasXMLFile,
+    nas_cut_original: SplitNasXml,
+    xml_objects: NasXmlObjects,
+}
+
+fn load_nas(paths: &[PathBuf], konfiguration: &Konfiguration) -> Result<NasGeladen, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        let is_zip = path
+            .extension()
+            .map(|e| e.to_string_lossy().eq_ignore_ascii_case("zip"))
+            .unwrap_or(false);
+        if is_zip {
+            let bytes = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            files.extend(tnviewer::read_nas_files_from_zip(&bytes));
+        } else {
+            files.push((path.display().to_string(), read_to_string(path)?));
+        }
+    }
+    let mut log = Vec::new();
+    let (nas_original, xml_objects) = tnviewer::nas::parse_nas_xml_files(
+        &files,
+        &tnviewer::get_nas_ebenen_whitelist(),
+        konfiguration.nas.arc_chord_tolerance_m,
+    )?;
+    let nas_cut_original = tnviewer::nas::split_xml_flurstuecke_inner(&nas_original, &mut log)?;
+    Ok(NasGeladen {
+        nas_original,
+        nas_cut_original,
+        xml_objects,
+    })
+}
+
+fn run(args: CliArgs) -> Result<(), String> {
+    let projekt = read_to_string(&args.projekt)?;
+    let projekt = serde_json::from_str::<SaveFile>(&projekt)
+        .map_err(|e| format!("{}: {e}", args.projekt.display()))?
+        .migrate_old();
+
+    let konfiguration = match args.konfiguration.as_ref() {
+        Some(p) => serde_json::from_str::<Konfiguration>(&read_to_string(p)?)
+            .map_err(|e| format!("{}: {e}", p.display()))?,
+        None => Konfiguration::default(),
+    };
+
+    let datum = match args.datum.as_deref() {
+        Some(d) => chrono::DateTime::parse_from_rfc3339(d).map_err(|e| format!("--datum: {e}"))?,
+        None => chrono::Local::now().fixed_offset(),
+    };
+
+    log_status(&format!("Lade NAS-XML ({} Dateien)...", args.nas.len()));
+    let NasGeladen {
+        nas_original,
+        nas_cut_original,
+        xml_objects,
+    } = load_nas(&args.nas, &konfiguration)?;
+    log_status("NAS XML ok!");
+
+    let pruefprotokoll = tnviewer::pruefung::pruefe_vor_export(
+        &reproject_aenderungen_into_target_space(&projekt.aenderungen, &nas_original.crs)?,
+        &nas_cut_original,
+        &nas_original,
+        &projekt.csv,
+        &konfiguration.pruefung,
+    );
+    log_status(&pruefprotokoll.to_text());
+
+    std::fs::create_dir_all(&args.out).map_err(|e| format!("{}: {e}", args.out.display()))?;
+
+    let mut antragsnr = projekt.info.antragsnr.trim().to_string();
+    if antragsnr.is_empty() {
+        antragsnr = "Aenderungen".to_string();
+    }
+    let csv_data: &CsvDataType = &projekt.csv;
+    let exporte = &args.exporte;
+
+    if exporte.iter().any(|s| s == "geograf") {
+        log_status("Starte Export nach GEOgraf...");
+        let zip = futures::executor::block_on(tnviewer::geograf::export_aenderungen_geograf(
+            &nas_cut_original,
+            &nas_original,
+            &projekt.info,
+            &konfiguration,
+            &projekt.aenderungen,
+            &projekt.risse,
+            csv_data,
+            false,
+            false,
+        ));
+        write_file(&args.out, &format!("{antragsnr}.Aenderungen.GEOgraf.zip"), &zip)?;
+    }
+
+    if exporte.iter().any(|s| s == "david" || s == "nas") {
+        let aenderungen =
+            reproject_aenderungen_into_target_space(&projekt.aenderungen, &nas_original.crs)?;
+
+        if exporte.iter().any(|s| s == "david") {
+            let antragskopf = tnviewer::david::Antragskopf::new(
+                &projekt.info,
+                &konfiguration.antrag,
+                &nas_original.crs,
+                &datum,
+            )?;
+            let xml = tnviewer::david::aenderungen_zu_fa_xml(
+                &aenderungen,
+                &nas_original,
+                &nas_cut_original,
+                csv_data,
+                &xml_objects,
+                &antragskopf,
+                &konfiguration.signaturen,
+                &datum,
+            )?;
+            write_file(&args.out, &format!("{antragsnr}.DAVID.FA.xml"), xml.as_bytes())?;
+        }
+
+        if exporte.iter().any(|s| s == "nas") {
+            let xml = tnviewer::david::aenderungen_zu_nas_xml(
+                &aenderungen,
+                &nas_original,
+                &nas_cut_original,
+                csv_data,
+                &xml_objects,
+            );
+            write_file(&args.out, &format!("{antragsnr}.Fortgefuehrt.NAS.xml"), xml.as_bytes())?;
+        }
+    }
+
+    if exporte.iter().any(|s| s == "uebersicht") {
+        let zip = futures::executor::block_on(tnviewer::export_pdf_overview_internal(
+            &konfiguration,
+            &nas_original,
+            nas_cut_original.clone(),
+            &projekt.aenderungen,
+            csv_data,
+            false,
+            false,
+        ));
+        write_file(&args.out, &format!("{antragsnr}.Uebersicht.zip"), &zip)?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{USAGE}");
+        return;
+    }
+
+    let args = match parse_args(&args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("Fehler: {e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(args) {
+        eprintln!("Fehler: {e}");
+        std::process::exit(1);
+    }
+}
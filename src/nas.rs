@@ -1744,92 +1744,516 @@ impl Ord for SvgPoint {
     }
 }
 
-/// Parse the XML, returns [AX_Gebauede => (Polygon)]
-pub fn parse_nas_xml(xml: Vec<XmlNode>, whitelist: &BTreeSet<String>) -> Result<NasXMLFile, String> {
-    // CRS parsen
-
+/// Parse the XML, returns [AX_Gebauede => (Polygon)] for the whitelisted types and
+/// the `member` objects of the whitelisted types and the presentation objects
+/// (AP_*, needed for the DAVID export). Streams through the file, only one
+/// `member` at a time is held as [XmlNode]s, other members are skipped unparsed.
+pub fn parse_nas_xml(
+    xml: &str,
+    whitelist: &BTreeSet<String>,
+    arc_tolerance: f64,
+) -> Result<(NasXMLFile, NasXmlObjects), String> {
     let mut crs: Option<String> = None;
-    let crs_nodes = get_all_nodes_in_subtree(&xml, "AA_Koordinatenreferenzsystemangaben");
-    for c in crs_nodes {
-        match get_all_nodes_in_subtree(&c.children, "standard").first() {
-            Some(XmlNode { text: Some(s), .. }) if s == "true" => {}
-            _ => continue,
-        }
-        let nodes = get_all_nodes_in_subtree(&c.children, "crs");
-        let cs = match nodes.first() {
-            Some(s) => s,
-            None => continue,
-        };
-        crs = cs.attributes.get("href").cloned();
-    }
+    let mut objekte = BTreeMap::new();
+    let mut objects = BTreeMap::new();
+
+    crate::xml::stream_xml_subtrees(
+        xml,
+        |node_type| node_type == "member" || node_type == "AA_Koordinatenreferenzsystemangaben",
+        |parent, node_type| {
+            parent == "member" && !whitelist.contains(node_type) && !node_type.starts_with("AP_")
+        },
+        |node| {
+            if node.node_type == "AA_Koordinatenreferenzsystemangaben" {
+                if let Some(s) = get_standard_crs(&node) {
+                    crs = Some(s);
+                }
+                return;
+            }
+
+            let o_node = match node.children.first() {
+                Some(s) => s,
+                None => return,
+            };
+
+            if let Some((id, mo)) = xml_node_to_member_object(o_node, arc_tolerance) {
+                objects.insert(id, mo);
+            }
+
+            if !whitelist.contains(o_node.node_type.as_str()) {
+                return;
+            }
+
+            for tp in xml_node_to_tagged_polygons(o_node, arc_tolerance) {
+                objekte
+                    .entry(o_node.node_type.clone())
+                    .or_insert_with(|| Vec::new())
+                    .push(tp);
+            }
+        },
+    )
+    .map_err(|e| format!("XML parse error: {e:?}"))?;
+
     let crs = match crs {
         Some(s) => s,
         None => return Err(format!("kein Koordinatenreferenzsystem gefunden (AA_Koordinatenreferenzsystemangaben = standard)")), // no CRS found
     };
-    let crs = crs.replace("urn:adv:crs:", "");
-
-    let crs = match get_proj_string(&crs) {
+    let crs = match crate::crs::get_crs_by_adv_id(&crs) {
         Some(s) => s,
-        None => return Err(format!("Unbekanntes CRS: {crs}")),
+        None => return Err(format!("Unbekanntes CRS: {}", crs.replace(crate::crs::ADV_CRS_PREFIX, ""))),
     };
 
-    // Objekte parsen
-    let objekte_nodes = get_all_nodes_in_subtree(&xml, "member");
-    let mut objekte = BTreeMap::new();
-    for o in objekte_nodes.iter() {
-        let o_node = match o.children.first() {
-            Some(s) => s,
-            None => continue,
-        };
-        let _flst_id = match o_node.attributes.get("id") {
-            Some(s) => s.clone(),
-            None => continue,
-        };
-        if !whitelist.contains(o_node.node_type.as_str()) {
-            continue;
+    // UTM coordinates with the zone number in front of the easting (33412345.678)
+    let has_zone_prefix = objekte
+        .values()
+        .flat_map(|v: &Vec<TaggedPolygon>| v.iter())
+        .find_map(|tp| tp.poly.outer_ring.points.first())
+        .map(|p| p.x > 1_000_000.0)
+        .unwrap_or(false);
+    let crs = match crs.with_zone_prefix() {
+        Some(prefixed) if has_zone_prefix => prefixed,
+        _ => crs,
+    };
+
+    Ok((
+        NasXMLFile {
+            crs: crs.proj,
+            ebenen: objekte,
+        },
+        NasXmlObjects { objects },
+    ))
+}
+
+/// Parses NAS delivered in several files (one per Flur or tile) and merges them, see [merge_nas_xml]
+pub fn parse_nas_xml_files(
+    files: &[(String, String)],
+    whitelist: &BTreeSet<String>,
+    arc_tolerance: f64,
+) -> Result<(NasXMLFile, NasXmlObjects), String> {
+    let mut parsed = Vec::new();
+    for (name, xml) in files.iter() {
+        log_status(&format!("Lese {name}..."));
+        let (nas, objects) =
+            parse_nas_xml(xml, whitelist, arc_tolerance).map_err(|e| format!("{name}: {e}"))?;
+        parsed.push((name.clone(), nas, objects));
+    }
+    merge_nas_xml(parsed)
+}
+
+/// Merges (file name, NAS, objects) into one NAS file. Objects with the same gml:id
+/// in several files (overlapping tiles) are only taken once: the version with the
+/// newest `beginnt`, otherwise the first file. All files must have the same CRS,
+/// for files without coordinates the zone prefix is unknown and not compared.
+pub fn merge_nas_xml(
+    files: Vec<(String, NasXMLFile, NasXmlObjects)>,
+) -> Result<(NasXMLFile, NasXmlObjects), String> {
+    let hat_koordinaten = |nas: &NasXMLFile| {
+        nas.ebenen
+            .values()
+            .flat_map(|v| v.iter())
+            .any(|tp| !tp.poly.outer_ring.points.is_empty())
+    };
+    let adv_id = |proj: &str| crate::crs::get_crs_by_proj(proj).map(|c| c.adv_id);
+    let (first_name, first_crs) = match files.iter().find(|(_, nas, _)| hat_koordinaten(nas)).or(files.first()) {
+        Some((name, nas, _)) => (name.clone(), nas.crs.clone()),
+        None => return Err("keine NAS-Dateien gefunden".to_string()),
+    };
+    let abweichend = files.iter().find(|(_, nas, _)| {
+        if hat_koordinaten(nas) {
+            nas.crs != first_crs
+        } else {
+            adv_id(&nas.crs) != adv_id(&first_crs)
+        }
+    });
+    if let Some((name, nas, _)) = abweichend {
+        return Err(format!(
+            "Unterschiedliche Koordinatenreferenzsysteme: {first_name} ({first_crs}), {name} ({})",
+            nas.crs
+        ));
+    }
+
+    // gml:id => index of the file the object is taken from
+    let mut quelle_objekte = BTreeMap::new();
+    let mut quelle_polygone = BTreeMap::new();
+    for (i, (_, nas, objects)) in files.iter().enumerate() {
+        for (id, mo) in objects.objects.iter() {
+            match quelle_objekte.get(id) {
+                Some((_, beginnt)) if *beginnt >= mo.beginnt => {}
+                _ => {
+                    quelle_objekte.insert(id.clone(), (i, mo.beginnt));
+                }
+            }
         }
-        let key = o_node.node_type.clone();
-        let poly = xml_select_svg_polygon(&o_node.children);
-        if poly.is_empty() {
-            continue;
-        };
+        for tp in nas.ebenen.values().flat_map(|v| v.iter()) {
+            if let Some(id) = tp.attributes.get("id") {
+                quelle_polygone.entry(id.clone()).or_insert(i);
+            }
+        }
+    }
 
-        let mut attributes = o_node
-            .children
-            .iter()
-            .filter_map(|cn| match &cn.text {
-                Some(s) => Some((cn.node_type.clone(), s.clone())),
-                None => None,
-            })
-            .collect::<BTreeMap<_, _>>();
-        attributes.extend(o_node.attributes.clone().into_iter());
-        attributes.insert("AX_Ebene".to_string(), key.clone());
+    let mut merged = NasXMLFile {
+        crs: first_crs,
+        ebenen: BTreeMap::new(),
+    };
+    let mut merged_objects = NasXmlObjects::default();
+    let mut duplikate = 0;
+    for (i, (_, nas, objects)) in files.into_iter().enumerate() {
+        for (id, mo) in objects.objects {
+            if quelle_objekte.get(&id).map(|q| q.0) == Some(i) {
+                merged_objects.objects.insert(id, mo);
+            }
+        }
+        for (ebene, tps) in nas.ebenen {
+            let target = merged.ebenen.entry(ebene).or_insert_with(|| Vec::new());
+            for tp in tps {
+                let quelle = match tp.attributes.get("id") {
+                    Some(id) => quelle_objekte
+                        .get(id)
+                        .map(|q| q.0)
+                        .or(quelle_polygone.get(id).copied()),
+                    None => Some(i),
+                };
+                if quelle == Some(i) {
+                    target.push(tp);
+                } else {
+                    duplikate += 1;
+                }
+            }
+        }
+    }
+
+    if duplikate > 0 {
+        log_status(&format!("{duplikate} doppelte Objekte übersprungen"));
+    }
+
+    Ok((merged, merged_objects))
+}
+
+#[test]
+fn test_merge_nas_xml() {
+    let tp = |id: &str, x: f64| TaggedPolygon {
+        poly: SvgPolygonInner::from_line(&SvgLine {
+            points: vec![
+                SvgPoint { x, y: 0.0 },
+                SvgPoint { x: x + 1.0, y: 0.0 },
+                SvgPoint { x, y: 1.0 },
+                SvgPoint { x, y: 0.0 },
+            ],
+        }),
+        attributes: vec![("id".to_string(), id.to_string())].into_iter().collect(),
+    };
+    let nas = |crs: String, tps: Vec<TaggedPolygon>| NasXMLFile {
+        crs,
+        ebenen: vec![("AX_Flurstueck".to_string(), tps)].into_iter().collect(),
+    };
+
+    let a = nas(default_etrs33(), vec![tp("DE1", 0.0), tp("DE2", 5.0)]);
+    let b = nas(default_etrs33(), vec![tp("DE2", 5.0), tp("DE3", 10.0)]);
+    let (merged, _) = merge_nas_xml(vec![
+        ("a.xml".to_string(), a.clone(), NasXmlObjects::default()),
+        ("b.xml".to_string(), b, NasXmlObjects::default()),
+    ])
+    .unwrap();
+    assert_eq!(merged.ebenen["AX_Flurstueck"].len(), 3);
+
+    let c = nas(crate::crs::get_crs_by_adv_id("ETRS89_UTM32").unwrap().proj, vec![tp("DE4", 0.0)]);
+    assert!(merge_nas_xml(vec![
+        ("a.xml".to_string(), a, NasXmlObjects::default()),
+        ("c.xml".to_string(), c.clone(), NasXmlObjects::default()),
+    ])
+    .is_err());
+
+    // a file without coordinates is never detected as zone-prefixed
+    let utm33 = crate::crs::get_crs_by_proj(&default_etrs33()).unwrap();
+    let leer = nas(default_etrs33(), Vec::new());
+    let prefixed = nas(utm33.with_zone_prefix().unwrap().proj, vec![tp("DE5", 33412345.0)]);
+    let (merged, _) = merge_nas_xml(vec![
+        ("leer.xml".to_string(), leer, NasXmlObjects::default()),
+        ("prefixed.xml".to_string(), prefixed.clone(), NasXmlObjects::default()),
+    ])
+    .unwrap();
+    assert_eq!(merged.crs, prefixed.crs);
+    let leer_utm32 = nas(c.crs.clone(), Vec::new());
+    assert!(merge_nas_xml(vec![
+        ("prefixed.xml".to_string(), prefixed, NasXmlObjects::default()),
+        ("leer.xml".to_string(), leer_utm32, NasXmlObjects::default()),
+    ])
+    .is_err());
+}
+
+// <AA_Koordinatenreferenzsystemangaben> with <standard>true</standard>
+fn get_standard_crs(c: &XmlNode) -> Option<String> {
+    match get_all_nodes_in_subtree(&c.children, "standard").first() {
+        Some(XmlNode { text: Some(s), .. }) if s == "true" => {}
+        _ => return None,
+    }
+    get_all_nodes_in_subtree(&c.children, "crs")
+        .first()?
+        .attributes
+        .get("href")
+        .cloned()
+}
+
+fn xml_node_to_tagged_polygons(o_node: &XmlNode, arc_tolerance: f64) -> Vec<TaggedPolygon> {
+    if !o_node.attributes.contains_key("id") {
+        return Vec::new();
+    }
+    let key = o_node.node_type.clone();
+    let poly = xml_select_svg_polygon(&o_node.children, arc_tolerance);
+    if poly.is_empty() {
+        return Vec::new();
+    };
+
+    let mut attributes = o_node
+        .children
+        .iter()
+        .filter_map(|cn| match &cn.text {
+            Some(s) => Some((cn.node_type.clone(), s.clone())),
+            None => None,
+        })
+        .collect::<BTreeMap<_, _>>();
+    attributes.extend(o_node.attributes.clone().into_iter());
+    attributes.insert("AX_Ebene".to_string(), key);
+
+    poly.into_iter()
+        .map(|s| TaggedPolygon { poly: s, attributes: attributes.clone() })
+        .collect()
+}
+
+/// Default max. deviation (Pfeilhöhe) in meters between a GML arc and its densified polyline
+pub const ARC_CHORD_TOLERANCE_M: f64 = 0.01;
 
-        for s in poly {
-            let tp = TaggedPolygon { poly: s, attributes: attributes.clone() };
-            objekte.entry(key.clone()).or_insert_with(|| Vec::new()).push(tp);
+/// Circular arc from a GML curve segment (Arc, ArcString, Circle, CircleByCenterPoint).
+/// Keeps the three control points so that unedited boundaries can be written back as
+/// `gml:Arc` / `gml:Circle`, `points` are the densified points that replaced it in the ring.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GmlArc {
+    pub start: SvgPoint,
+    pub mid: SvgPoint,
+    pub end: SvgPoint,
+    #[serde(default)]
+    pub circle: bool,
+    pub points: Vec<SvgPoint>,
+}
+
+impl GmlArc {
+    pub fn new(start: SvgPoint, mid: SvgPoint, end: SvgPoint, circle: bool, tolerance: f64) -> Self {
+        Self {
+            start,
+            mid,
+            end,
+            circle,
+            points: densify_arc(&start, &mid, &end, circle, tolerance),
+        }
+    }
+
+    /// Same arc, traversed in the opposite direction
+    pub fn reverse(&self) -> Self {
+        let mut points = self.points.clone();
+        points.reverse();
+        if self.circle {
+            Self {
+                start: self.start,
+                mid: self.end,
+                end: self.mid,
+                circle: true,
+                points,
+            }
+        } else {
+            Self {
+                start: self.end,
+                mid: self.mid,
+                end: self.start,
+                circle: false,
+                points,
+            }
         }
     }
 
-    Ok(NasXMLFile {
-        crs: crs,
-        ebenen: objekte,
-    })
+    /// Returns the arc (possibly reversed) if `points` starts with the densified arc points
+    pub fn matches_start_of(&self, points: &[SvgPoint]) -> Option<Self> {
+        const EPSILON: f64 = 0.002;
+        if self.points.len() < 2 || points.len() < self.points.len() {
+            return None;
+        }
+        let matches = |arc_points: &[SvgPoint]| {
+            arc_points
+                .iter()
+                .zip(points.iter())
+                .all(|(a, b)| a.equals_approx(b, EPSILON))
+        };
+        if matches(&self.points) {
+            return Some(self.clone());
+        }
+        let rev = self.reverse();
+        if matches(&rev.points) {
+            return Some(rev);
+        }
+        None
+    }
+}
+
+/// Circle through three points, returns (center, radius)
+fn circle_from_3_points(p0: &SvgPoint, p1: &SvgPoint, p2: &SvgPoint) -> Option<(SvgPoint, f64)> {
+    // relative to p0, otherwise UTM coordinates lose too much precision
+    let (bx, by) = (p1.x - p0.x, p1.y - p0.y);
+    let (cx, cy) = (p2.x - p0.x, p2.y - p0.y);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d.abs() < 1e-9 {
+        return None;
+    }
+    let b2 = bx * bx + by * by;
+    let c2 = cx * cx + cy * cy;
+    let ux = (cy * b2 - by * c2) / d;
+    let uy = (bx * c2 - cx * b2) / d;
+    let radius = (ux * ux + uy * uy).sqrt();
+    Some((SvgPoint { x: p0.x + ux, y: p0.y + uy }, radius))
+}
+
+/// Densifies the circular arc from `p0` through `p1` to `p2` (or the full circle through
+/// the three points) so that no chord deviates more than `tolerance` meters from the arc.
+/// Always starts at `p0` and ends exactly at `p2` (at `p0` for a full circle).
+pub fn densify_arc(p0: &SvgPoint, p1: &SvgPoint, p2: &SvgPoint, full_circle: bool, tolerance: f64) -> Vec<SvgPoint> {
+    use std::f64::consts::PI;
+
+    const MAX_SEGMENTS: usize = 1024;
+
+    let (center, radius) = match circle_from_3_points(p0, p1, p2) {
+        Some(s) => s,
+        None => {
+            // collinear control points: straight line
+            return if full_circle { vec![*p0] } else { vec![*p0, *p2] };
+        }
+    };
+
+    let angle = |p: &SvgPoint| (p.y - center.y).atan2(p.x - center.x);
+    let ccw = (p1.x - p0.x) * (p2.y - p1.y) - (p1.y - p0.y) * (p2.x - p1.x) > 0.0;
+    let a0 = angle(p0);
+    let sweep = if full_circle {
+        2.0 * PI
+    } else {
+        let mut sweep = angle(p2) - a0;
+        if ccw {
+            while sweep <= 0.0 {
+                sweep += 2.0 * PI;
+            }
+        } else {
+            while sweep >= 0.0 {
+                sweep -= 2.0 * PI;
+            }
+        }
+        sweep.abs()
+    };
+
+    let tolerance = tolerance.max(0.0001);
+    let max_step = if tolerance < radius {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        PI / 2.0
+    };
+    let segments = ((sweep / max_step).ceil() as usize).max(1).min(MAX_SEGMENTS);
+    let step = if ccw { sweep } else { -sweep } / segments as f64;
+
+    let mut points = vec![*p0];
+    for i in 1..segments {
+        let a = a0 + step * i as f64;
+        points.push(SvgPoint {
+            x: center.x + radius * a.cos(),
+            y: center.y + radius * a.sin(),
+        });
+    }
+    points.push(if full_circle { *p0 } else { *p2 });
+    points
+}
+
+#[test]
+fn test_densify_arc() {
+    let c = SvgPoint { x: 400000.0, y: 5800000.0 };
+    let r = 10.0;
+    let p0 = c.translate(r, 0.0);
+    let p1 = c.translate(r * 45.0_f64.to_radians().cos(), r * 45.0_f64.to_radians().sin());
+    let p2 = c.translate(0.0, r);
+    let points = densify_arc(&p0, &p1, &p2, false, 0.01);
+    assert!(points.len() > 3);
+    assert_eq!(points.first(), Some(&p0));
+    assert_eq!(points.last(), Some(&p2));
+    for w in points.windows(2) {
+        assert!((w[0].dist(&c) - r).abs() < 1e-6);
+        let mid = SvgPoint { x: (w[0].x + w[1].x) / 2.0, y: (w[0].y + w[1].y) / 2.0 };
+        assert!(r - mid.dist(&c) <= 0.01 + 1e-6);
+    }
+    // clockwise: same points, reversed
+    let mut points_cw = densify_arc(&p2, &p1, &p0, false, 0.01);
+    points_cw.reverse();
+    assert!(points.iter().zip(points_cw.iter()).all(|(a, b)| a.equals(b)));
 }
 
 enum LineType {
     LineStringSegment { points: Vec<SvgPoint> },
-    Arc { points: Vec<SvgPoint> },
+    Arc { arcs: Vec<GmlArc> },
 }
 
 impl LineType {
+    fn from_segment(s: &XmlNode, tolerance: f64) -> Option<Self> {
+        match s.node_type.as_str() {
+            "LineStringSegment" => Some(LineType::LineStringSegment {
+                points: get_children_points(s),
+            }),
+            // ArcString: p0 p1 p2 (p3 p4) ..., consecutive arcs share the end point
+            "Arc" | "ArcString" => {
+                let points = get_children_points(s);
+                let arcs = (0..points.len().saturating_sub(2))
+                    .step_by(2)
+                    .map(|i| GmlArc::new(points[i], points[i + 1], points[i + 2], false, tolerance))
+                    .collect::<Vec<_>>();
+                Some(LineType::Arc { arcs })
+            }
+            "Circle" => match get_children_points(s).as_slice() {
+                [p0, p1, p2, ..] => Some(LineType::Arc {
+                    arcs: vec![GmlArc::new(*p0, *p1, *p2, true, tolerance)],
+                }),
+                _ => None,
+            },
+            "CircleByCenterPoint" | "ArcByCenterPoint" => {
+                let center = get_children_points(s).first().copied()?;
+                let child_f64 = |name: &str| {
+                    s.children
+                        .iter()
+                        .find(|c| c.node_type == name)
+                        .and_then(|c| c.text.as_ref()?.trim().parse::<f64>().ok())
+                };
+                let radius = child_f64("radius")?;
+                let point_at = |deg: f64| SvgPoint {
+                    x: center.x + radius * deg.to_radians().cos(),
+                    y: center.y + radius * deg.to_radians().sin(),
+                };
+                let arc = if s.node_type == "CircleByCenterPoint" {
+                    GmlArc::new(point_at(0.0), point_at(120.0), point_at(240.0), true, tolerance)
+                } else {
+                    // angles are counter-clockwise, in degrees
+                    let start = child_f64("startAngle").unwrap_or(0.0);
+                    let mut end = child_f64("endAngle").unwrap_or(360.0);
+                    while end <= start {
+                        end += 360.0;
+                    }
+                    GmlArc::new(
+                        point_at(start),
+                        point_at((start + end) / 2.0),
+                        point_at(end),
+                        false,
+                        tolerance,
+                    )
+                };
+                Some(LineType::Arc { arcs: vec![arc] })
+            }
+            _ => None,
+        }
+    }
+
     pub fn get_points(&self) -> Vec<SvgPoint> {
         match self {
             LineType::LineStringSegment { points } => points.clone(),
-            LineType::Arc { points } => {
-                // TODO: incorrect, but probably ok for now
-                points.clone()
-            },
+            LineType::Arc { arcs } => arcs.iter().flat_map(|a| a.points.iter().copied()).collect(),
         }
     }
 }
@@ -1858,14 +2282,19 @@ fn get_children_points(s: &XmlNode) -> Vec<SvgPoint> {
     }).collect()
 }
 
-fn xml_select_svg_polygon(xml: &Vec<XmlNode>) -> Vec<SvgPolygonInner> {
+fn xml_select_svg_polygon(xml: &Vec<XmlNode>, arc_tolerance: f64) -> Vec<SvgPolygonInner> {
+    xml_select_svg_polygon_with_arcs(xml, arc_tolerance).0
+}
+
+fn xml_select_svg_polygon_with_arcs(xml: &Vec<XmlNode>, arc_tolerance: f64) -> (Vec<SvgPolygonInner>, Vec<GmlArc>) {
     let patches = get_all_nodes_in_subtree(&xml, "PolygonPatch");
     if patches.is_empty() {
-        return Vec::new();
+        return (Vec::new(), Vec::new());
     }
 
     let mut outer_rings = Vec::new();
     let mut inner_rings = Vec::new();
+    let mut arcs = Vec::new();
     let children = patches
         .iter()
         .flat_map(|s| s.children.clone())
@@ -1883,26 +2312,25 @@ fn xml_select_svg_polygon(xml: &Vec<XmlNode>) -> Vec<SvgPolygonInner> {
         let linestring_points = linestrings
             .iter()
             .flat_map(|s| {
-                s.children.iter().filter_map(|s| {
-                    match s.node_type.as_str() {
-                        "LineStringSegment" => Some(LineType::LineStringSegment { points: get_children_points(s) }),
-                        "Arc" => Some(LineType::Arc { points: get_children_points(s) }),
-                        _ => None,
-                    }
-                })
+                s.children.iter().filter_map(|s| LineType::from_segment(s, arc_tolerance))
             })
             .collect::<Vec<_>>();
 
         let mut line_points = linestring_points
-            .into_iter()
+            .iter()
             .flat_map(|f| f.get_points())
             .collect::<Vec<_>>();
 
         line_points.dedup();
         
         if line_points.len() < 3 {
-            return Vec::new();
+            return (Vec::new(), Vec::new());
         }
+
+        arcs.extend(linestring_points.into_iter().flat_map(|l| match l {
+            LineType::Arc { arcs } => arcs,
+            LineType::LineStringSegment { .. } => Vec::new(),
+        }));
         
         let line = SvgLine {
             points: line_points,
@@ -1916,10 +2344,10 @@ fn xml_select_svg_polygon(xml: &Vec<XmlNode>) -> Vec<SvgPolygonInner> {
     }
 
     if outer_rings.is_empty() && inner_rings.is_empty() {
-        return Vec::new();
+        return (Vec::new(), Vec::new());
     }
 
-    recombine_polys(&outer_rings, &inner_rings)
+    (recombine_polys(&outer_rings, &inner_rings), arcs)
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -1933,6 +2361,12 @@ pub struct MemberObject {
     pub ist_teil_von: Option<String>,
     pub extra_attribute: BTreeMap<String, String>,
     pub poly: Vec<SvgPolygonInner>,
+    /// Original GML arcs of the geometry, see [GmlArc]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arcs: Vec<GmlArc>,
+    /// `gml:Point` position of presentation objects (AP_PTO, AP_PPO)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub punkt: Option<SvgPoint>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -1940,95 +2374,78 @@ pub struct NasXmlObjects {
     pub objects: BTreeMap<String, MemberObject>,
 }
 
-pub fn parse_nas_xml_objects(xml: &Vec<XmlNode>) -> NasXmlObjects {
-    let mut map = BTreeMap::new();
-
-    let objekte_nodes = get_all_nodes_in_subtree(&xml, "member");
-
-    for o in objekte_nodes.iter() {
-        let o_node = match o.children.first() {
-            Some(s) => s,
-            None => continue,
-        };
-
-        let id = match o_node.attributes.get("id").map(|s| s.clone()) {
-            Some(s) => s,
-            None => continue,
-        };
-
-        let beginnt = match o_node
-            .select_subitems(&["lebenszeitintervall", "AA_Lebenszeitintervall", "beginnt"])
-            .first()
-            .and_then(|s| DateTime::parse_from_rfc3339(&s.text.as_ref()?).ok())
-        {
-            Some(s) => s,
-            None => continue,
-        };
-
-        let member_type = o_node.node_type.clone();
+fn xml_node_to_member_object(o_node: &XmlNode, arc_tolerance: f64) -> Option<(String, MemberObject)> {
+    let id = o_node.attributes.get("id")?.clone();
 
-        let dient_zur_darstellung_von = o_node
-            .select_subitems(&["dientZurDarstellungVon"])
-            .first()
-            .and_then(|s| s.attributes.get("href").cloned());
+    let beginnt = o_node
+        .select_subitems(&["lebenszeitintervall", "AA_Lebenszeitintervall", "beginnt"])
+        .first()
+        .and_then(|s| DateTime::parse_from_rfc3339(&s.text.as_ref()?).ok())?;
 
-        let ist_bestandteil_von = o_node
-            .select_subitems(&["istBestandteilVon"])
-            .first()
-            .and_then(|s| s.attributes.get("href").cloned());
+    let member_type = o_node.node_type.clone();
 
-        let ist_teil_von = o_node
-            .select_subitems(&["istTeilVon"])
-            .first()
-            .and_then(|s| s.attributes.get("href").cloned());
+    let dient_zur_darstellung_von = o_node
+        .select_subitems(&["dientZurDarstellungVon"])
+        .first()
+        .and_then(|s| s.attributes.get("href").cloned());
 
-        let hat = o_node
-            .select_subitems(&["hat"])
-            .first()
-            .and_then(|s| s.attributes.get("href").cloned());
-
-        let poly = xml_select_svg_polygon(
-            &o_node
-                .select_subitems(&["position"])
-                .into_iter()
-                .cloned()
-                .collect(),
-        );
+    let ist_bestandteil_von = o_node
+        .select_subitems(&["istBestandteilVon"])
+        .first()
+        .and_then(|s| s.attributes.get("href").cloned());
 
-        let extra_attribute = o_node
-            .children
-            .iter()
-            .filter_map(|s| Some((s.node_type.clone(), s.text.clone()?)))
-            .collect();
+    let ist_teil_von = o_node
+        .select_subitems(&["istTeilVon"])
+        .first()
+        .and_then(|s| s.attributes.get("href").cloned());
 
-        map.insert(
-            id,
-            MemberObject {
-                member_type,
-                beginnt,
-                dient_zur_darstellung_von,
-                ist_bestandteil_von,
-                hat,
-                ist_teil_von,
-                extra_attribute,
-                poly,
-            },
-        );
-    }
+    let hat = o_node
+        .select_subitems(&["hat"])
+        .first()
+        .and_then(|s| s.attributes.get("href").cloned());
 
-    NasXmlObjects { objects: map }
-}
+    let (poly, arcs) = xml_select_svg_polygon_with_arcs(
+        &o_node
+            .select_subitems(&["position"])
+            .into_iter()
+            .cloned()
+            .collect(),
+        arc_tolerance,
+    );
 
-fn get_proj_string(input: &str) -> Option<String> {
-    let mut known_strings = BTreeMap::new();
-    for i in 0..60 {
-        known_strings.insert(
-            format!("ETRS89_UTM{i}"),
-            format!("+proj=utm +ellps=GRS80 +units=m +no_defs +zone={i}"),
-        );
-    }
+    let extra_attribute = o_node
+        .children
+        .iter()
+        .filter_map(|s| Some((s.node_type.clone(), s.text.clone()?)))
+        .collect();
 
-    known_strings.get(input).cloned()
+    let punkt = o_node
+        .select_subitems(&["position", "Point", "pos"])
+        .first()
+        .filter(|s| s.node_type == "pos")
+        .and_then(|s| {
+            let mut coords = s.text.as_deref()?.split_whitespace();
+            Some(SvgPoint {
+                x: coords.next()?.parse().ok()?,
+                y: coords.next()?.parse().ok()?,
+            })
+        });
+
+    Some((
+        id,
+        MemberObject {
+            member_type,
+            beginnt,
+            dient_zur_darstellung_von,
+            ist_bestandteil_von,
+            hat,
+            ist_teil_von,
+            extra_attribute,
+            poly,
+            arcs,
+            punkt,
+        },
+    ))
 }
 
 #[derive(Debug, Copy, Clone)]
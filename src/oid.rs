@@ -0,0 +1,119 @@
+//! Object identifiers (OID) for objects inserted by the Fortführungsauftrag
+
+use crate::nas::NasXmlObjects;
+use std::collections::BTreeSet;
+
+/// Length of an AdV OID, e.g. "DEBBAL0100003z1f"
+pub const OID_LAENGE: usize = 16;
+
+/// "DE" + Land + 4 characters of the Vermessungsstelle
+pub const OID_PRAEFIX_LAENGE: usize = 8;
+
+const OID_ZEICHEN: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Checks that the prefix is "DE" + Land (e.g. "BB") + Vermessungsstelle (4 characters)
+pub fn check_oid_praefix(praefix: &str) -> Result<(), String> {
+    if praefix.len() != OID_PRAEFIX_LAENGE || !praefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!(
+            "OID-Präfix \"{praefix}\" ungültig: erwartet {OID_PRAEFIX_LAENGE} Zeichen (A-Z, 0-9), z.B. \"DEBBAL01\""
+        ));
+    }
+    if !praefix.starts_with("DE") {
+        return Err(format!("OID-Präfix \"{praefix}\" ungültig: muss mit \"DE\" beginnen"));
+    }
+    Ok(())
+}
+
+/// Generates 16-character OIDs ("DEBBAL01" + 8 characters counter),
+/// skipping all OIDs that already exist in the loaded NAS objects.
+///
+/// The counter starts at the milliseconds since [OID_EPOCHE] of the export,
+/// so that the OIDs of two exports (e.g. a second Fortführungsauftrag before
+/// the first one is in the NAS data) don't overlap as long as each export
+/// uses fewer OIDs than milliseconds passed in between.
+#[derive(Debug, Clone)]
+pub struct OidGenerator {
+    praefix: String,
+    naechste: u64,
+    belegt: BTreeSet<String>,
+}
+
+/// Start of the OID counter, 36^8 milliseconds later (year 2109) it overflows
+const OID_EPOCHE: &str = "2020-01-01T00:00:00Z";
+
+impl OidGenerator {
+    pub fn new(
+        praefix: &str,
+        objects: &NasXmlObjects,
+        datum_jetzt: &chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<Self, String> {
+        let epoche = chrono::DateTime::parse_from_rfc3339(OID_EPOCHE).map_err(|e| e.to_string())?;
+        let start = datum_jetzt.signed_duration_since(epoche).num_milliseconds().max(1) as u64;
+        Self::with_belegt(praefix, start, objects.objects.keys())
+    }
+
+    pub fn with_belegt<'a>(
+        praefix: &str,
+        start: u64,
+        belegt: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Self, String> {
+        check_oid_praefix(praefix)?;
+        Ok(Self {
+            praefix: praefix.to_string(),
+            naechste: start,
+            belegt: belegt
+                .into_iter()
+                .filter(|k| k.starts_with(praefix))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    pub fn next_oid(&mut self) -> String {
+        loop {
+            let oid = format!("{}{}", self.praefix, encode_counter(self.naechste));
+            self.naechste += 1;
+            if self.belegt.insert(oid.clone()) {
+                return oid;
+            }
+        }
+    }
+}
+
+fn encode_counter(mut n: u64) -> String {
+    let laenge = OID_LAENGE - OID_PRAEFIX_LAENGE;
+    let mut s = vec![b'0'; laenge];
+    for c in s.iter_mut().rev() {
+        *c = OID_ZEICHEN[(n % OID_ZEICHEN.len() as u64) as usize];
+        n /= OID_ZEICHEN.len() as u64;
+    }
+    String::from_utf8(s).unwrap_or_default()
+}
+
+#[test]
+fn test_oid_generator() {
+    let belegt = vec!["DEBBAL0100000002".to_string()];
+    let mut g = OidGenerator::with_belegt("DEBBAL01", 1, &belegt).unwrap();
+    assert_eq!(g.next_oid(), "DEBBAL0100000001");
+    assert_eq!(g.next_oid(), "DEBBAL0100000003");
+    assert_eq!(g.next_oid().len(), OID_LAENGE);
+    assert_eq!(encode_counter(36), "00000010");
+    let datum = chrono::DateTime::parse_from_rfc3339("2024-05-06T10:00:00+02:00").unwrap();
+    assert!(OidGenerator::new("DE_001", &NasXmlObjects::default(), &datum).is_err());
+    assert!(check_oid_praefix("ATBBAL01").is_err());
+}
+
+#[test]
+fn test_oid_generator_exporte_ueberlappen_nicht() {
+    let objects = NasXmlObjects::default();
+    let erster = chrono::DateTime::parse_from_rfc3339("2024-05-06T10:00:00+02:00").unwrap();
+    let zweiter = chrono::DateTime::parse_from_rfc3339("2024-05-06T10:01:00+02:00").unwrap();
+    let mut a = OidGenerator::new("DEBBAL01", &objects, &erster).unwrap();
+    let mut b = OidGenerator::new("DEBBAL01", &objects, &zweiter).unwrap();
+    let oids_a = (0..1000).map(|_| a.next_oid()).collect::<BTreeSet<_>>();
+    let oids_b = (0..1000).map(|_| b.next_oid()).collect::<BTreeSet<_>>();
+    assert_eq!(oids_a.len(), 1000);
+    assert_eq!(oids_b.len(), 1000);
+    assert!(oids_a.is_disjoint(&oids_b));
+    assert!(oids_a.iter().chain(oids_b.iter()).all(|o| o.len() == OID_LAENGE));
+}
@@ -7,21 +7,37 @@ use crate::{
         RissConfig,
         RissExtentReprojected,
     },
+    process::AngleDegrees,
     ui::{
         AenderungenIntersection,
+        SplitflaecheId,
         TextPlacement,
+        TextStatus,
     },
-    uuid_wasm::js_random,
 };
-use std::{
-    collections::BTreeMap,
-    f64::consts::PI,
+use printpdf::{
+    Mm,
+    Pt,
 };
+use quadtree_f32::Rect;
+use rand::{
+    Rng,
+    SeedableRng,
+};
+use rand_xorshift::XorShiftRng;
+use serde_derive::{
+    Deserialize,
+    Serialize,
+};
+use std::f64::consts::PI;
 
 #[derive(Debug, Clone)]
 pub struct OptimizedTextPlacement {
     pub original: TextPlacement,
     pub optimized: TextPlacement,
+    /// Rotation of the text around `optimized.pos`, counterclockwise
+    /// (0 = horizontal)
+    pub winkel: AngleDegrees,
 }
 
 impl OptimizedTextPlacement {
@@ -38,9 +54,180 @@ impl OptimizedTextPlacement {
     }
 }
 
-pub const LABEL_HEIGHT_M: f64 = 13.0;
-pub const LABEL_WIDTH_M: f64 = 20.0;
-pub const LABEL_WIDTH_PER_CHAR_M: f64 = 5.0;
+/// Manually placed label (moved on the Riss preview or in GEOgraf and
+/// re-imported from the GRAFBAT.out), stored per Riss. The label is pinned
+/// by [optimize_labels], the other labels avoid it. Coordinates are in the
+/// project CRS.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct BeschriftungOverride {
+    /// Splitfläche of the label, together with `status` the key of the
+    /// override. `None` for overrides of older projects, matched by `anker`.
+    #[serde(default)]
+    pub splitflaeche: Option<SplitflaecheId>,
+    pub kuerzel: String,
+    pub status: TextStatus,
+    /// Initial label position (`OptimizedTextPlacement.original.pos`)
+    pub anker: SvgPoint,
+    pub pos: SvgPoint,
+    /// Corrected end point of the Bezugslinie
+    #[serde(default)]
+    pub ref_pos: Option<SvgPoint>,
+    #[serde(default)]
+    pub winkel: AngleDegrees,
+}
+
+/// Max. distance between `anker` and the label position of a new export
+pub const OVERRIDE_ANKER_TOLERANZ_M: f64 = 0.01;
+
+impl BeschriftungOverride {
+    pub fn matches(&self, tp: &TextPlacement) -> bool {
+        match self.splitflaeche.as_ref() {
+            Some(id) => tp.splitflaeche.as_ref() == Some(id) && self.status == tp.status,
+            None => {
+                self.kuerzel == tp.kuerzel
+                    && self.status == tp.status
+                    && self.anker.equals_approx(&tp.pos, OVERRIDE_ANKER_TOLERANZ_M)
+            }
+        }
+    }
+
+    /// Whether both overrides are for the same label
+    pub fn gleiches_label(&self, other: &BeschriftungOverride) -> bool {
+        if self.status != other.status {
+            return false;
+        }
+        match (self.splitflaeche.as_ref(), other.splitflaeche.as_ref()) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                self.kuerzel == other.kuerzel
+                    && self.anker.equals_approx(&other.anker, OVERRIDE_ANKER_TOLERANZ_M)
+            }
+        }
+    }
+
+    /// Whether the Splitfläche of the override still exists
+    pub fn ist_vorhanden(&self, splitflaechen: &[AenderungenIntersection]) -> bool {
+        match self.splitflaeche.as_ref() {
+            Some(id) => splitflaechen.iter().any(|s| {
+                s.flst_id_part == id.flst_id_part && s.alt == id.alt && s.neu == id.neu
+            }),
+            None => splitflaechen.iter().any(|s| {
+                let kuerzel = match self.status {
+                    TextStatus::Old | TextStatus::StaysAsIs => &s.alt,
+                    TextStatus::New => &s.neu,
+                };
+                *kuerzel == self.kuerzel
+                    && crate::nas::point_is_in_polygon(&self.anker, &s.poly_cut)
+            }),
+        }
+    }
+
+    pub fn get_beschreibung(&self) -> String {
+        match self.splitflaeche.as_ref() {
+            Some(id) => format!(
+                "Beschriftung \"{}\" ({}: {} -> {})",
+                self.kuerzel, id.flst_id_part, id.alt, id.neu
+            ),
+            None => format!(
+                "Beschriftung \"{}\" bei {:.2}, {:.2}",
+                self.kuerzel, self.anker.x, self.anker.y
+            ),
+        }
+    }
+}
+
+/// Overrides of the Riss whose Splitfläche no longer exists
+/// (Änderung deleted, Nutzung changed, ...), these are ignored by [optimize_labels]
+pub fn get_verwaiste_overrides<'a>(
+    overrides: &'a [BeschriftungOverride],
+    splitflaechen: &[AenderungenIntersection],
+) -> Vec<&'a BeschriftungOverride> {
+    overrides
+        .iter()
+        .filter(|o| !o.ist_vorhanden(splitflaechen))
+        .collect()
+}
+
+/// Maps the overrides onto the merged Splitflächen of
+/// [crate::ui::AenderungenIntersections::get_future_flaechen] (alt = neu, empty
+/// `flst_id_part`): the "neu" / "bleibt" label of a Splitfläche becomes the
+/// "bleibt" label of the future Splitfläche that contains it. "Alt" labels
+/// and overrides without Splitfläche have no counterpart and are dropped.
+pub fn get_future_overrides(
+    overrides: &[BeschriftungOverride],
+    splitflaechen: &[AenderungenIntersection],
+    future: &[AenderungenIntersection],
+) -> Vec<BeschriftungOverride> {
+    overrides
+        .iter()
+        .filter(|o| o.status != TextStatus::Old)
+        .filter_map(|o| {
+            let id = o.splitflaeche.as_ref()?;
+            let quelle = splitflaechen.iter().find(|s| s.get_splitflaeche_id() == *id)?;
+            let punkt = quelle.poly_cut.get_label_pos()?;
+            let ziel = future.iter().find(|f| {
+                f.flst_id == quelle.flst_id
+                    && f.neu == quelle.neu
+                    && crate::nas::point_is_in_polygon(&punkt, &f.poly_cut)
+            })?;
+            Some(BeschriftungOverride {
+                splitflaeche: Some(ziel.get_splitflaeche_id()),
+                kuerzel: ziel.neu.clone(),
+                status: TextStatus::StaysAsIs,
+                ..o.clone()
+            })
+        })
+        .collect()
+}
+
+/// Assigns every override to at most one label (the one closest to the
+/// override, if the Splitfläche is split into several parts by the Riss)
+fn get_fixierte_labels<'a>(
+    texte: &[TextPlacement],
+    overrides: &'a [BeschriftungOverride],
+) -> Vec<Option<&'a BeschriftungOverride>> {
+    let mut fixiert = vec![None; texte.len()];
+    for o in overrides.iter() {
+        let naechstes = texte
+            .iter()
+            .enumerate()
+            .filter(|(i, tp)| fixiert[*i].is_none() && o.matches(tp))
+            .min_by(|a, b| a.1.pos.dist(&o.pos).total_cmp(&b.1.pos.dist(&o.pos)))
+            .map(|(i, _)| i);
+        if let Some(i) = naechstes {
+            fixiert[i] = Some(o);
+        }
+    }
+    fixiert
+}
+
+/// Free space around the text of a label in mm
+pub const LABEL_PADDING_MM: f64 = 0.3;
+/// Line width of the white outline around the label text in pt, half of it
+/// lies outside of the glyphs
+pub const BESCHRIFTUNG_KONTUR_PT: f32 = 1.2;
+/// Resolution of the boolmaps: pixels per font size
+const PIXEL_PRO_SCHRIFTGROESSE: f64 = 4.0;
+
+/// Extent of a label around its position (start of the baseline)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LabelGroesse {
+    pub breite_m: f64,
+    pub oberlaenge_m: f64,
+    pub unterlaenge_m: f64,
+    pub padding_m: f64,
+}
+
+impl LabelGroesse {
+    pub fn get_rect(&self, pos: &SvgPoint) -> Rect {
+        Rect {
+            min_x: pos.x - self.padding_m,
+            min_y: pos.y - self.unterlaenge_m - self.padding_m,
+            max_x: pos.x + self.breite_m + self.padding_m,
+            max_y: pos.y + self.oberlaenge_m + self.padding_m,
+        }
+    }
+}
 
 pub struct OptimizeConfig {
     riss_extent: RissExtentReprojected,
@@ -48,6 +235,11 @@ pub struct OptimizeConfig {
     height_pixels: usize,
     one_px_x_in_m: f64,
     one_px_y_in_m: f64,
+    seed: u64,
+    scale: f64,
+    /// Font size of the labels in pt
+    schriftgroesse: f32,
+    beschriftungen: Vec<BeschriftungOverride>,
 }
 
 #[derive(Debug, Clone)]
@@ -57,21 +249,54 @@ pub struct Pixel {
 }
 
 impl OptimizeConfig {
+    /// `schriftgroesse` in pt, one pixel of the boolmaps is a quarter of the
+    /// font size on paper, so the resolution in m follows the scale
     pub fn new(
         riss_config: &RissConfig,
         riss_extent: &RissExtentReprojected,
-        tolerance: f64,
+        schriftgroesse: f32,
     ) -> Self {
-        let how_many_pixels_x = (riss_config.width_mm as f64 / tolerance).round() as usize;
-        let how_many_pixels_y = (riss_config.height_mm as f64 / tolerance).round() as usize;
-        let one_px_x_in_m = riss_extent.width_m() / how_many_pixels_x as f64;
-        let one_px_y_in_m = riss_extent.height_m() / how_many_pixels_y as f64;
+        let scale = riss_config.scale as f64;
+        let schriftgroesse_mm: Mm = Pt(schriftgroesse).into();
+        let one_px_in_m = (schriftgroesse_mm.0 as f64 / PIXEL_PRO_SCHRIFTGROESSE).max(0.1) * scale / 1000.0;
+        let how_many_pixels_x = (riss_extent.width_m() / one_px_in_m).ceil().max(1.0) as usize;
+        let how_many_pixels_y = (riss_extent.height_m() / one_px_in_m).ceil().max(1.0) as usize;
         Self {
             riss_extent: riss_extent.clone(),
-            one_px_x_in_m: one_px_x_in_m,
-            one_px_y_in_m: one_px_y_in_m,
+            one_px_x_in_m: one_px_in_m,
+            one_px_y_in_m: one_px_in_m,
             width_pixels: how_many_pixels_x,
             height_pixels: how_many_pixels_y,
+            seed: riss_config.seed,
+            scale,
+            schriftgroesse,
+            beschriftungen: riss_config.beschriftungen.clone(),
+        }
+    }
+
+    pub fn mm_to_m(&self, mm: f64) -> f64 {
+        mm * self.scale / 1000.0
+    }
+
+    /// Size of the label text in the font of the PDF (Helvetica-Bold), the
+    /// padding includes the white outline of the text
+    pub fn get_label_groesse(&self, kuerzel: &str) -> LabelGroesse {
+        use crate::geograf::{
+            calc_text_width_helvetica_bold_pt,
+            HELVETICA_BOLD_OBERLAENGE,
+            HELVETICA_BOLD_UNTERLAENGE,
+        };
+
+        let pt_to_m = |pt: f32| {
+            let mm: Mm = Pt(pt).into();
+            self.mm_to_m(mm.0 as f64)
+        };
+
+        LabelGroesse {
+            breite_m: pt_to_m(calc_text_width_helvetica_bold_pt(kuerzel, self.schriftgroesse).0),
+            oberlaenge_m: pt_to_m(HELVETICA_BOLD_OBERLAENGE * self.schriftgroesse),
+            unterlaenge_m: pt_to_m(HELVETICA_BOLD_UNTERLAENGE * self.schriftgroesse),
+            padding_m: self.mm_to_m(LABEL_PADDING_MM) + pt_to_m(BESCHRIFTUNG_KONTUR_PT / 2.0),
         }
     }
 
@@ -90,14 +315,6 @@ impl OptimizeConfig {
         }
     }
 
-    pub fn label_height_pixel(&self) -> usize {
-        (LABEL_HEIGHT_M / self.one_px_y_in_m).ceil() as usize
-    }
-
-    pub fn label_width_pixel(&self, lw_meter: f64) -> usize {
-        (lw_meter / self.one_px_x_in_m).ceil() as usize
-    }
-
     fn translate_svg_point_to_pixel_space(&self, point: &SvgPoint) -> SvgPoint {
         SvgPoint {
             x: (point.x - self.riss_extent.min_x) / self.one_px_x_in_m,
@@ -127,6 +344,66 @@ impl OptimizeConfig {
     }
 }
 
+/// Weights of the cost function of [optimize_labels]
+pub const KOSTEN_UEBERLAPPUNG: f64 = 1_000_000.0;
+pub const KOSTEN_LINIE_UEBER_LABEL: f64 = 100_000.0;
+pub const KOSTEN_LINIEN_KREUZUNG: f64 = 10_000.0;
+pub const KOSTEN_LINIE_UEBER_FEATURE: f64 = 1_000.0;
+pub const KOSTEN_DISTANZ_PRO_M: f64 = 10.0;
+pub const KOSTEN_GEDREHT: f64 = 2.0;
+
+/// Splitflächen whose minimum rotated rectangle is at least this much
+/// longer than wide get candidates along their main axis
+pub const GEDREHT_AB_SEITENVERHAELTNIS: f64 = 2.5;
+/// Main axes closer to horizontal than this are labeled horizontally
+const GEDREHT_MIN_WINKEL: AngleDegrees = 5.0;
+
+/// Labels with a lower cost are not moved any further
+const KOSTEN_AUSREICHEND: f64 = 5.0;
+const MAX_RINGE: usize = 20;
+const PUNKTE_PRO_RING: usize = 50;
+/// Distance between the candidate rings on paper
+const RING_ABSTAND_MM: f64 = 1.2;
+const SA_SCHRITTE_PRO_LABEL: usize = 200;
+const SA_MAX_SCHRITTE: usize = 20_000;
+const SA_TEMPERATUR_START: f64 = KOSTEN_LINIE_UEBER_FEATURE;
+const SA_TEMPERATUR_ENDE: f64 = 0.1;
+
+#[derive(Debug, Clone)]
+pub struct OptimizeResult {
+    pub texte: Vec<OptimizedTextPlacement>,
+    /// Total cost of the placement, see [optimize_labels]
+    pub kosten: f64,
+    /// Labels that still overlap a feature or another label
+    pub nicht_platziert: usize,
+    /// Labels pinned by a [BeschriftungOverride]
+    pub fixiert: usize,
+}
+
+/// Places the labels of the Splitflächen, deterministic for the same
+/// input and `config.seed`.
+///
+/// The cost of a placement is the sum of:
+///
+/// - [KOSTEN_UEBERLAPPUNG] for every label that overlaps a feature
+///   (Flurstücks- / Nutzungsgrenze, Gebäude, avoided area) and for every
+///   pair of overlapping labels
+/// - [KOSTEN_LINIE_UEBER_LABEL] for every leader line crossing another label
+/// - [KOSTEN_LINIEN_KREUZUNG] for every pair of crossing leader lines
+/// - [KOSTEN_LINIE_UEBER_FEATURE] per pixel of a feature under a leader line
+/// - [KOSTEN_DISTANZ_PRO_M] per meter between the label and its Splitfläche
+/// - [KOSTEN_GEDREHT] for a rotated label, so that horizontal labels win
+///   if both fit
+///
+/// Long, narrow Splitflächen (see [GEDREHT_AB_SEITENVERHAELTNIS]) also get
+/// candidates rotated along the main axis of their minimum rotated
+/// rectangle, centered on the candidate position.
+///
+/// Labels with a [BeschriftungOverride] of the Riss are pinned to the manual
+/// position and placed first, so that they count as obstacles for the others.
+/// The remaining labels are placed greedily (smallest area first) on candidate
+/// positions in growing rings around the initial position, then the placement
+/// is repaired with simulated annealing, moving one label at a time.
 pub fn optimize_labels(
     flurstuecke: &SplitNasXml,
     splitflaechen: &[AenderungenIntersection],
@@ -134,14 +411,20 @@ pub fn optimize_labels(
     avoid_areas_in_pdf_space: &[SvgPolygonInner],
     initial_text_pos: &[TextPlacement],
     config: &OptimizeConfig,
-) -> Vec<OptimizedTextPlacement> {
-    let initial = initial_text_pos
-        .iter()
-        .map(|s| OptimizedTextPlacement {
-            optimized: s.clone(),
-            original: s.clone(),
-        })
-        .collect();
+) -> OptimizeResult {
+    let initial = OptimizeResult {
+        texte: initial_text_pos
+            .iter()
+            .map(|s| OptimizedTextPlacement {
+                optimized: s.clone(),
+                original: s.clone(),
+                winkel: 0.0,
+            })
+            .collect(),
+        kosten: 0.0,
+        nicht_platziert: 0,
+        fixiert: 0,
+    };
 
     let background_boolmap = match render_stage1_overlap_boolmap(
         flurstuecke,
@@ -154,146 +437,436 @@ pub fn optimize_labels(
         None => return initial,
     };
 
-    let mut label_boolmap = match initialize_empty_boolmap(config) {
-        Some(s) => s,
-        None => return initial,
-    };
+    let mut initial_text_pos_clone = initial_text_pos.to_vec();
+    initial_text_pos_clone.sort_by(|a, b| a.area.cmp(&b.area)); // label small areas first
 
-    let mut lines_boolmap = match initialize_empty_boolmap(config) {
-        Some(s) => s,
-        None => return initial,
-    };
+    place_labels(&initial_text_pos_clone, &background_boolmap, config)
+}
 
-    let maxiterations = 20;
-    let maxpoints_per_iter = 50;
-    let random_number_cache = (0..(maxiterations * maxpoints_per_iter))
-        .map(|_| (js_random(), js_random(), js_random()))
-        .collect::<Vec<_>>();
+#[derive(Debug, Copy, Clone)]
+struct Kandidat {
+    pos: SvgPoint,
+    ref_pos: SvgPoint,
+    groesse: LabelGroesse,
+    winkel: AngleDegrees,
+    /// Label is outside of its Splitfläche and needs a leader line
+    mit_linie: bool,
+    ueberlappt_feature: bool,
+    /// Cost that does not depend on the other labels
+    kosten_fest: f64,
+}
 
-    let mut initial_text_pos_clone = initial_text_pos.to_vec();
-    initial_text_pos_clone.sort_by(|a, b| a.area.cmp(&b.area)); // label small areas first
-    let mut modifications = BTreeMap::new();
+impl Kandidat {
+    /// Box in the coordinate system of the label (origin `pos`, x along the text)
+    fn get_rect_lokal(&self) -> Rect {
+        self.groesse.get_rect(&SvgPoint { x: 0.0, y: 0.0 })
+    }
 
-    for (i, tp) in initial_text_pos_clone.iter().enumerate() {
-        let mut textpos_totry = vec![tp.pos];
-        let mut textpos_found = Vec::new();
-        let tp_width = tp.kuerzel.chars().count() as f64 * LABEL_WIDTH_PER_CHAR_M + 2.5;
+    fn to_lokal(&self, p: &SvgPoint) -> SvgPoint {
+        let (sin, cos) = self.winkel.to_radians().sin_cos();
+        let (dx, dy) = (p.x - self.pos.x, p.y - self.pos.y);
+        SvgPoint {
+            x: dx * cos + dy * sin,
+            y: -dx * sin + dy * cos,
+        }
+    }
 
-        let tp_triangles = tp.poly.get_triangle_points();
-        let mut taken_nearest_points = Vec::new();
+    fn get_ecken(&self) -> [SvgPoint; 4] {
+        let r = self.get_rect_lokal();
+        [
+            drehe(&self.pos, r.min_x, r.min_y, self.winkel),
+            drehe(&self.pos, r.max_x, r.min_y, self.winkel),
+            drehe(&self.pos, r.max_x, r.max_y, self.winkel),
+            drehe(&self.pos, r.min_x, r.max_y, self.winkel),
+        ]
+    }
 
-        for i in 0..maxiterations {
-            for newpostotry in textpos_totry.iter() {
-                let label_overlaps_background_feature =
-                    label_overlaps_feature(newpostotry, &background_boolmap, &config, tp_width);
+    fn get_bbox(&self) -> Rect {
+        let ecken = self.get_ecken();
+        let mut bbox = Rect {
+            min_x: ecken[0].x,
+            min_y: ecken[0].y,
+            max_x: ecken[0].x,
+            max_y: ecken[0].y,
+        };
+        for e in ecken.iter().skip(1) {
+            bbox.min_x = bbox.min_x.min(e.x);
+            bbox.min_y = bbox.min_y.min(e.y);
+            bbox.max_x = bbox.max_x.max(e.x);
+            bbox.max_y = bbox.max_y.max(e.y);
+        }
+        bbox
+    }
 
-                let label_overlaps_other_label =
-                    label_overlaps_feature(newpostotry, &label_boolmap, &config, tp_width);
+    fn enthaelt(&self, p: &SvgPoint) -> bool {
+        let l = self.to_lokal(p);
+        let r = self.get_rect_lokal();
+        l.x >= r.min_x && l.x <= r.max_x && l.y >= r.min_y && l.y <= r.max_y
+    }
 
-                let label_overlaps_other_line =
-                    label_overlaps_feature(newpostotry, &lines_boolmap, &config, tp_width);
+    fn linie_schneidet(&self, a: &SvgPoint, b: &SvgPoint) -> bool {
+        segment_schneidet_rect(&self.to_lokal(a), &self.to_lokal(b), &self.get_rect_lokal())
+    }
 
-                let nearest_point = tp_triangles
-                    .iter()
-                    .filter_map(|t| {
-                        if taken_nearest_points.iter().any(|q: &SvgPoint| q.equals(t)) {
-                            None
-                        } else {
-                            Some(*t)
-                        }
-                    })
-                    .min_by_key(|s| (s.dist(newpostotry) * 1000.0) as usize)
-                    .unwrap_or(tp.pos);
-
-                let line_will_overlap_other_label =
-                    test_line_will_intersect(newpostotry, &nearest_point, &label_boolmap, &config)
-                        as u64;
-
-                let line_will_overlap_other_line =
-                    test_line_will_intersect(newpostotry, &nearest_point, &lines_boolmap, &config)
-                        as u64;
-
-                let line_will_overlap_background = test_line_will_intersect(
-                    newpostotry,
-                    &nearest_point,
-                    &background_boolmap,
-                    &config,
-                ) as u64;
-
-                let distance = newpostotry.dist(&nearest_point);
-
-                let penalty = if label_overlaps_background_feature
-                    || label_overlaps_other_label
-                    || label_overlaps_other_line
-                {
-                    u64::MAX
-                } else {
-                    (distance * 10.0).round() as u64
-                        + (line_will_overlap_other_label * 1_000_000)
-                        + (line_will_overlap_other_line * 10_000)
-                        + (line_will_overlap_background * 1_000)
+    /// Separating axis test of the two (rotated) boxes
+    fn ueberlappt(&self, other: &Kandidat) -> bool {
+        let (ea, eb) = (self.get_ecken(), other.get_ecken());
+        [self.winkel, other.winkel].iter().all(|w| {
+            let (sin, cos) = w.to_radians().sin_cos();
+            [(cos, sin), (-sin, cos)].iter().all(|(ax, ay)| {
+                let projiziere = |e: &[SvgPoint; 4]| {
+                    e.iter().map(|p| p.x * ax + p.y * ay).fold(
+                        (f64::MAX, f64::MIN),
+                        |(min, max), v| (min.min(v), max.max(v)),
+                    )
                 };
+                let ((min_a, max_a), (min_b, max_b)) = (projiziere(&ea), projiziere(&eb));
+                min_a < max_b && min_b < max_a
+            })
+        })
+    }
+}
 
-                textpos_found.push((penalty, *newpostotry, nearest_point));
-                taken_nearest_points.push(nearest_point);
-            }
+/// `pos` + (x, y) rotated by `winkel`
+fn drehe(pos: &SvgPoint, x: f64, y: f64, winkel: AngleDegrees) -> SvgPoint {
+    let (sin, cos) = winkel.to_radians().sin_cos();
+    SvgPoint {
+        x: pos.x + x * cos - y * sin,
+        y: pos.y + x * sin + y * cos,
+    }
+}
 
-            if !textpos_found.iter().any(|(penalty, _pos, _)| *penalty < 5) {
-                let np = gen_new_points(&tp.pos, i, maxpoints_per_iter, &random_number_cache);
-                textpos_totry = np;
-            } else {
-                break;
+/// Angle of the long side of the minimum rotated rectangle (-90..90 degrees),
+/// if the polygon is long and narrow
+fn get_hauptachse(poly: &SvgPolygonInner) -> Option<AngleDegrees> {
+    use geo::MinimumRotatedRect;
+
+    let rect = translate_to_geo_poly_special_shared(&[poly]).minimum_rotated_rect()?;
+    let p = rect.exterior().0.clone();
+    let (a, b, c) = (p.get(0)?, p.get(1)?, p.get(2)?);
+    let seite1 = ((b.x - a.x), (b.y - a.y));
+    let seite2 = ((c.x - b.x), (c.y - b.y));
+    let laenge = |s: (f64, f64)| (s.0 * s.0 + s.1 * s.1).sqrt();
+    let (lang, kurz) = if laenge(seite1) >= laenge(seite2) {
+        (seite1, seite2)
+    } else {
+        (seite2, seite1)
+    };
+    if laenge(kurz) <= 0.0 || laenge(lang) / laenge(kurz) < GEDREHT_AB_SEITENVERHAELTNIS {
+        return None;
+    }
+
+    let mut winkel = lang.1.atan2(lang.0).to_degrees();
+    if winkel > 90.0 {
+        winkel -= 180.0;
+    } else if winkel <= -90.0 {
+        winkel += 180.0;
+    }
+    if winkel.abs() < GEDREHT_MIN_WINKEL {
+        return None;
+    }
+    Some(winkel)
+}
+
+struct Label<'a> {
+    tp: &'a TextPlacement,
+    groesse: LabelGroesse,
+    hauptachse: Option<AngleDegrees>,
+    ankerpunkte: Vec<SvgPoint>,
+    kandidaten: Vec<Kandidat>,
+    ringe: usize,
+    /// Pinned by an override, the only candidate is never moved
+    fixiert: bool,
+}
+
+impl<'a> Label<'a> {
+    fn add_ring(
+        &mut self,
+        background: &ndarray::Array2<bool>,
+        config: &OptimizeConfig,
+        rng: &mut XorShiftRng,
+    ) {
+        let neu = if self.ringe == 0 {
+            vec![self.tp.pos]
+        } else {
+            let abstand = config.mm_to_m(RING_ABSTAND_MM);
+            gen_new_points(&self.tp.pos, self.ringe, abstand, PUNKTE_PRO_RING, rng)
+        };
+        self.ringe += 1;
+
+        let gedreht = self.hauptachse.map(|winkel| {
+            // centered on the candidate position
+            let g = &self.groesse;
+            (winkel, -g.breite_m / 2.0, -(g.oberlaenge_m - g.unterlaenge_m) / 2.0)
+        });
+
+        for p in neu {
+            self.add_kandidat(p, 0.0, None, background, config);
+            if let Some((winkel, dx, dy)) = gedreht {
+                self.add_kandidat(drehe(&p, dx, dy, winkel), winkel, None, background, config);
             }
         }
+    }
+
+    fn add_kandidat(
+        &mut self,
+        pos: SvgPoint,
+        winkel: AngleDegrees,
+        ref_pos: Option<SvgPoint>,
+        background: &ndarray::Array2<bool>,
+        config: &OptimizeConfig,
+    ) {
+        let ref_pos = ref_pos.unwrap_or_else(|| {
+            self.ankerpunkte
+                .iter()
+                .min_by(|a, b| a.dist(&pos).total_cmp(&b.dist(&pos)))
+                .copied()
+                .unwrap_or(self.tp.pos)
+        });
+        let mut k = Kandidat {
+            pos,
+            ref_pos,
+            groesse: self.groesse,
+            winkel,
+            mit_linie: !crate::nas::point_is_in_polygon(&pos, &self.tp.poly),
+            ueberlappt_feature: false,
+            kosten_fest: 0.0,
+        };
+        k.ueberlappt_feature = label_overlaps_feature(&k, background, config);
+        k.kosten_fest = pos.dist(&ref_pos) * KOSTEN_DISTANZ_PRO_M;
+        if k.ueberlappt_feature {
+            k.kosten_fest += KOSTEN_UEBERLAPPUNG;
+        }
+        if k.mit_linie {
+            k.kosten_fest += test_line_will_intersect(&pos, &ref_pos, background, config) as f64
+                * KOSTEN_LINIE_UEBER_FEATURE;
+        }
+        if winkel != 0.0 {
+            k.kosten_fest += KOSTEN_GEDREHT;
+        }
+        self.kandidaten.push(k);
+    }
+}
+
+/// Cost between two placed labels, `true` if the labels overlap
+fn kosten_paar(a: &Kandidat, b: &Kandidat) -> (f64, bool) {
+    let ueberlappt = a.ueberlappt(b);
+    let mut kosten = if ueberlappt { KOSTEN_UEBERLAPPUNG } else { 0.0 };
+    if a.mit_linie && b.linie_schneidet(&a.pos, &a.ref_pos) {
+        kosten += KOSTEN_LINIE_UEBER_LABEL;
+    }
+    if b.mit_linie && a.linie_schneidet(&b.pos, &b.ref_pos) {
+        kosten += KOSTEN_LINIE_UEBER_LABEL;
+    }
+    if a.mit_linie
+        && b.mit_linie
+        && segmente_schneiden(&a.pos, &a.ref_pos, &b.pos, &b.ref_pos)
+    {
+        kosten += KOSTEN_LINIEN_KREUZUNG;
+    }
+    (kosten, ueberlappt)
+}
+
+fn kosten_label(i: usize, k: &Kandidat, platziert: &[Option<Kandidat>]) -> f64 {
+    k.kosten_fest
+        + platziert
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .filter_map(|(_, p)| p.as_ref())
+            .map(|p| kosten_paar(k, p).0)
+            .sum::<f64>()
+}
+
+fn gesamtkosten(platziert: &[Option<Kandidat>]) -> f64 {
+    platziert
+        .iter()
+        .enumerate()
+        .filter_map(|(i, k)| Some((i, k.as_ref()?)))
+        .map(|(i, k)| {
+            k.kosten_fest
+                + platziert
+                    .iter()
+                    .skip(i + 1)
+                    .filter_map(|p| p.as_ref())
+                    .map(|p| kosten_paar(k, p).0)
+                    .sum::<f64>()
+        })
+        .sum()
+}
+
+fn ist_unguenstig(platziert: &[Option<Kandidat>]) -> bool {
+    platziert.iter().enumerate().any(|(i, k)| {
+        k.as_ref()
+            .map(|k| kosten_label(i, k, platziert) >= KOSTEN_AUSREICHEND)
+            .unwrap_or(false)
+    })
+}
+
+fn place_labels(
+    texte: &[TextPlacement],
+    background: &ndarray::Array2<bool>,
+    config: &OptimizeConfig,
+) -> OptimizeResult {
+    let mut rng = XorShiftRng::seed_from_u64(config.seed);
 
-        textpos_found.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut labels = texte
+        .iter()
+        .map(|tp| Label {
+            tp,
+            groesse: config.get_label_groesse(&tp.kuerzel),
+            hauptachse: get_hauptachse(&tp.poly),
+            ankerpunkte: tp.poly.get_triangle_points(),
+            kandidaten: Vec::new(),
+            ringe: 0,
+            fixiert: false,
+        })
+        .collect::<Vec<_>>();
 
-        let (_least_penalty, newpos, newtargetpos) =
-            textpos_found
-                .first()
-                .cloned()
-                .unwrap_or((u64::MAX, tp.pos, tp.pos));
+    // pinned labels first, so that the others avoid them
+    let mut platziert: Vec<Option<Kandidat>> = vec![None; labels.len()];
+    let mut aktuell = vec![0_usize; labels.len()];
+    let fixierte_labels = get_fixierte_labels(texte, &config.beschriftungen);
+    for (i, o) in fixierte_labels.iter().enumerate() {
+        let o = match o {
+            Some(s) => s,
+            None => continue,
+        };
+        labels[i].add_kandidat(o.pos, o.winkel, o.ref_pos, background, config);
+        labels[i].fixiert = true;
+        platziert[i] = labels[i].kandidaten.first().copied();
+    }
 
-        paint_label_onto_map(&newpos, &mut label_boolmap, &config, tp_width);
+    // greedy: best candidate given the labels placed so far
+    for i in 0..labels.len() {
+        if labels[i].fixiert {
+            continue;
+        }
+        let mut beste = (f64::MAX, 0);
+        while labels[i].ringe <= MAX_RINGE {
+            let von = labels[i].kandidaten.len();
+            labels[i].add_ring(background, config, &mut rng);
+            for (ki, k) in labels[i].kandidaten.iter().enumerate().skip(von) {
+                let kosten = kosten_label(i, k, &platziert);
+                if kosten < beste.0 {
+                    beste = (kosten, ki);
+                }
+            }
+            if beste.0 < KOSTEN_AUSREICHEND {
+                break;
+            }
+        }
+        aktuell[i] = beste.1;
+        platziert[i] = labels[i].kandidaten.get(beste.1).copied();
+    }
 
-        paint_line_onto_map(&newpos, &newtargetpos, &mut lines_boolmap, &config);
+    // repair: simulated annealing, moving one label at a time
+    let mut kosten = gesamtkosten(&platziert);
+    let mut beste_kosten = kosten;
+    let mut beste_platzierung = aktuell.clone();
+    let beweglich = (0..labels.len())
+        .filter(|i| !labels[*i].fixiert)
+        .collect::<Vec<_>>();
+    let schritte = (beweglich.len() * SA_SCHRITTE_PRO_LABEL).min(SA_MAX_SCHRITTE);
+    let abkuehlung = (SA_TEMPERATUR_ENDE / SA_TEMPERATUR_START).powf(1.0 / schritte.max(1) as f64);
+    let mut temperatur = SA_TEMPERATUR_START;
 
-        modifications.insert(i, (newpos, newtargetpos));
+    if !beweglich.is_empty() && ist_unguenstig(&platziert) {
+        for _ in 0..schritte {
+            temperatur *= abkuehlung;
+            let i = beweglich[rng.gen_range(0..beweglich.len())];
+            if labels[i].ringe <= MAX_RINGE {
+                labels[i].add_ring(background, config, &mut rng);
+            }
+            let ki = rng.gen_range(0..labels[i].kandidaten.len());
+            if ki == aktuell[i] {
+                continue;
+            }
+            let alt = match platziert[i].as_ref() {
+                Some(s) => kosten_label(i, s, &platziert),
+                None => continue,
+            };
+            let neu_kandidat = labels[i].kandidaten[ki];
+            let delta = kosten_label(i, &neu_kandidat, &platziert) - alt;
+            if delta <= 0.0 || rng.gen::<f64>() < (-delta / temperatur).exp() {
+                platziert[i] = Some(neu_kandidat);
+                aktuell[i] = ki;
+                kosten += delta;
+                if kosten < beste_kosten {
+                    beste_kosten = kosten;
+                    beste_platzierung = aktuell.clone();
+                }
+            }
+        }
     }
 
-    initial_text_pos_clone
+    let platziert = beste_platzierung
         .iter()
         .enumerate()
-        .map(|(i, tp)| {
-            let optimized_pos = modifications.get(&i).cloned().unwrap_or((tp.pos, tp.pos));
+        .map(|(i, ki)| labels[i].kandidaten.get(*ki).copied())
+        .collect::<Vec<_>>();
+
+    let nicht_platziert = platziert
+        .iter()
+        .enumerate()
+        .filter(|(i, k)| match k {
+            Some(k) => {
+                k.ueberlappt_feature
+                    || platziert
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| j != i)
+                        .filter_map(|(_, p)| p.as_ref())
+                        .any(|p| kosten_paar(k, p).1)
+            }
+            None => true,
+        })
+        .count();
+
+    let texte = texte
+        .iter()
+        .zip(platziert.iter())
+        .map(|(tp, k)| {
+            let (pos, ref_pos, winkel) = k
+                .map(|k| (k.pos, k.ref_pos, k.winkel))
+                .unwrap_or((tp.pos, tp.pos, 0.0));
             OptimizedTextPlacement {
                 optimized: TextPlacement {
                     kuerzel: tp.kuerzel.clone(),
                     status: tp.status.clone(),
-                    pos: optimized_pos.0, // TODO
-                    ref_pos: optimized_pos.1,
+                    pos,
+                    ref_pos,
                     area: tp.area.clone(),
                     poly: tp.poly.clone(),
+                    splitflaeche: tp.splitflaeche.clone(),
                 },
                 original: tp.clone(),
+                winkel,
             }
         })
-        .collect()
+        .collect();
+
+    OptimizeResult {
+        texte,
+        kosten: gesamtkosten(&platziert),
+        nicht_platziert,
+        fixiert: fixierte_labels.iter().filter(|o| o.is_some()).count(),
+    }
 }
 
 fn gen_new_points(
     p: &SvgPoint,
-    iteration: usize,
+    ring: usize,
+    ring_abstand_m: f64,
     maxpoints: usize,
-    cache: &[(f64, f64, f64)],
+    rng: &mut XorShiftRng,
 ) -> Vec<SvgPoint> {
     (0..maxpoints)
-        .map(|i| {
-            let random1 = cache[(iteration * maxpoints) + i];
-            let t = 2.0 * PI * random1.0;
-            let u = random1.1 + random1.2;
+        .map(|_| {
+            let t = 2.0 * PI * rng.gen::<f64>();
+            let u = rng.gen::<f64>() + rng.gen::<f64>();
             let r = if u > 1.0 { 2.0 - u } else { u };
-            let maxdst = (iteration + 1) as f64 * 4.0;
+            let maxdst = ring as f64 * ring_abstand_m;
             let xshift = r * t.cos() * maxdst;
             let yshift = r * t.sin() * maxdst;
             p.translate(xshift, yshift)
@@ -301,6 +874,32 @@ fn gen_new_points(
         .collect()
 }
 
+fn orientierung(a: &SvgPoint, b: &SvgPoint, c: &SvgPoint) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+fn segmente_schneiden(a1: &SvgPoint, a2: &SvgPoint, b1: &SvgPoint, b2: &SvgPoint) -> bool {
+    let d1 = orientierung(b1, b2, a1);
+    let d2 = orientierung(b1, b2, a2);
+    let d3 = orientierung(a1, a2, b1);
+    let d4 = orientierung(a1, a2, b2);
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+fn segment_schneidet_rect(a: &SvgPoint, b: &SvgPoint, r: &Rect) -> bool {
+    let drin = |p: &SvgPoint| p.x > r.min_x && p.x < r.max_x && p.y > r.min_y && p.y < r.max_y;
+    if drin(a) || drin(b) {
+        return true;
+    }
+    let ecken = [
+        SvgPoint { x: r.min_x, y: r.min_y },
+        SvgPoint { x: r.max_x, y: r.min_y },
+        SvgPoint { x: r.max_x, y: r.max_y },
+        SvgPoint { x: r.min_x, y: r.max_y },
+    ];
+    (0..4).any(|i| segmente_schneiden(a, b, &ecken[i], &ecken[(i + 1) % 4]))
+}
+
 // returns how many lines this position will intersect
 fn test_line_will_intersect(
     start: &SvgPoint,
@@ -331,69 +930,32 @@ fn test_line_will_intersect(
     intersections
 }
 
-fn paint_line_onto_map(
-    start: &SvgPoint,
-    end: &SvgPoint,
-    map: &mut ndarray::Array2<bool>,
-    config: &OptimizeConfig,
-) {
-    use bresenham::Bresenham;
-
-    let start = config.point_to_pixel(start);
-    let end = config.point_to_pixel(end);
-
-    for (x, y) in Bresenham::new(
-        (start.x as isize, start.y as isize),
-        (end.x as isize, end.y as isize),
-    ) {
-        match map.get_mut((y.max(0) as usize, x.max(0) as usize)) {
-            Some(s) => {
-                *s = true;
-            }
-            _ => {}
-        }
-    }
-}
-
-fn paint_label_onto_map(
-    point: &SvgPoint,
-    map: &mut ndarray::Array2<bool>,
-    config: &OptimizeConfig,
-    tp_width: f64,
-) -> bool {
-    let pixel = config.point_to_pixel(point);
-    let label_height_px = config.label_height_pixel();
-    let label_width_px = config.label_width_pixel(tp_width);
-
-    for y_test in 0..label_height_px {
-        for x_test in 0..label_width_px {
-            match map.get_mut((pixel.y.saturating_sub(y_test), pixel.x + x_test)) {
-                Some(s) => {
-                    *s = true;
-                }
-                _ => {}
-            }
-        }
-    }
-
-    false
-}
-
 fn label_overlaps_feature(
-    point: &SvgPoint,
+    k: &Kandidat,
     map: &ndarray::Array2<bool>,
     config: &OptimizeConfig,
-    tp_width: f64,
 ) -> bool {
-    let pixel = config.point_to_pixel(point);
-    let label_height_px = config.label_height_pixel();
-    let label_width_px = config.label_width_pixel(tp_width);
+    let rect = k.get_bbox();
+    let oben_links = config.point_to_pixel(&SvgPoint {
+        x: rect.min_x,
+        y: rect.max_y,
+    });
+    let unten_rechts = config.point_to_pixel(&SvgPoint {
+        x: rect.max_x,
+        y: rect.min_y,
+    });
 
-    for y_test in 0..label_height_px {
-        for x_test in 0..label_width_px {
-            match map.get((pixel.y.saturating_sub(y_test), pixel.x + x_test)) {
+    for y_test in oben_links.y..=unten_rechts.y {
+        for x_test in oben_links.x..=unten_rechts.x {
+            match map.get((y_test, x_test)) {
                 Some(s) => {
-                    if *s {
+                    if *s
+                        && (k.winkel == 0.0
+                            || k.enthaelt(&config.pixel_to_point(&Pixel {
+                                x: x_test,
+                                y: y_test,
+                            })))
+                    {
                         return true;
                     }
                 }
@@ -405,18 +967,6 @@ fn label_overlaps_feature(
     false
 }
 
-fn initialize_empty_boolmap(config: &OptimizeConfig) -> Option<ndarray::Array2<bool>> {
-    let r = geo_rasterize::BinaryBuilder::new()
-        .width(config.width_pixels)
-        .height(config.height_pixels)
-        .build()
-        .ok()?;
-
-    let pixels = r.finish();
-
-    Some(pixels)
-}
-
 fn render_stage1_overlap_boolmap(
     flurstuecke: &SplitNasXml,
     splitflaechen: &[AenderungenIntersection],
@@ -468,3 +1018,237 @@ fn render_stage1_overlap_boolmap(
 
     Some(pixels)
 }
+
+#[cfg(test)]
+use crate::test_utils::test_poly;
+
+/// 100 x 100 mm sheet with the map extent starting at (0, 0)
+#[cfg(test)]
+fn test_riss(scale: f32, beschriftungen: Vec<BeschriftungOverride>) -> (RissConfig, RissExtentReprojected) {
+    let riss = RissConfig {
+        width_mm: 100.0,
+        height_mm: 100.0,
+        scale,
+        seed: 42,
+        beschriftungen,
+        ..Default::default()
+    };
+    let extent = RissExtentReprojected {
+        crs: String::new(),
+        scale: scale as f64,
+        min_x: 0.0,
+        max_x: scale as f64 / 10.0,
+        min_y: 0.0,
+        max_y: scale as f64 / 10.0,
+        rissgebiet: None,
+    };
+    (riss, extent)
+}
+
+#[test]
+fn test_place_labels_deterministic() {
+    let quadrat = test_poly(&[(40.0, 40.0), (40.0, 60.0), (60.0, 60.0), (60.0, 40.0), (40.0, 40.0)]);
+    let tp = |kuerzel: &str| TextPlacement {
+        kuerzel: kuerzel.to_string(),
+        status: TextStatus::New,
+        pos: SvgPoint { x: 45.0, y: 45.0 },
+        ref_pos: SvgPoint { x: 45.0, y: 45.0 },
+        area: 400,
+        poly: quadrat.clone(),
+        splitflaeche: None,
+    };
+    let texte = vec![tp("GR"), tp("A"), tp("WALD")];
+
+    let (riss, extent) = test_riss(1000.0, Vec::new());
+    let config = OptimizeConfig::new(&riss, &extent, 6.0);
+    let background = ndarray::Array2::from_elem((config.height_pixels, config.width_pixels), false);
+
+    let a = place_labels(&texte, &background, &config);
+    let b = place_labels(&texte, &background, &config);
+    let positionen = |r: &OptimizeResult| {
+        r.texte
+            .iter()
+            .map(|t| (t.optimized.pos, t.optimized.ref_pos))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(positionen(&a), positionen(&b));
+    assert_eq!(a.kosten, b.kosten);
+    assert_eq!(a.nicht_platziert, 0);
+    assert!(a.kosten < KOSTEN_UEBERLAPPUNG);
+}
+
+#[test]
+fn test_label_groesse() {
+    let config = |scale: f32| {
+        let (riss, extent) = test_riss(scale, Vec::new());
+        OptimizeConfig::new(&riss, &extent, 6.0)
+    };
+    let c1000 = config(1000.0);
+    let c5000 = config(5000.0);
+
+    let wald = c1000.get_label_groesse("WALD");
+    let gr_a = c1000.get_label_groesse("GR / A");
+    assert!(gr_a.breite_m > wald.breite_m);
+    // 6 pt = 2.1 mm text height = 2.1 m at 1:1000
+    assert!(wald.oberlaenge_m + wald.unterlaenge_m > 1.5);
+    assert!(wald.oberlaenge_m + wald.unterlaenge_m < 2.5);
+
+    // Helvetica-Bold: G = 0.778 em, R = 0.722 em, plus half of the outline
+    let gr: Mm = Pt(1.5 * 6.0).into();
+    assert!((c1000.get_label_groesse("GR").breite_m - gr.0 as f64).abs() < 0.001);
+    let kontur: Mm = Pt(BESCHRIFTUNG_KONTUR_PT / 2.0).into();
+    assert!((wald.padding_m - LABEL_PADDING_MM - kontur.0 as f64).abs() < 0.001);
+
+    let wald_5000 = c5000.get_label_groesse("WALD");
+    assert!((wald_5000.breite_m / wald.breite_m - 5.0).abs() < 0.001);
+    // same number of pixels per sheet, whatever the scale
+    assert_eq!(c1000.width_pixels, c5000.width_pixels);
+}
+
+#[test]
+fn test_gedrehte_labels() {
+    // 4 m wide ditch from (10, 10) to (90, 90)
+    let graben = test_poly(&[
+        (10.0, 12.0),
+        (88.0, 90.0),
+        (90.0, 88.0),
+        (12.0, 10.0),
+        (10.0, 12.0),
+    ]);
+    let winkel = get_hauptachse(&graben).unwrap();
+    assert!((winkel - 45.0).abs() < 0.5);
+
+    let quadrat = test_poly(&[(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0), (0.0, 0.0)]);
+    assert!(get_hauptachse(&quadrat).is_none());
+
+    let kandidat = |x: f64, y: f64, winkel: AngleDegrees| Kandidat {
+        pos: SvgPoint { x, y },
+        ref_pos: SvgPoint { x, y },
+        groesse: LabelGroesse {
+            breite_m: 10.0,
+            oberlaenge_m: 2.0,
+            unterlaenge_m: 0.5,
+            padding_m: 0.0,
+        },
+        winkel,
+        mit_linie: false,
+        ueberlappt_feature: false,
+        kosten_fest: 0.0,
+    };
+
+    let gedreht = kandidat(0.0, 0.0, 45.0);
+    assert!(gedreht.enthaelt(&drehe(&gedreht.pos, 5.0, 1.0, 45.0)));
+    assert!(!gedreht.enthaelt(&SvgPoint { x: 5.0, y: 1.0 }));
+    assert!(gedreht.ueberlappt(&kandidat(3.0, 3.0, 0.0)));
+    assert!(!gedreht.ueberlappt(&kandidat(20.0, 0.0, 0.0)));
+    assert!(!gedreht.ueberlappt(&kandidat(5.0, -1.0, 0.0)));
+}
+
+#[test]
+fn test_fixierte_labels() {
+    let quadrat = test_poly(&[(40.0, 40.0), (40.0, 60.0), (60.0, 60.0), (60.0, 40.0), (40.0, 40.0)]);
+    let splitflaeche = AenderungenIntersection {
+        alt: "GR".to_string(),
+        neu: "A".to_string(),
+        flst_id_part: "120001001000010000__:AX_Grünland:DEBBAL0000000001".to_string(),
+        poly_cut: quadrat.clone(),
+        ..Default::default()
+    };
+    let start = SvgPoint { x: 45.0, y: 45.0 };
+    let tp = |kuerzel: &str, status: TextStatus| TextPlacement {
+        kuerzel: kuerzel.to_string(),
+        status,
+        pos: start,
+        ref_pos: start,
+        area: 400,
+        poly: quadrat.clone(),
+        splitflaeche: Some(splitflaeche.get_splitflaeche_id()),
+    };
+    let texte = vec![tp("GR", TextStatus::Old), tp("A", TextStatus::New)];
+
+    // "A" pinned on the start position of both labels
+    let fixiert = BeschriftungOverride {
+        splitflaeche: Some(splitflaeche.get_splitflaeche_id()),
+        kuerzel: "A".to_string(),
+        status: TextStatus::New,
+        anker: start,
+        pos: start,
+        ref_pos: None,
+        winkel: 0.0,
+    };
+    let verwaist = BeschriftungOverride {
+        splitflaeche: Some(SplitflaecheId {
+            neu: "WALD".to_string(),
+            ..splitflaeche.get_splitflaeche_id()
+        }),
+        kuerzel: "WALD".to_string(),
+        ..fixiert.clone()
+    };
+
+    let (riss, extent) = test_riss(1000.0, vec![fixiert.clone(), verwaist.clone()]);
+    let config = OptimizeConfig::new(&riss, &extent, 6.0);
+    let background = ndarray::Array2::from_elem((config.height_pixels, config.width_pixels), false);
+
+    let r = place_labels(&texte, &background, &config);
+    assert_eq!(r.fixiert, 1);
+    assert_eq!(r.nicht_platziert, 0);
+    assert_eq!(r.texte[1].optimized.pos, start);
+    assert_ne!(r.texte[0].optimized.pos, start);
+
+    let verwaiste = get_verwaiste_overrides(&riss.beschriftungen, &[splitflaeche]);
+    assert_eq!(verwaiste, vec![&verwaist]);
+}
+
+#[test]
+fn test_fixierte_labels_future() {
+    use crate::ui::AenderungenIntersections;
+
+    let splitflaeche = |alt: &str, min_x: f64, teil: &str| AenderungenIntersection {
+        alt: alt.to_string(),
+        neu: "WALD".to_string(),
+        flst_id: "120001001000010000".to_string(),
+        flst_id_part: format!("120001001000010000__:{teil}"),
+        poly_cut: test_poly(&[
+            (min_x, 40.0),
+            (min_x, 60.0),
+            (min_x + 20.0, 60.0),
+            (min_x + 20.0, 40.0),
+            (min_x, 40.0),
+        ]),
+    };
+    let splitflaechen = vec![splitflaeche("GR", 40.0, "a"), splitflaeche("WALD", 60.0, "b")];
+    let pinned = SvgPoint { x: 55.0, y: 55.0 };
+    let fixiert = BeschriftungOverride {
+        splitflaeche: Some(splitflaechen[0].get_splitflaeche_id()),
+        kuerzel: "WALD".to_string(),
+        status: TextStatus::New,
+        anker: SvgPoint::default(),
+        pos: pinned,
+        ref_pos: None,
+        winkel: 0.0,
+    };
+    let sichtbar = test_poly(&[(0.0, 0.0), (0.0, 100.0), (100.0, 100.0), (100.0, 0.0), (0.0, 0.0)]);
+    let platziert = |texte: &[TextPlacement], beschriftungen: Vec<BeschriftungOverride>| {
+        let (riss, extent) = test_riss(1000.0, beschriftungen);
+        let config = OptimizeConfig::new(&riss, &extent, 6.0);
+        let background = ndarray::Array2::from_elem((config.height_pixels, config.width_pixels), false);
+        place_labels(texte, &background, &config)
+    };
+
+    // changed Splitflächen (PDF, GRAFBAT)
+    let texte = AenderungenIntersections::get_texte(&splitflaechen, &sichtbar);
+    let r = platziert(&texte, vec![fixiert.clone()]);
+    assert_eq!(r.fixiert, 1);
+    let neu = r.texte.iter().find(|t| t.optimized.status == TextStatus::New).unwrap();
+    assert_eq!(neu.optimized.pos, pinned);
+
+    // merged future Splitflächen (Hintergrund-Vorschau)
+    let future = AenderungenIntersections(splitflaechen.clone()).get_future_flaechen();
+    assert!(future.0.iter().all(|f| f.flst_id_part.is_empty()));
+    let overrides = get_future_overrides(&[fixiert], &splitflaechen, &future.0);
+    assert_eq!(overrides.len(), 1);
+    let texte = AenderungenIntersections::get_texte(&future.0, &sichtbar);
+    let r = platziert(&texte, overrides);
+    assert_eq!(r.fixiert, 1);
+    assert!(r.texte.iter().any(|t| t.optimized.kuerzel == "WALD" && t.optimized.pos == pinned));
+}
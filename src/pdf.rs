@@ -1,4 +1,8 @@
 use crate::{
+    anschlussrisse::{
+        AnschlussrissMarker,
+        ANSCHLUSSRISS_TEXT_HOEHE_MM,
+    },
     csv::CsvDataType,
     geograf::{
         get_default_riss_extent,
@@ -21,8 +25,13 @@ use crate::{
     optimize::{
         OptimizeConfig,
         OptimizedTextPlacement,
+        BESCHRIFTUNG_KONTUR_PT,
     },
     process::AngleDegrees,
+    rissplanung::{
+        BLATTKOPF_BREITE_MM,
+        BLATTKOPF_HOEHE_MM,
+    },
     ui::{
         Aenderungen,
         AenderungenIntersection,
@@ -79,6 +88,116 @@ pub struct Konfiguration {
     pub pdf: PdfStyleConfig,
     #[serde(default)]
     pub merge: MergeConfig,
+    #[serde(default)]
+    pub nas: NasImportConfig,
+    #[serde(default)]
+    pub pruefung: PruefungConfig,
+    #[serde(default)]
+    pub antrag: AntragskopfConfig,
+    #[serde(default)]
+    pub signaturen: SignaturKatalog,
+    #[serde(default)]
+    pub rissplanung: RissPlanungConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NasImportConfig {
+    /// Max. deviation in meters when converting GML arcs / circles to lines
+    #[serde(default = "arc_chord_tolerance_m")]
+    pub arc_chord_tolerance_m: f64,
+}
+
+impl Default for NasImportConfig {
+    fn default() -> Self {
+        Self {
+            arc_chord_tolerance_m: arc_chord_tolerance_m(),
+        }
+    }
+}
+
+fn arc_chord_tolerance_m() -> f64 {
+    crate::nas::ARC_CHORD_TOLERANCE_M
+}
+
+/// Thresholds for the Prüfprotokoll before the GEOgraf / DAVID export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruefungConfig {
+    /// Changed Splitflächen below this area (m²) are reported
+    #[serde(default = "one")]
+    pub min_splitflaeche_m2: f64,
+    /// Overlaps of new polygons below this area (m²) are ignored
+    #[serde(default = "zero_point_two")]
+    pub max_ueberlappung_m2: f64,
+}
+
+impl Default for PruefungConfig {
+    fn default() -> Self {
+        Self {
+            min_splitflaeche_m2: one(),
+            max_ueberlappung_m2: zero_point_two(),
+        }
+    }
+}
+
+/// Antragskopf of the DAVID Fortführungsauftrag, see [crate::david::Antragskopf]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AntragskopfConfig {
+    pub profilkennung: String,
+    /// Dienststellenschlüssel of the Katasteramt, e.g. "73_0073"
+    pub dienststelle: String,
+    /// Placeholders: {dienststelle}, {datum} (YYYYMMDD), {auftragsart},
+    /// {antragsnr} and {katasteramt} (from the ProjektInfo)
+    pub antragsnummer_schema: String,
+    /// Same placeholders as `antragsnummer_schema`, empty: the Antragsnummer
+    pub auftragsnummer_schema: String,
+    pub auftragsart: String,
+    pub verarbeitungsart: String,
+    pub implizite_loeschung_der_reservierung: String,
+    /// AdV CRS id ("ETRS89_UTM33"), empty: CRS of the NAS file
+    pub crs: String,
+    /// Prefix of the OIDs of inserted objects: "DE" + Land + Vermessungsstelle,
+    /// see [crate::oid::OidGenerator]
+    pub oid_praefix: String,
+}
+
+impl Default for AntragskopfConfig {
+    fn default() -> Self {
+        Self {
+            profilkennung: "schuettf".to_string(),
+            dienststelle: "73_0073".to_string(),
+            antragsnummer_schema: "{dienststelle}_{datum}_{auftragsart}".to_string(),
+            auftragsnummer_schema: String::new(),
+            auftragsart: "999".to_string(),
+            verarbeitungsart: "2000".to_string(),
+            implizite_loeschung_der_reservierung: "4000".to_string(),
+            crs: String::new(),
+            oid_praefix: "DEBB0073".to_string(),
+        }
+    }
+}
+
+/// Allowed sheets for the automatic Riss layout, see [crate::rissplanung]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RissPlanungConfig {
+    /// "A4" - "A0"
+    pub formate: Vec<String>,
+    pub hochformat: bool,
+    pub querformat: bool,
+    /// e.g. 1000.0 for 1:1000
+    pub massstaebe: Vec<f32>,
+}
+
+impl Default for RissPlanungConfig {
+    fn default() -> Self {
+        Self {
+            formate: ["A4", "A3", "A2", "A1", "A0"].iter().map(|s| s.to_string()).collect(),
+            hochformat: true,
+            querformat: true,
+            massstaebe: vec![500.0, 1000.0, 2000.0, 2500.0, 3500.0, 5000.0],
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -168,9 +287,20 @@ pub struct PdfStyleConfig {
     pub beschriftungen: BTreeMap<String, PtoStil>,
     #[serde(default)]
     pub symbole: BTreeMap<String, PpoStil>,
+    /// Font size of the Nutzungsarten-Beschriftungen in pt
+    #[serde(default)]
+    pub beschriftung_schriftgroesse: Option<f32>,
 }
 
+pub const DEFAULT_BESCHRIFTUNG_SCHRIFTGROESSE: f32 = 6.0;
+
 impl PdfStyleConfig {
+    pub fn get_beschriftung_schriftgroesse(&self) -> f32 {
+        self.beschriftung_schriftgroesse
+            .filter(|s| *s > 0.0)
+            .unwrap_or(DEFAULT_BESCHRIFTUNG_SCHRIFTGROESSE)
+    }
+
     pub fn get_nutzungsarten_sorted(&self) -> Vec<(String, PdfEbenenStyle)> {
         self.layer_ordnung
             .iter()
@@ -263,6 +393,58 @@ pub struct PpoStil {
     pub svg_base64: Option<String>, // ...
 }
 
+// same format as the Konfiguration, shipped next to nutzung.json
+const SIGNATUREN_DEFAULT: &str = include_str!("../signaturen.json");
+
+/// Signatures (AP_PPO / AP_Darstellung) per Kürzel, used for the DAVID
+/// export and for the symbols in the PDF
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SignaturKatalog(pub BTreeMap<String, SignaturStil>);
+
+impl Default for SignaturKatalog {
+    fn default() -> Self {
+        Self(serde_json::from_str(SIGNATUREN_DEFAULT).unwrap_or_default())
+    }
+}
+
+impl SignaturKatalog {
+    /// Entries without Signaturnummer (just added in the Konfiguration) are skipped
+    pub fn get(&self, kuerzel: &str) -> Option<&SignaturStil> {
+        self.0
+            .get(kuerzel.trim())
+            .filter(|s| !s.signaturnummer.trim().is_empty())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturTyp {
+    /// AP_PPO at the label position
+    Punkt,
+    /// AP_Darstellung, symbols distributed over the area
+    Flaeche,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignaturStil {
+    pub typ: SignaturTyp,
+    pub signaturnummer: String,
+    pub art: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub positionierungsregel: Option<String>,
+    /// Symbol in the PDF: lines in mm relative to the insertion point
+    #[serde(default)]
+    pub pdf_linien: Vec<Vec<[f32; 2]>>,
+    /// Distance of the symbols of area signatures in the PDF (mm)
+    #[serde(default = "signatur_abstand_mm")]
+    pub pdf_abstand_mm: f32,
+}
+
+fn signatur_abstand_mm() -> f32 {
+    25.0
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RissExtent {
     pub coords: Vec<LatLng>,
@@ -402,10 +584,8 @@ impl RissExtentReprojected {
         }
     }
     pub fn get_poly(&self) -> SvgPolygonInner {
-        let header_width_mm = 175.0;
-        let header_height_mm = 35.0;
-        let header_width_m = header_width_mm * self.scale / 1000.0;
-        let header_height_m = header_height_mm * self.scale / 1000.0;
+        let header_width_m = BLATTKOPF_BREITE_MM * self.scale / 1000.0;
+        let header_height_m = BLATTKOPF_HOEHE_MM * self.scale / 1000.0;
 
         //
         //           4------5
@@ -487,6 +667,13 @@ pub struct RissConfig {
     pub scale: f32,
     #[serde(default)]
     pub rissgebiet: Option<SvgPolygon>,
+    /// Seed of the label optimizer, see [crate::optimize::optimize_labels]
+    #[serde(default)]
+    pub seed: u64,
+    /// Manually placed labels (label preview or [crate::grafbat] import),
+    /// pinned by [crate::optimize::optimize_labels]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub beschriftungen: Vec<crate::optimize::BeschriftungOverride>,
 }
 
 #[derive(
@@ -717,6 +904,7 @@ impl Flurstuecke {
                     poly,
                     status: TextStatus::StaysAsIs,
                     area: 1000,
+                    splitflaeche: None,
                 })
             })
             .collect()
@@ -787,6 +975,7 @@ impl HintergrundCache {
     ) -> Self {
         let target_dpi = 96.0;
         let tile_size_px = 1024.0;
+        let crs = crate::crs::get_crs_by_proj_or_default(target_crs);
 
         let mut tiles = Vec::new();
         let len = risse.len();
@@ -812,6 +1001,14 @@ impl HintergrundCache {
 
             for xi in 0..num_tiles_x {
                 for yi in 0..num_tiles_y {
+                    let min = crs.wms_point(&SvgPoint {
+                        x: rect.min_x + (xi as f64 * tile_wh_m),
+                        y: rect.min_y + (yi as f64 * tile_wh_m),
+                    });
+                    let max = crs.wms_point(&SvgPoint {
+                        x: rect.min_x + ((xi + 1) as f64 * tile_wh_m),
+                        y: rect.min_y + ((yi + 1) as f64 * tile_wh_m),
+                    });
                     let t = (
                         id,
                         xi as f64 * tile_wh_mm,
@@ -819,10 +1016,11 @@ impl HintergrundCache {
                         crate::uuid_wasm::FetchWmsImageRequest {
                             width_px: tile_size_px.round() as usize,
                             height_px: tile_size_px.round() as usize,
-                            max_x: SvgPoint::round_f64(rect.min_x + ((xi + 1) as f64 * tile_wh_m)),
-                            min_x: SvgPoint::round_f64(rect.min_x + (xi as f64 * tile_wh_m)),
-                            max_y: SvgPoint::round_f64(rect.min_y + ((yi + 1) as f64 * tile_wh_m)),
-                            min_y: SvgPoint::round_f64(rect.min_y + (yi as f64 * tile_wh_m)),
+                            max_x: SvgPoint::round_f64(max.x),
+                            min_x: SvgPoint::round_f64(min.x),
+                            max_y: SvgPoint::round_f64(max.y),
+                            min_y: SvgPoint::round_f64(min.y),
+                            srs: crs.wms_srs(),
                             dop_layers: dop_layers.clone(),
                             dop_source: dop_source.clone(),
                         },
@@ -832,9 +1030,7 @@ impl HintergrundCache {
             }
         }
 
-        web_sys::console::log_1(
-            &format!("Fetche {} WMS Hintergrund Kacheln...", tiles.len()).into(),
-        );
+        log_status(&format!("Fetche {} WMS Hintergrund Kacheln...", tiles.len()));
 
         let tiles_2 = tiles.iter().map(|s| s.3.clone()).collect::<Vec<_>>();
         let resolved_tiles = crate::uuid_wasm::get_wms_images(&tiles_2).await;
@@ -935,6 +1131,8 @@ pub async fn export_overview(
                 scale: SCALE_OVERVIEW as f32,
                 lat: latlon_center.y,
                 lon: latlon_center.x,
+                seed: 0,
+                beschriftungen: Vec::new(),
             };
             riss_extente_reprojected.push((rc, extent));
             min_x += width_m * 0.8;
@@ -1042,8 +1240,9 @@ pub async fn export_overview(
                 &gebaeude,
                 &[],
                 &aenderungen_texte,
-                &OptimizeConfig::new(&rc, &extent, 0.5 /* mm */),
-            );
+                &OptimizeConfig::new(&rc, &extent, konfiguration.pdf.get_beschriftung_schriftgroesse()),
+            )
+            .texte;
 
             let page = doc.get_page(page_idx);
             let mut layer = page.get_layer(layer_idx);
@@ -1103,6 +1302,7 @@ pub async fn export_overview(
                 &extent,
                 &rc,
                 &beschriftungen,
+                konfiguration.pdf.get_beschriftung_schriftgroesse(),
                 has_background,
             );
 
@@ -1142,6 +1342,7 @@ pub fn generate_pdf_internal(
     fluren: &Fluren,                           // in ETRS space,
     flst: &Flurstuecke,                        // in ETRS space
     gebaeude: &Gebaeude,                       // in ETRS space
+    anschlussrisse: &[AnschlussrissMarker],    // in ETRS space
 ) -> Vec<u8> {
     let (num_riss, total_risse) = riss_von;
 
@@ -1266,6 +1467,17 @@ pub fn generate_pdf_internal(
         .collect::<Vec<_>>();
     let _ = write_na_untergehend_linien(&mut layer, &na_untergehend_linien);
 
+    log_status(&format!(
+        "[{num_riss} / {total_risse}] Rendere Signaturen..."
+    ));
+    let _ = write_signaturen(
+        &mut layer,
+        riss_extent,
+        rc,
+        &beschriftungen,
+        &konfiguration.signaturen,
+    );
+
     log_status(&format!(
         "[{num_riss} / {total_risse}] Rendere Beschriftungen..."
     ));
@@ -1275,9 +1487,15 @@ pub fn generate_pdf_internal(
         riss_extent,
         rc,
         &beschriftungen,
+        konfiguration.pdf.get_beschriftung_schriftgroesse(),
         has_background,
     );
 
+    log_status(&format!(
+        "[{num_riss} / {total_risse}] Rendere Anschlussrisse..."
+    ));
+    let _ = write_anschlussrisse(&mut layer, &helvetica, riss_extent, rc, anschlussrisse);
+
     let _ = write_border(
         &mut layer,
         &rc,
@@ -1345,6 +1563,8 @@ pub fn reproject_rissgebiete_into_target_space(
             height_mm: v.height_mm,
             scale: v.scale,
             rissgebiet,
+            seed: v.seed,
+            beschriftungen: v.beschriftungen.clone(),
         }))
     }).collect()
 }
@@ -1742,12 +1962,114 @@ fn write_rote_linien(layer: &mut PdfLayerReference, linien: &[SvgLine]) -> Optio
     Some(())
 }
 
+/// Symbols of the new Nutzungsarten from the [SignaturKatalog], kept clear of the label
+fn write_signaturen(
+    layer: &mut PdfLayerReference,
+    riss_extent: &RissExtentReprojected,
+    riss: &RissConfig,
+    beschriftungen: &[OptimizedTextPlacement],
+    signaturen: &SignaturKatalog,
+) -> Option<()> {
+    const ABSTAND_TEXT_MM: f64 = 4.0;
+
+    layer.save_graphics_state();
+    layer.set_outline_color(printpdf::Color::Rgb(Rgb {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        icc_profile: None,
+    }));
+    layer.set_outline_thickness(0.5);
+    layer.set_line_cap_style(printpdf::LineCapStyle::Round);
+    layer.set_line_join_style(printpdf::LineJoinStyle::Round);
+
+    for b in beschriftungen.iter() {
+        if b.optimized.status == TextStatus::Old {
+            continue;
+        }
+        let stil = match signaturen.get(&b.optimized.kuerzel) {
+            Some(s) if !s.pdf_linien.is_empty() => s,
+            _ => continue,
+        };
+
+        let poly = poly_into_pdf_space(&b.optimized.poly, riss_extent, riss);
+        let text_pos = point_into_pdf_space(&b.optimized.pos, riss_extent, riss);
+
+        let punkte = match stil.typ {
+            SignaturTyp::Punkt => poly
+                .get_label_pos()
+                .map(|p| {
+                    if p.dist(&text_pos) < ABSTAND_TEXT_MM {
+                        p.translate(0.0, -ABSTAND_TEXT_MM)
+                    } else {
+                        p
+                    }
+                })
+                .into_iter()
+                .collect::<Vec<_>>(),
+            SignaturTyp::Flaeche => get_signatur_positionen(&poly, stil.pdf_abstand_mm as f64)
+                .into_iter()
+                .filter(|p| p.dist(&text_pos) >= ABSTAND_TEXT_MM)
+                .collect(),
+        };
+
+        for p in punkte {
+            for l in stil.pdf_linien.iter() {
+                layer.add_line(printpdf::Line {
+                    points: l
+                        .iter()
+                        .map(|[x, y]| {
+                            (
+                                printpdf::Point {
+                                    x: Mm(p.x as f32 + x).into_pt(),
+                                    y: Mm(p.y as f32 + y).into_pt(),
+                                },
+                                false,
+                            )
+                        })
+                        .collect(),
+                    is_closed: false,
+                });
+            }
+        }
+    }
+
+    layer.restore_graphics_state();
+
+    Some(())
+}
+
+/// Grid points (with `abstand_mm` spacing, every second row shifted) inside the polygon
+fn get_signatur_positionen(poly: &SvgPolygonInner, abstand_mm: f64) -> Vec<SvgPoint> {
+    if abstand_mm <= 0.0 || poly.is_empty() {
+        return Vec::new();
+    }
+    let rect = poly.get_rect();
+    let mut punkte = Vec::new();
+    let mut y = rect.min_y + abstand_mm / 2.0;
+    let mut zeile = 0;
+    while y < rect.max_y {
+        let mut x = rect.min_x + abstand_mm / if zeile % 2 == 0 { 2.0 } else { 1.0 };
+        while x < rect.max_x {
+            let p = SvgPoint { x, y };
+            if crate::nas::point_is_in_polygon(&p, poly) {
+                punkte.push(p);
+            }
+            x += abstand_mm;
+        }
+        y += abstand_mm;
+        zeile += 1;
+    }
+    punkte
+}
+
 fn write_splitflaechen_beschriftungen(
     layer: &mut PdfLayerReference,
     font: &IndirectFontRef,
     riss_extent: &RissExtentReprojected,
     riss: &RissConfig,
     beschriftungen: &[OptimizedTextPlacement],
+    schriftgroesse: f32,
     has_background: bool,
 ) -> Option<()> {
     let linien = beschriftungen
@@ -1785,39 +2107,57 @@ fn write_splitflaechen_beschriftungen(
     let texte_alt = beschriftungen
         .iter()
         .filter(|s| s.optimized.status == TextStatus::Old)
-        .map(|p| TextPlacement {
-            kuerzel: p.optimized.kuerzel.clone(),
-            status: p.optimized.status.clone(),
-            pos: point_into_pdf_space(&p.optimized.pos, riss_extent, riss),
-            ref_pos: point_into_pdf_space(&p.optimized.ref_pos, riss_extent, riss),
-            area: p.optimized.area,
-            poly: p.optimized.poly.clone(),
+        .map(|p| {
+            (
+                TextPlacement {
+                    kuerzel: p.optimized.kuerzel.clone(),
+                    status: p.optimized.status.clone(),
+                    pos: point_into_pdf_space(&p.optimized.pos, riss_extent, riss),
+                    ref_pos: point_into_pdf_space(&p.optimized.ref_pos, riss_extent, riss),
+                    area: p.optimized.area,
+                    poly: p.optimized.poly.clone(),
+                    splitflaeche: p.optimized.splitflaeche.clone(),
+                },
+                p.winkel,
+            )
         })
         .collect::<Vec<_>>();
 
     let texte_neu = beschriftungen
         .into_iter()
         .filter(|s| s.optimized.status == TextStatus::New)
-        .map(|p| TextPlacement {
-            kuerzel: p.optimized.kuerzel.clone(),
-            status: p.optimized.status.clone(),
-            pos: point_into_pdf_space(&p.optimized.pos, riss_extent, riss),
-            ref_pos: point_into_pdf_space(&p.optimized.ref_pos, riss_extent, riss),
-            area: p.optimized.area,
-            poly: p.optimized.poly.clone(),
+        .map(|p| {
+            (
+                TextPlacement {
+                    kuerzel: p.optimized.kuerzel.clone(),
+                    status: p.optimized.status.clone(),
+                    pos: point_into_pdf_space(&p.optimized.pos, riss_extent, riss),
+                    ref_pos: point_into_pdf_space(&p.optimized.ref_pos, riss_extent, riss),
+                    area: p.optimized.area,
+                    poly: p.optimized.poly.clone(),
+                    splitflaeche: p.optimized.splitflaeche.clone(),
+                },
+                p.winkel,
+            )
         })
         .collect::<Vec<_>>();
 
     let texte_bleibt = beschriftungen
         .into_iter()
         .filter(|s| s.optimized.status == TextStatus::StaysAsIs)
-        .map(|p| TextPlacement {
-            kuerzel: p.optimized.kuerzel.clone(),
-            status: p.optimized.status.clone(),
-            pos: point_into_pdf_space(&p.optimized.pos, riss_extent, riss),
-            ref_pos: point_into_pdf_space(&p.optimized.ref_pos, riss_extent, riss),
-            area: p.optimized.area,
-            poly: p.optimized.poly.clone(),
+        .map(|p| {
+            (
+                TextPlacement {
+                    kuerzel: p.optimized.kuerzel.clone(),
+                    status: p.optimized.status.clone(),
+                    pos: point_into_pdf_space(&p.optimized.pos, riss_extent, riss),
+                    ref_pos: point_into_pdf_space(&p.optimized.ref_pos, riss_extent, riss),
+                    area: p.optimized.area,
+                    poly: p.optimized.poly.clone(),
+                    splitflaeche: p.optimized.splitflaeche.clone(),
+                },
+                p.winkel,
+            )
         })
         .collect::<Vec<_>>();
 
@@ -1871,36 +2211,48 @@ fn write_splitflaechen_beschriftungen(
 
     layer.save_graphics_state();
 
-    let write_text = |t: &str, x: f64, y: f64, color: printpdf::Color| {
+    let set_cursor = |x: f64, y: f64, winkel: f64| {
+        if winkel == 0.0 {
+            layer.set_text_cursor(Mm(x as f32), Mm(y as f32));
+        } else {
+            layer.set_text_matrix(printpdf::TextMatrix::TranslateRotate(
+                Mm(x as f32).into_pt(),
+                Mm(y as f32).into_pt(),
+                winkel as f32,
+            ));
+        }
+    };
+
+    let write_text = |t: &str, x: f64, y: f64, winkel: f64, color: printpdf::Color| {
         layer.begin_text_section();
         layer.set_outline_color(white.clone());
-        layer.set_outline_thickness(1.2);
+        layer.set_outline_thickness(BESCHRIFTUNG_KONTUR_PT);
         layer.set_fill_color(color.clone());
-        layer.set_font(&font, 6.0);
+        layer.set_font(&font, schriftgroesse);
         layer.set_text_rendering_mode(TextRenderingMode::FillStroke);
-        layer.set_text_cursor(Mm(x as f32), Mm(y as f32));
+        set_cursor(x, y, winkel);
         layer.write_text(t, &font);
         layer.end_text_section();
 
         layer.begin_text_section();
         layer.set_fill_color(color.clone());
-        layer.set_font(&font, 6.0);
+        layer.set_font(&font, schriftgroesse);
         layer.set_text_rendering_mode(TextRenderingMode::Fill);
-        layer.set_text_cursor(Mm(x as f32), Mm(y as f32));
+        set_cursor(x, y, winkel);
         layer.write_text(t, &font);
         layer.end_text_section();
     };
 
-    for t in texte_bleibt {
-        write_text(&t.kuerzel, t.pos.x, t.pos.y, bleibt_color.clone());
+    for (t, winkel) in texte_bleibt {
+        write_text(&t.kuerzel, t.pos.x, t.pos.y, winkel, bleibt_color.clone());
     }
 
-    for t in texte_alt {
-        write_text(&t.kuerzel, t.pos.x, t.pos.y, alt_color.clone());
+    for (t, winkel) in texte_alt {
+        write_text(&t.kuerzel, t.pos.x, t.pos.y, winkel, alt_color.clone());
     }
 
-    for t in texte_neu {
-        write_text(&t.kuerzel, t.pos.x, t.pos.y, neu_color.clone());
+    for (t, winkel) in texte_neu {
+        write_text(&t.kuerzel, t.pos.x, t.pos.y, winkel, neu_color.clone());
     }
 
     layer.restore_graphics_state();
@@ -1927,6 +2279,70 @@ fn write_splitflaechen_beschriftungen(
     Some(())
 }
 
+fn write_anschlussrisse(
+    layer: &mut PdfLayerReference,
+    font: &IndirectFontRef,
+    riss_extent: &RissExtentReprojected,
+    riss: &RissConfig,
+    anschlussrisse: &[AnschlussrissMarker],
+) -> Option<()> {
+    if anschlussrisse.is_empty() {
+        return Some(());
+    }
+
+    let black = printpdf::Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None));
+    let white = printpdf::Color::Rgb(Rgb::new(1.0, 1.0, 1.0, None));
+
+    layer.save_graphics_state();
+    layer.set_outline_thickness(1.0);
+    layer.set_outline_color(black.clone());
+
+    for a in anschlussrisse {
+        let rahmen = line_into_pdf_space(&a.get_box(), riss_extent, riss);
+        let points = rahmen
+            .points
+            .iter()
+            .take(4)
+            .map(|p| {
+                (
+                    printpdf::Point {
+                        x: Mm(p.x as f32).into_pt(),
+                        y: Mm(p.y as f32).into_pt(),
+                    },
+                    false,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        layer.set_fill_color(white.clone());
+        layer.add_polygon(printpdf::Polygon {
+            rings: vec![points],
+            mode: PaintMode::FillStroke,
+            winding_order: printpdf::path::WindingOrder::NonZero,
+        });
+
+        let pos = point_into_pdf_space(&a.get_text_pos(), riss_extent, riss);
+        layer.begin_text_section();
+        layer.set_fill_color(black.clone());
+        layer.set_font(font, Mm(ANSCHLUSSRISS_TEXT_HOEHE_MM).into_pt().0);
+        if a.vert {
+            layer.set_text_matrix(printpdf::TextMatrix::TranslateRotate(
+                Mm(pos.x as f32).into_pt(),
+                Mm(pos.y as f32).into_pt(),
+                90.0,
+            ));
+        } else {
+            layer.set_text_cursor(Mm(pos.x as f32), Mm(pos.y as f32));
+        }
+        layer.write_text(a.get_text(), font);
+        layer.end_text_section();
+    }
+
+    layer.restore_graphics_state();
+
+    Some(())
+}
+
 fn write_border(
     layer: &mut PdfLayerReference,
     riss: &RissConfig,
@@ -2024,11 +2440,12 @@ fn write_border(
     );
 
     if let Some((num_riss, total_riss)) = num_riss_total_riss {
+        let blattkopf_hoehe_mm = BLATTKOPF_HOEHE_MM as f32;
         add_rect(
             border_width_mm,
-            riss.height_mm - border_width_mm - 35.0,
-            175.0,
-            35.0,
+            riss.height_mm - border_width_mm - blattkopf_hoehe_mm,
+            BLATTKOPF_BREITE_MM as f32,
+            blattkopf_hoehe_mm,
             PaintMode::Fill,
         );
 
@@ -2040,7 +2457,7 @@ fn write_border(
             times_roman_bold,
             num_riss,
             total_riss,
-            riss.height_mm - border_width_mm - 35.0,
+            riss.height_mm - border_width_mm - blattkopf_hoehe_mm,
             border_width_mm,
         );
     }
@@ -2871,3 +3288,11 @@ fn translate_poly(svg: &SvgPolygonInner, paintmode: PaintMode) -> printpdf::Poly
         winding_order: printpdf::path::WindingOrder::NonZero,
     }
 }
+
+#[test]
+fn test_signaturen_default() {
+    let k = SignaturKatalog::default();
+    assert_eq!(k.get("WALD").map(|s| s.typ), Some(SignaturTyp::Flaeche));
+    assert_eq!(k.get("SUM").map(|s| s.signaturnummer.as_str()), Some("3478"));
+    assert!(k.0.values().all(|s| !s.pdf_linien.is_empty()));
+}
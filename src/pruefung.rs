@@ -0,0 +1,467 @@
+//! Plausibility checks (Prüfprotokoll) that run before the GEOgraf / DAVID export,
+//! so that inconsistencies between Änderungen, CSV and NAS are found before the
+//! Riss is drawn and not only after the DHK update.
+
+use crate::{
+    csv::{
+        CsvDataType,
+        Status,
+    },
+    nas::{
+        NasXMLFile,
+        SplitNasXml,
+        SvgPoint,
+        SvgPolygonInner,
+        TaggedPolygon,
+    },
+    pdf::{
+        PruefungConfig,
+        Risse,
+    },
+    ui::{
+        Aenderungen,
+        AenderungenIntersection,
+        AenderungenIntersections,
+    },
+    xlsx::{
+        FlstIdParsed,
+        FlstIdParsedNumber,
+    },
+};
+use serde_derive::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Schweregrad {
+    Fehler,
+    Warnung,
+    Hinweis,
+}
+
+impl Schweregrad {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Schweregrad::Fehler => "FEHLER",
+            Schweregrad::Warnung => "WARNUNG",
+            Schweregrad::Hinweis => "HINWEIS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Befund {
+    pub schweregrad: Schweregrad,
+    /// Kennung der Prüfung, z.B. "polyneu_ohne_nutzung"
+    pub pruefung: String,
+    pub meldung: String,
+    /// Position in the CRS of the NAS file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ort: Option<SvgPoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poly_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flst_id: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pruefprotokoll {
+    pub befunde: Vec<Befund>,
+}
+
+impl Pruefprotokoll {
+    pub fn anzahl(&self, schweregrad: Schweregrad) -> usize {
+        self.befunde
+            .iter()
+            .filter(|b| b.schweregrad == schweregrad)
+            .count()
+    }
+
+    pub fn hat_fehler(&self) -> bool {
+        self.anzahl(Schweregrad::Fehler) > 0
+    }
+
+    pub fn get_zusammenfassung(&self) -> String {
+        format!(
+            "Prüfprotokoll: {} Fehler, {} Warnungen, {} Hinweise",
+            self.anzahl(Schweregrad::Fehler),
+            self.anzahl(Schweregrad::Warnung),
+            self.anzahl(Schweregrad::Hinweis),
+        )
+    }
+
+    /// Plain text version for the export ZIP
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![self.get_zusammenfassung(), String::new()];
+        for b in self.befunde.iter() {
+            let mut ort = Vec::new();
+            if let Some(f) = b.flst_id.as_deref() {
+                ort.push(format!("Flst. {}", AenderungenIntersection::format_flst_id_func(f)));
+            }
+            if let Some(p) = b.poly_id.as_deref() {
+                ort.push(format!("Polygon {p}"));
+            }
+            if let Some(p) = b.ort.as_ref() {
+                ort.push(format!("{:.2} / {:.2}", p.x, p.y));
+            }
+            lines.push(format!(
+                "[{}] {}: {} ({})",
+                b.schweregrad.get_name(),
+                b.pruefung,
+                b.meldung,
+                ort.join(", ")
+            ));
+        }
+        lines.join("\r\n")
+    }
+
+    fn push(
+        &mut self,
+        schweregrad: Schweregrad,
+        pruefung: &str,
+        meldung: String,
+        ort: Option<SvgPoint>,
+        poly_id: Option<&str>,
+        flst_id: Option<&str>,
+    ) {
+        self.befunde.push(Befund {
+            schweregrad,
+            pruefung: pruefung.to_string(),
+            meldung,
+            ort,
+            poly_id: poly_id.map(|s| s.to_string()),
+            flst_id: flst_id.map(|s| s.to_string()),
+        });
+    }
+}
+
+/// `aenderungen` and `splitflaechen` have to be in the CRS of `nas_xml`
+pub fn pruefe_aenderungen(
+    aenderungen: &Aenderungen,
+    nas_xml: &NasXMLFile,
+    splitflaechen: &AenderungenIntersections,
+    csv: &CsvDataType,
+    config: &PruefungConfig,
+) -> Pruefprotokoll {
+    let mut protokoll = Pruefprotokoll::default();
+    pruefe_polyneu_ohne_nutzung(aenderungen, &mut protokoll);
+    pruefe_ueberlappungen(aenderungen, config, &mut protokoll);
+    pruefe_ausserhalb_csv(aenderungen, nas_xml, csv, &mut protokoll);
+    pruefe_kleine_splitflaechen(splitflaechen, config, &mut protokoll);
+    pruefe_csv_nutzung(splitflaechen, csv, &mut protokoll);
+    pruefe_wirtschaftsart_status(aenderungen, splitflaechen, csv, &mut protokoll);
+    protokoll
+        .befunde
+        .sort_by(|a, b| a.schweregrad.cmp(&b.schweregrad));
+    protokoll
+}
+
+/// Same as [pruefe_aenderungen], calculates the Splitflächen first
+pub fn pruefe_vor_export(
+    aenderungen: &Aenderungen,
+    split_nas: &SplitNasXml,
+    nas_xml: &NasXMLFile,
+    csv: &CsvDataType,
+    config: &PruefungConfig,
+) -> Pruefprotokoll {
+    let splitflaechen = crate::geograf::calc_splitflaechen(aenderungen, split_nas, nas_xml, csv);
+    pruefe_aenderungen(aenderungen, nas_xml, &splitflaechen, csv, config)
+}
+
+fn pruefe_polyneu_ohne_nutzung(aenderungen: &Aenderungen, protokoll: &mut Pruefprotokoll) {
+    for (id, p) in aenderungen.na_polygone_neu.iter() {
+        if p.nutzung.as_deref().map(|s| s.trim().is_empty()).unwrap_or(true) {
+            protokoll.push(
+                Schweregrad::Fehler,
+                "polyneu_ohne_nutzung",
+                "Neues Polygon hat keine Nutzung".to_string(),
+                p.poly.get_inner().get_label_pos(),
+                Some(id),
+                None,
+            );
+        }
+    }
+}
+
+fn pruefe_ueberlappungen(
+    aenderungen: &Aenderungen,
+    config: &PruefungConfig,
+    protokoll: &mut Pruefprotokoll,
+) {
+    let polys = aenderungen
+        .na_polygone_neu
+        .iter()
+        .filter_map(|(id, p)| {
+            let poly = p.poly.get_inner();
+            let rect = poly.get_rect();
+            Some((id, p.nutzung.clone()?, poly, rect))
+        })
+        .collect::<Vec<_>>();
+
+    for (i, (id_a, kuerzel_a, poly_a, rect_a)) in polys.iter().enumerate() {
+        for (id_b, kuerzel_b, poly_b, rect_b) in polys.iter().skip(i + 1) {
+            if kuerzel_a == kuerzel_b || !rect_a.overlaps_rect(rect_b) {
+                continue;
+            }
+            let schnitt = crate::ops::intersect_polys(poly_a, poly_b, false);
+            let flaeche = schnitt.iter().map(|s| s.area_m2().abs()).sum::<f64>();
+            if flaeche <= config.max_ueberlappung_m2 {
+                continue;
+            }
+            protokoll.push(
+                Schweregrad::Warnung,
+                "polyneu_ueberlappung",
+                format!(
+                    "Neue Polygone mit {kuerzel_a} und {kuerzel_b} überlappen sich ({flaeche:.2} m², Polygon {id_b})"
+                ),
+                schnitt.iter().find_map(|s| s.get_label_pos()),
+                Some(id_a),
+                None,
+            );
+        }
+    }
+}
+
+fn get_csv_flst(csv: &CsvDataType) -> BTreeMap<FlstIdParsedNumber, String> {
+    csv.keys()
+        .into_iter()
+        .filter_map(|k| Some((FlstIdParsed::from_str(k).parse_num()?, k.clone())))
+        .collect()
+}
+
+fn pruefe_ausserhalb_csv(
+    aenderungen: &Aenderungen,
+    nas_xml: &NasXMLFile,
+    csv: &CsvDataType,
+    protokoll: &mut Pruefprotokoll,
+) {
+    let csv_flst = get_csv_flst(csv);
+    if csv_flst.is_empty() {
+        return;
+    }
+    let flurstuecke = nas_xml
+        .ebenen
+        .get("AX_Flurstueck")
+        .map(|v| v.as_slice())
+        .unwrap_or(&[])
+        .iter()
+        .filter(|tp| {
+            tp.attributes
+                .get("flurstueckskennzeichen")
+                .and_then(|f| FlstIdParsed::from_str(f).parse_num())
+                .map(|f| csv_flst.contains_key(&f))
+                .unwrap_or(false)
+        })
+        .map(|tp| (tp.poly.get_rect(), &tp.poly))
+        .collect::<Vec<_>>();
+
+    for (id, p) in aenderungen.na_polygone_neu.iter() {
+        let poly = p.poly.get_inner();
+        let rect = poly.get_rect();
+        let in_csv_flst = flurstuecke
+            .iter()
+            .any(|(r, f)| r.overlaps_rect(&rect) && poly.overlaps(f));
+        if !in_csv_flst {
+            protokoll.push(
+                Schweregrad::Fehler,
+                "polyneu_ausserhalb_csv",
+                "Neues Polygon liegt außerhalb der Flurstücke aus der CSV".to_string(),
+                poly.get_label_pos(),
+                Some(id),
+                None,
+            );
+        }
+    }
+}
+
+fn pruefe_kleine_splitflaechen(
+    splitflaechen: &AenderungenIntersections,
+    config: &PruefungConfig,
+    protokoll: &mut Pruefprotokoll,
+) {
+    for s in splitflaechen.0.iter() {
+        if s.alt == s.neu {
+            continue;
+        }
+        let flaeche = s.poly_cut.area_m2().abs();
+        if flaeche < config.min_splitflaeche_m2 {
+            protokoll.push(
+                Schweregrad::Warnung,
+                "splitflaeche_zu_klein",
+                format!(
+                    "Splitfläche {} -> {} hat nur {flaeche:.2} m² (min. {} m²)",
+                    s.alt, s.neu, config.min_splitflaeche_m2
+                ),
+                s.poly_cut.get_label_pos(),
+                None,
+                Some(&s.flst_id),
+            );
+        }
+    }
+}
+
+/// Kürzel mentioned in the CSV "Nutzung" column, either as Kürzel ("GR")
+/// or as Bezeichnung from the Nutzungsartenkatalog ("Grünland")
+fn get_csv_kuerzel(nutzung: &str) -> BTreeSet<String> {
+    let katalog = crate::get_nutzungsartenkatalog();
+    let woerter = nutzung
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<BTreeSet<_>>();
+    let nutzung_lower = nutzung.to_lowercase();
+    katalog
+        .iter()
+        .filter(|(k, na)| {
+            woerter.contains(k.as_str())
+                || (!na.bez.trim().is_empty() && nutzung_lower.contains(&na.bez.trim().to_lowercase()))
+        })
+        .map(|(k, _)| k.clone())
+        .collect()
+}
+
+fn pruefe_csv_nutzung(
+    splitflaechen: &AenderungenIntersections,
+    csv: &CsvDataType,
+    protokoll: &mut Pruefprotokoll,
+) {
+    for (flst_id, ds) in csv.get_old_fallback().iter() {
+        let nutzung = match ds.iter().find(|d| !d.nutzung.trim().is_empty()) {
+            Some(d) => &d.nutzung,
+            None => continue,
+        };
+        let csv_kuerzel = get_csv_kuerzel(nutzung);
+        if csv_kuerzel.is_empty() {
+            continue;
+        }
+        let sf = AenderungenIntersection::get_splitflaechen_fuer_flst(&splitflaechen.0, flst_id);
+        if sf.is_empty() {
+            continue;
+        }
+        let alt = sf.iter().map(|s| s.alt.clone()).collect::<BTreeSet<_>>();
+        let neu = sf.iter().map(|s| s.neu.clone()).collect::<BTreeSet<_>>();
+        if csv_kuerzel == alt || csv_kuerzel == neu {
+            continue;
+        }
+        let join = |s: &BTreeSet<String>| s.iter().cloned().collect::<Vec<_>>().join(", ");
+        protokoll.push(
+            Schweregrad::Warnung,
+            "csv_nutzung_abweichend",
+            format!(
+                "Nutzung laut CSV ({}) passt nicht zu den Splitflächen (alt: {}, neu: {})",
+                join(&csv_kuerzel),
+                join(&alt),
+                join(&neu)
+            ),
+            None,
+            None,
+            Some(flst_id),
+        );
+    }
+}
+
+/// A single Splitfläche can change its Wirtschaftsart while the set of
+/// Wirtschaftsarten of the Flurstück stays the same: the automatic status then
+/// says "no notification", which has to be checked by hand
+fn pruefe_wirtschaftsart_status(
+    aenderungen: &Aenderungen,
+    splitflaechen: &AenderungenIntersections,
+    csv: &CsvDataType,
+    protokoll: &mut Pruefprotokoll,
+) {
+    let gebaeude_flst = aenderungen.get_gebaeude_modified_flst();
+    for flst_id in csv.keys() {
+        let sf = AenderungenIntersection::get_splitflaechen_fuer_flst_veraendert(
+            &splitflaechen.0,
+            flst_id,
+        );
+        let wia_geaendert = sf.iter().find(|s| {
+            TaggedPolygon::get_wirtschaftsart(&s.alt) != TaggedPolygon::get_wirtschaftsart(&s.neu)
+        });
+        let s = match wia_geaendert {
+            Some(s) => s,
+            None => continue,
+        };
+        let status =
+            AenderungenIntersection::get_auto_status(&splitflaechen.0, &gebaeude_flst, flst_id);
+        if matches!(status, Status::AenderungMitBenachrichtigung(_)) {
+            continue;
+        }
+        protokoll.push(
+            Schweregrad::Warnung,
+            "wirtschaftsart_ohne_benachrichtigung",
+            format!(
+                "Wirtschaftsart ändert sich ({} -> {}), Status ist aber \"{}\"",
+                s.alt,
+                s.neu,
+                status.get_notiz("Änderung ohne Benachrichtigung")
+            ),
+            s.poly_cut.get_label_pos(),
+            None,
+            Some(flst_id),
+        );
+    }
+}
+
+/// Manual label positions of the Risse whose Splitfläche no longer exists,
+/// `splitflaechen` in the CRS of the NAS file
+pub fn pruefe_beschriftungen(
+    risse: &Risse,
+    splitflaechen: &AenderungenIntersections,
+    protokoll: &mut Pruefprotokoll,
+) {
+    for (i, riss) in risse.values().enumerate() {
+        for o in crate::optimize::get_verwaiste_overrides(&riss.beschriftungen, &splitflaechen.0) {
+            protokoll.push(
+                Schweregrad::Warnung,
+                "beschriftung_ohne_splitflaeche",
+                format!(
+                    "Riss {}: {} wird ignoriert, die Splitfläche existiert nicht mehr",
+                    i + 1,
+                    o.get_beschreibung()
+                ),
+                Some(o.pos),
+                None,
+                None,
+            );
+        }
+    }
+    protokoll
+        .befunde
+        .sort_by(|a, b| a.schweregrad.cmp(&b.schweregrad));
+}
+
+#[test]
+fn test_pruefe_polyneu() {
+    use crate::test_utils::test_poly_neu;
+
+    let mut aenderungen = Aenderungen::default();
+    aenderungen.na_polygone_neu.insert("a".to_string(), test_poly_neu(0.0, 10.0, Some("WALD")));
+    aenderungen.na_polygone_neu.insert("b".to_string(), test_poly_neu(5.0, 10.0, Some("GR")));
+    aenderungen.na_polygone_neu.insert("c".to_string(), test_poly_neu(100.0, 10.0, None));
+
+    let protokoll = pruefe_aenderungen(
+        &aenderungen,
+        &NasXMLFile::default(),
+        &AenderungenIntersections::default(),
+        &CsvDataType::default(),
+        &PruefungConfig::default(),
+    );
+    let pruefungen = protokoll
+        .befunde
+        .iter()
+        .map(|b| (b.pruefung.as_str(), b.poly_id.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pruefungen,
+        vec![
+            ("polyneu_ohne_nutzung", Some("c")),
+            ("polyneu_ueberlappung", Some("a")),
+        ]
+    );
+    assert!(protokoll.hat_fehler());
+}
@@ -0,0 +1,380 @@
+//! Automatic layout of the Risse for the Arbeitsmappe
+//!
+//! The extent of all changes is split recursively along its longer side
+//! ("guillotine" cuts) until every part fits on one allowed sheet, leaving
+//! room for the border ([PADDING]) and the Blattkopf in the upper left
+//! corner (see [crate::pdf::RissExtentReprojected::get_poly]). Cuts that do
+//! not cross a change are preferred, among those the one that puts the most
+//! changes into a part that already fits. Each part gets the rectangle of
+//! its changes as `rissgebiet`, minus the rectangles of the parts before it,
+//! so that the rissgebiete don't overlap when no clean cut was possible.
+//! If that difference falls apart, every piece gets its own Riss.
+
+use crate::{
+    geograf::PADDING,
+    ops::subtract_from_poly,
+    nas::{
+        SvgLine,
+        SvgPoint,
+        SvgPolygon,
+        SvgPolygonInner,
+        TaggedPolygon,
+    },
+    pdf::{
+        reproject_point_back_into_latlon,
+        reproject_poly_back_into_latlon,
+        RissConfig,
+        RissPlanungConfig,
+    },
+    ui::AenderungenIntersection,
+};
+use quadtree_f32::Rect;
+
+pub const BLATTKOPF_BREITE_MM: f64 = 175.0;
+pub const BLATTKOPF_HOEHE_MM: f64 = 35.0;
+
+/// Width and height in mm (Hochformat)
+pub fn get_papierformat(name: &str) -> Option<(f32, f32)> {
+    match name.trim().to_uppercase().as_str() {
+        "A4" => Some((210.0, 297.0)),
+        "A3" => Some((297.0, 420.0)),
+        "A2" => Some((420.0, 594.0)),
+        "A1" => Some((594.0, 841.0)),
+        "A0" => Some((841.0, 1189.0)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RissFormat {
+    pub width_mm: f32,
+    pub height_mm: f32,
+    pub scale: f32,
+}
+
+impl RissFormat {
+    /// Map area inside the border in meters
+    fn get_karte_m(&self) -> (f64, f64) {
+        let f = self.scale as f64 / 1000.0;
+        (
+            (self.width_mm - PADDING) as f64 * f,
+            (self.height_mm - PADDING) as f64 * f,
+        )
+    }
+
+    fn get_blattkopf_m(&self) -> (f64, f64) {
+        let f = self.scale as f64 / 1000.0;
+        (BLATTKOPF_BREITE_MM * f, BLATTKOPF_HOEHE_MM * f)
+    }
+
+    /// Whether `rect` fits on the sheet either below or right of the Blattkopf
+    pub fn fits(&self, rect: &Rect) -> bool {
+        let (w, h) = self.get_karte_m();
+        let (kw, kh) = self.get_blattkopf_m();
+        let (rw, rh) = (rect.get_width(), rect.get_height());
+        rw <= w && rh <= h && (rw <= w - kw || rh <= h - kh)
+    }
+
+    /// Center of the sheet, `rect` is centered below or right of the Blattkopf
+    pub fn get_center(&self, rect: &Rect) -> SvgPoint {
+        let (w, h) = self.get_karte_m();
+        let (kw, kh) = self.get_blattkopf_m();
+        let (rw, rh) = (rect.get_width(), rect.get_height());
+        let (min_x, max_y) = if rh <= h - kh {
+            (
+                rect.min_x - (w - rw) / 2.0,
+                rect.max_y + kh + (h - kh - rh) / 2.0,
+            )
+        } else {
+            (
+                rect.min_x - kw - (w - kw - rw) / 2.0,
+                rect.max_y + (h - rh) / 2.0,
+            )
+        };
+        SvgPoint {
+            x: min_x + w / 2.0,
+            y: max_y - h / 2.0,
+        }
+    }
+}
+
+/// Allowed sheets, ordered by preference: more detailed scale first,
+/// then the smaller paper format
+pub fn get_formate(config: &RissPlanungConfig) -> Vec<RissFormat> {
+    let papier = config
+        .formate
+        .iter()
+        .filter_map(|f| get_papierformat(f))
+        .collect::<Vec<_>>();
+
+    let mut formate = Vec::new();
+    for scale in config.massstaebe.iter().filter(|s| **s > 0.0) {
+        for (w, h) in papier.iter() {
+            if config.hochformat {
+                formate.push(RissFormat {
+                    width_mm: *w,
+                    height_mm: *h,
+                    scale: *scale,
+                });
+            }
+            if config.querformat {
+                formate.push(RissFormat {
+                    width_mm: *h,
+                    height_mm: *w,
+                    scale: *scale,
+                });
+            }
+        }
+    }
+
+    formate.sort_by(|a, b| {
+        a.scale
+            .total_cmp(&b.scale)
+            .then((a.width_mm * a.height_mm).total_cmp(&(b.width_mm * b.height_mm)))
+    });
+    formate.dedup();
+    formate
+}
+
+/// Plans the Risse for the changed Splitflächen and the deleted buildings
+/// (both in `crs`), lat / lon and rissgebiet of the result are in latlon
+pub fn plane_risse(
+    splitflaechen: &[AenderungenIntersection],
+    gebaeude: &[TaggedPolygon],
+    crs: &str,
+    config: &RissPlanungConfig,
+) -> Result<Vec<RissConfig>, String> {
+    let formate = get_formate(config);
+    if formate.is_empty() {
+        return Err("Rissplanung: keine gültigen Papierformate / Maßstäbe konfiguriert".to_string());
+    }
+
+    let objekte = get_objekte(splitflaechen, gebaeude);
+    let mut teile = Vec::new();
+    if !objekte.is_empty() {
+        teile_auf(objekte, &formate, &mut teile)?;
+    }
+
+    get_rissgebiete(&teile)
+        .into_iter()
+        .map(|(rect, format, rissgebiet)| {
+            let center = reproject_point_back_into_latlon(&format.get_center(&rect), crs)?;
+            let rissgebiet = reproject_poly_back_into_latlon(&rissgebiet, crs)?;
+            Ok(RissConfig {
+                lat: center.y,
+                lon: center.x,
+                crs: "latlon".to_string(),
+                width_mm: format.width_mm,
+                height_mm: format.height_mm,
+                scale: format.scale,
+                rissgebiet: Some(SvgPolygon::Old(rissgebiet)),
+                seed: 0,
+                beschriftungen: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn get_objekte(splitflaechen: &[AenderungenIntersection], gebaeude: &[TaggedPolygon]) -> Vec<(String, Rect)> {
+    splitflaechen
+        .iter()
+        .map(|s| {
+            (
+                format!("Splitfläche {} ({} -> {})", s.format_flst_id(), s.alt, s.neu),
+                s.poly_cut.get_rect(),
+            )
+        })
+        .chain(gebaeude.iter().map(|g| {
+            (
+                format!(
+                    "Gebäude {}",
+                    g.attributes.get("id").cloned().unwrap_or_default()
+                ),
+                g.get_rect(),
+            )
+        }))
+        .collect()
+}
+
+fn union_rect(objekte: &[(String, Rect)]) -> Rect {
+    let mut rect = objekte[0].1;
+    for (_, r) in objekte.iter().skip(1) {
+        rect = rect.union(r);
+    }
+    rect
+}
+
+fn teile_auf(
+    mut objekte: Vec<(String, Rect)>,
+    formate: &[RissFormat],
+    target: &mut Vec<(Rect, RissFormat)>,
+) -> Result<(), String> {
+    let rect = union_rect(&objekte);
+    if let Some(f) = formate.iter().find(|f| f.fits(&rect)) {
+        target.push((rect, *f));
+        return Ok(());
+    }
+
+    if objekte.len() == 1 {
+        return Err(format!(
+            "Rissplanung: {} ist zu groß für alle erlaubten Formate und Maßstäbe",
+            objekte[0].0
+        ));
+    }
+
+    // cut perpendicular to the longer side
+    let entlang_x = rect.get_width() >= rect.get_height();
+    let von = |r: &Rect| if entlang_x { r.min_x } else { r.min_y };
+    let bis = |r: &Rect| if entlang_x { r.max_x } else { r.max_y };
+    objekte.sort_by(|a, b| von(&a.1).total_cmp(&von(&b.1)));
+
+    let n = objekte.len();
+    let mut vorne = objekte[0].1;
+    let mut vorne_bis = bis(&objekte[0].1);
+    let mut bester_schnitt = (false, false, i64::MIN, 1);
+    for k in 1..n {
+        let sauber = vorne_bis <= von(&objekte[k].1);
+        let passt = formate.iter().any(|f| f.fits(&vorne));
+        let rang = if passt {
+            k as i64
+        } else {
+            -((2 * k) as i64 - n as i64).abs()
+        };
+        if (sauber, passt, rang) > (bester_schnitt.0, bester_schnitt.1, bester_schnitt.2) {
+            bester_schnitt = (sauber, passt, rang, k);
+        }
+        vorne = vorne.union(&objekte[k].1);
+        vorne_bis = vorne_bis.max(bis(&objekte[k].1));
+    }
+
+    let hinten = objekte.split_off(bester_schnitt.3);
+    teile_auf(objekte, formate, target)?;
+    teile_auf(hinten, formate, target)
+}
+
+/// Clips the rectangle of every part against the parts before it. Parts
+/// that are completely covered by their predecessors are dropped, if the
+/// difference falls apart, every piece is returned separately.
+fn get_rissgebiete(teile: &[(Rect, RissFormat)]) -> Vec<(Rect, RissFormat, SvgPolygonInner)> {
+    let mut rissgebiete = Vec::new();
+    for (i, (rect, format)) in teile.iter().enumerate() {
+        let gebiet = get_rect_poly(rect);
+        let vorher = teile[..i]
+            .iter()
+            .filter(|(r, _)| r.overlaps_rect(rect))
+            .map(|(r, _)| get_rect_poly(r))
+            .collect::<Vec<_>>();
+        rissgebiete.extend(
+            subtract_from_poly(&gebiet, &vorher.iter().collect::<Vec<_>>(), false)
+                .into_iter()
+                .filter(|p| !p.is_zero_area())
+                .map(|p| (*rect, *format, p)),
+        );
+    }
+    rissgebiete
+}
+
+fn get_rect_poly(rect: &Rect) -> SvgPolygonInner {
+    let (min_x, min_y, max_x, max_y) = (rect.min_x, rect.min_y, rect.max_x, rect.max_y);
+    SvgPolygonInner {
+        outer_ring: SvgLine {
+            points: vec![
+                SvgPoint { x: min_x, y: min_y },
+                SvgPoint { x: min_x, y: max_y },
+                SvgPoint { x: max_x, y: max_y },
+                SvgPoint { x: max_x, y: min_y },
+                SvgPoint { x: min_x, y: min_y },
+            ],
+        },
+        inner_rings: Vec::new(),
+    }
+}
+
+#[test]
+fn test_rissplanung_teile_auf() {
+    use crate::test_utils::test_rect as rect;
+
+    let formate = get_formate(&RissPlanungConfig {
+        formate: vec!["A3".to_string()],
+        hochformat: true,
+        querformat: false,
+        massstaebe: vec![1000.0],
+    });
+    // A3 hoch 1:1000: 264 x 387 m map area, Blattkopf 175 x 35 m
+    assert!(formate[0].fits(&rect(0.0, 0.0, 264.0, 350.0)));
+    assert!(!formate[0].fits(&rect(0.0, 0.0, 264.0, 380.0)));
+
+    let objekte = vec![
+        ("a".to_string(), rect(0.0, 0.0, 100.0, 100.0)),
+        ("b".to_string(), rect(100.0, 0.0, 200.0, 100.0)),
+        ("c".to_string(), rect(500.0, 0.0, 600.0, 100.0)),
+    ];
+    let mut teile = Vec::new();
+    teile_auf(objekte, &formate, &mut teile).unwrap();
+    assert_eq!(teile.len(), 2);
+    assert_eq!((teile[0].0.min_x, teile[0].0.max_x), (0.0, 200.0));
+
+    let mut teile = Vec::new();
+    let zu_gross = vec![("d".to_string(), rect(0.0, 0.0, 1000.0, 100.0))];
+    assert!(teile_auf(zu_gross, &formate, &mut teile).is_err());
+}
+
+#[test]
+fn test_rissplanung_verschachtelt() {
+    use crate::{
+        ops::intersect_polys,
+        test_utils::test_rect as rect,
+    };
+
+    let formate = get_formate(&RissPlanungConfig {
+        formate: vec!["A3".to_string()],
+        hochformat: true,
+        querformat: false,
+        massstaebe: vec![1000.0],
+    });
+
+    // long changes that reach into each other, no cut is clean
+    let splitflaeche = |r: Rect| AenderungenIntersection {
+        poly_cut: get_rect_poly(&r),
+        ..Default::default()
+    };
+    let splitflaechen = vec![
+        splitflaeche(rect(0.0, 0.0, 250.0, 40.0)),
+        splitflaeche(rect(200.0, 20.0, 450.0, 60.0)),
+        splitflaeche(rect(400.0, 40.0, 650.0, 80.0)),
+    ];
+    let gebaeude = vec![TaggedPolygon {
+        poly: get_rect_poly(&rect(240.0, 30.0, 260.0, 50.0)),
+        attributes: Default::default(),
+    }];
+    let mut teile = Vec::new();
+    teile_auf(get_objekte(&splitflaechen, &gebaeude), &formate, &mut teile).unwrap();
+    assert_eq!(teile.len(), 3);
+    let rissgebiete = get_rissgebiete(&teile);
+
+    // every change is covered by the rissgebiete exactly once
+    let flaeche = |polys: &[SvgPolygonInner]| polys.iter().map(|p| p.area_m2().abs()).sum::<f64>();
+    let objekte = splitflaechen.iter().map(|s| &s.poly_cut).chain(gebaeude.iter().map(|g| &g.poly));
+    for o in objekte {
+        let abgedeckt = rissgebiete
+            .iter()
+            .map(|(_, _, r)| flaeche(&intersect_polys(o, r, false)))
+            .sum::<f64>();
+        assert!((abgedeckt - flaeche(&[o.clone()])).abs() < 0.01);
+    }
+    for (i, (_, _, a)) in rissgebiete.iter().enumerate() {
+        for (_, _, b) in rissgebiete.iter().skip(i + 1) {
+            assert!(flaeche(&intersect_polys(a, b, false)) < 0.001);
+        }
+    }
+
+    // a Riss cutting through a later one splits it into two Risse
+    let teile = vec![
+        (rect(100.0, 0.0, 150.0, 100.0), formate[0]),
+        (rect(0.0, 20.0, 300.0, 60.0), formate[0]),
+    ];
+    let rissgebiete = get_rissgebiete(&teile);
+    assert_eq!(rissgebiete.len(), 3);
+    let flaechen = rissgebiete.iter().map(|(_, _, r)| r.area_m2().abs()).collect::<Vec<_>>();
+    assert!((flaechen.iter().sum::<f64>() - (50.0 * 100.0 + 250.0 * 40.0)).abs() < 0.01);
+}
@@ -0,0 +1,133 @@
+//! Environment the core logic runs in: randomness, status logging, the
+//! Nutzungsartenkatalog and the NAK ranking. In the browser these come from
+//! JS globals, natively from a seeded RNG, stderr and the data embedded at build time.
+
+use crate::search::{
+    get_nutzungsartenkatalog_embedded,
+    NutzungsArtMap,
+};
+use rand::{
+    Rng,
+    SeedableRng,
+};
+use rand_xorshift::XorShiftRng;
+use std::cell::RefCell;
+
+pub trait Runtime {
+    /// Uniform random number in `0.0..=1.0`
+    fn random(&mut self) -> f64;
+    fn log_status(&mut self, s: &str);
+    fn log_status_clear(&mut self);
+    fn get_nutzungsartenkatalog(&mut self) -> NutzungsArtMap;
+    /// Kürzel ordered from "top" to "bottom" (WAS > ... > A)
+    fn get_nak_ranking(&mut self) -> Vec<String>;
+}
+
+thread_local! {
+    static RUNTIME: RefCell<Box<dyn Runtime>> = RefCell::new(default_runtime());
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_runtime() -> Box<dyn Runtime> {
+    Box::new(WasmRuntime)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_runtime() -> Box<dyn Runtime> {
+    Box::new(NativeRuntime::default())
+}
+
+/// Replaces the runtime for the current thread (e.g. to use a different seed in tests)
+pub fn set_runtime(r: Box<dyn Runtime>) {
+    RUNTIME.with(|rt| *rt.borrow_mut() = r);
+}
+
+pub fn with_runtime<T>(f: impl FnOnce(&mut dyn Runtime) -> T) -> T {
+    RUNTIME.with(|rt| f(rt.borrow_mut().as_mut()))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct WasmRuntime;
+
+#[cfg(target_arch = "wasm32")]
+impl Runtime for WasmRuntime {
+    fn random(&mut self) -> f64 {
+        crate::uuid_wasm::js::random().max(0.0).min(1.0)
+    }
+    fn log_status(&mut self, s: &str) {
+        web_sys::console::log_1(&s.into());
+        crate::uuid_wasm::js::update_export_status(s.trim().to_string())
+    }
+    fn log_status_clear(&mut self) {
+        crate::uuid_wasm::js::export_status_clear();
+    }
+    fn get_nutzungsartenkatalog(&mut self) -> NutzungsArtMap {
+        serde_json::from_str::<NutzungsArtMap>(&crate::uuid_wasm::js::get_nak())
+            .unwrap_or_else(|_| get_nutzungsartenkatalog_embedded())
+    }
+    fn get_nak_ranking(&mut self) -> Vec<String> {
+        serde_json::from_str::<Vec<String>>(&crate::uuid_wasm::js::get_nak_ranking())
+            .unwrap_or_default()
+    }
+}
+
+// also injected as window.nutzungsarten_ranking into skeleton.html by postbuild.py
+const NAK_RANKING_DEFAULT: &str = include_str!("../nutzungsarten_ranking.json");
+
+pub struct NativeRuntime {
+    rng: XorShiftRng,
+    nak: Option<NutzungsArtMap>,
+    nak_ranking: Vec<String>,
+}
+
+impl Default for NativeRuntime {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl NativeRuntime {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: XorShiftRng::seed_from_u64(seed),
+            nak: None,
+            nak_ranking: serde_json::from_str(NAK_RANKING_DEFAULT).unwrap_or_default(),
+        }
+    }
+
+    pub fn with_nak_ranking(self, nak_ranking: Vec<String>) -> Self {
+        Self {
+            nak_ranking,
+            ..self
+        }
+    }
+}
+
+impl Runtime for NativeRuntime {
+    fn random(&mut self) -> f64 {
+        self.rng.gen_range(0.0..=1.0)
+    }
+    fn log_status(&mut self, s: &str) {
+        eprintln!("{s}");
+    }
+    fn log_status_clear(&mut self) {}
+    fn get_nutzungsartenkatalog(&mut self) -> NutzungsArtMap {
+        self.nak
+            .get_or_insert_with(get_nutzungsartenkatalog_embedded)
+            .clone()
+    }
+    fn get_nak_ranking(&mut self) -> Vec<String> {
+        self.nak_ranking.clone()
+    }
+}
+
+#[test]
+fn test_native_runtime_seeded() {
+    let mut a = NativeRuntime::new(5);
+    let mut b = NativeRuntime::new(5);
+    let ra = (0..10).map(|_| a.random()).collect::<Vec<_>>();
+    let rb = (0..10).map(|_| b.random()).collect::<Vec<_>>();
+    assert_eq!(ra, rb);
+    assert!(ra.iter().all(|r| (0.0..=1.0).contains(r)));
+    assert!(a.get_nak_ranking().contains(&"WALD".to_string()));
+}
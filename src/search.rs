@@ -20,12 +20,16 @@ pub struct NutzungsArt {
 pub type NutzungsArtMap = BTreeMap<String, NutzungsArt>;
 
 pub fn get_nutzungsartenkatalog() -> NutzungsArtMap {
-    serde_json::from_str::<NutzungsArtMap>(&crate::uuid_wasm::get_js_nak())
-        .unwrap_or_else(|_| include!(concat!(env!("OUT_DIR"), "/nutzung.rs")))
+    crate::runtime::with_runtime(|r| r.get_nutzungsartenkatalog())
+}
+
+/// Catalog from nutzung.json, embedded by build.rs
+pub fn get_nutzungsartenkatalog_embedded() -> NutzungsArtMap {
+    include!(concat!(env!("OUT_DIR"), "/nutzung.rs"))
 }
 
 pub fn get_nak_ranking(kuerzel: &str) -> usize {
-    let mut ranking = serde_json::from_str::<Vec<String>>(&&crate::uuid_wasm::get_js_nak_ranking()).unwrap_or_default();
+    let mut ranking = crate::uuid_wasm::get_js_nak_ranking();
     ranking.reverse();
     let s =ranking.iter().enumerate().find_map(|s| if s.1 == kuerzel { Some(s.0 + 1) } else { None }).unwrap_or(0);
     crate::uuid_wasm::log_status(&format!("NAK ranking {kuerzel}: {s}"));
@@ -69,3 +73,10 @@ pub fn search_map(term: &str) -> Vec<(String, NutzungsArt)> {
     preferred.extend(target.iter().map(|(k, v)| (k.clone(), v.clone())));
     preferred
 }
+
+#[test]
+fn test_nak_ranking() {
+    assert_eq!(get_nak_ranking("A"), 1);
+    assert!(get_nak_ranking("WAS") > get_nak_ranking("WALD"));
+    assert_eq!(get_nak_ranking("XYZ"), 0);
+}
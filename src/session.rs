@@ -0,0 +1,147 @@
+//! Parsed NAS data kept in wasm memory between calls. After loading the NAS file,
+//! JS only holds a numeric handle instead of passing the whole `NasXMLFile` /
+//! `SplitNasXml` as JSON into every function. The Änderungen and the CSV data are
+//! pushed once per edit via [Session::set_aenderungen] / [Session::set_csv].
+
+use crate::{
+    csv::CsvDataType,
+    nas::{
+        NasXMLFile,
+        NasXmlObjects,
+        NasXmlQuadTree,
+        SplitNasXml,
+        SplitNasXmlQuadTree,
+    },
+    pdf::Konfiguration,
+    ui::Aenderungen,
+};
+use std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    collections::BTreeMap,
+    rc::Rc,
+};
+
+pub type SessionHandle = u32;
+
+pub struct Session {
+    pub konfiguration: Konfiguration,
+    pub nas_original: NasXMLFile,
+    pub nas_cut_original: SplitNasXml,
+    pub nas_projected: NasXMLFile,
+    pub nas_cut_projected: SplitNasXml,
+    pub xml_objects: NasXmlObjects,
+    /// Quadtree of `nas_original` (clean_stage4)
+    pub nas_original_quadtree: NasXmlQuadTree,
+    /// Quadtree of `nas_cut_original` (clean_stage3)
+    pub nas_cut_original_quadtree: SplitNasXmlQuadTree,
+    /// Änderungen in lat / lon, as edited in the UI
+    pub aenderungen: Aenderungen,
+    pub csv: CsvDataType,
+}
+
+impl Session {
+    pub fn new(
+        konfiguration: Konfiguration,
+        nas_original: NasXMLFile,
+        nas_cut_original: SplitNasXml,
+        nas_projected: NasXMLFile,
+        nas_cut_projected: SplitNasXml,
+        xml_objects: NasXmlObjects,
+    ) -> Self {
+        let nas_original_quadtree = nas_original.create_quadtree();
+        let nas_cut_original_quadtree = nas_cut_original.create_quadtree();
+        Self {
+            konfiguration,
+            nas_original,
+            nas_cut_original,
+            nas_projected,
+            nas_cut_projected,
+            xml_objects,
+            nas_original_quadtree,
+            nas_cut_original_quadtree,
+            aenderungen: Aenderungen::default(),
+            csv: CsvDataType::default(),
+        }
+    }
+
+    /// The NAS data and quadtrees stay, only the Konfiguration is replaced
+    pub fn set_konfiguration(&mut self, konfiguration: Konfiguration) {
+        self.konfiguration = konfiguration;
+    }
+
+    pub fn set_aenderungen(&mut self, aenderungen: Aenderungen) {
+        self.aenderungen = aenderungen;
+    }
+
+    pub fn set_csv(&mut self, csv: CsvDataType) {
+        self.csv = csv;
+    }
+}
+
+thread_local! {
+    static SESSIONS: RefCell<BTreeMap<SessionHandle, Rc<RefCell<Session>>>> = RefCell::new(BTreeMap::new());
+    static NEXT_HANDLE: Cell<SessionHandle> = Cell::new(1);
+}
+
+pub fn new_session_handle() -> SessionHandle {
+    NEXT_HANDLE.with(|h| {
+        let handle = h.get();
+        h.set(handle.wrapping_add(1).max(1));
+        handle
+    })
+}
+
+pub fn insert_session(handle: SessionHandle, session: Session) {
+    SESSIONS.with(|s| s.borrow_mut().insert(handle, Rc::new(RefCell::new(session))));
+}
+
+pub fn remove_session(handle: SessionHandle) {
+    SESSIONS.with(|s| s.borrow_mut().remove(&handle));
+}
+
+/// The map is only borrowed to look up the session, so `f` may call
+/// [insert_session] / [remove_session]. A nested call on the same session
+/// (e.g. a JS callback during `f`) returns an error instead of panicking.
+pub fn with_session<T>(
+    handle: SessionHandle,
+    f: impl FnOnce(&mut Session) -> T,
+) -> Result<T, String> {
+    let session = SESSIONS
+        .with(|s| s.borrow().get(&handle).cloned())
+        .ok_or_else(|| format!("Ungültige Session {handle}"))?;
+    let mut session = session
+        .try_borrow_mut()
+        .map_err(|_| format!("Session {handle} wird gerade verwendet"))?;
+    Ok(f(&mut session))
+}
+
+#[test]
+fn test_session_handles() {
+    let session = || {
+        Session::new(
+            Konfiguration::default(),
+            NasXMLFile::default(),
+            SplitNasXml::default(),
+            NasXMLFile::default(),
+            SplitNasXml::default(),
+            NasXmlObjects::default(),
+        )
+    };
+    let a = new_session_handle();
+    let b = new_session_handle();
+    assert_ne!(a, b);
+    insert_session(a, session());
+    insert_session(b, session());
+    assert!(with_session(a, |s| s.nas_original.ebenen.len()).is_ok());
+    remove_session(a);
+    assert!(with_session(a, |_| ()).is_err());
+    assert!(with_session(b, |_| ()).is_ok());
+    let verschachtelt = with_session(b, |_| with_session(b, |_| ()));
+    assert!(verschachtelt.unwrap().is_err());
+    let entfernt = with_session(b, |_| remove_session(b));
+    assert!(entfernt.is_ok());
+    assert!(with_session(b, |_| ()).is_err());
+}
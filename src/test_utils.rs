@@ -0,0 +1,55 @@
+//! Geometries shared by the tests
+
+use crate::{
+    nas::{
+        SvgLine,
+        SvgPoint,
+        SvgPolygon,
+        SvgPolygonInner,
+    },
+    ui::PolyNeu,
+};
+use quadtree_f32::Rect;
+
+/// Polygon without holes, the points are taken as they are (first = last)
+pub fn test_poly(points: &[(f64, f64)]) -> SvgPolygonInner {
+    SvgPolygonInner {
+        outer_ring: SvgLine {
+            points: points.iter().map(|(x, y)| SvgPoint { x: *x, y: *y }).collect(),
+        },
+        inner_rings: Vec::new(),
+    }
+}
+
+/// Clockwise rectangle, starting at (min_x, min_y)
+pub fn test_rechteck(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> SvgPolygonInner {
+    test_poly(&[
+        (min_x, min_y),
+        (min_x, max_y),
+        (max_x, max_y),
+        (max_x, min_y),
+        (min_x, min_y),
+    ])
+}
+
+pub fn test_quadrat(min_x: f64, min_y: f64, groesse: f64) -> SvgPolygonInner {
+    test_rechteck(min_x, min_y, min_x + groesse, min_y + groesse)
+}
+
+pub fn test_rect(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Rect {
+    Rect {
+        min_x,
+        min_y,
+        max_x,
+        max_y,
+    }
+}
+
+/// Unlocked new polygon: square at (x, 0)
+pub fn test_poly_neu(x: f64, groesse: f64, nutzung: Option<&str>) -> PolyNeu {
+    PolyNeu {
+        poly: SvgPolygon::Old(test_quadrat(x, 0.0, groesse)),
+        nutzung: nutzung.map(|s| s.to_string()),
+        locked: false,
+    }
+}
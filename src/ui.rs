@@ -387,6 +387,12 @@ pub fn render_popover_content(rpc_data: &UiData, konfiguration: &Konfiguration)
                                 <label style='font-size:20px;font-style:italic;'>DGM Ebene</label>
                                 <input type='text' class='konfiguration-editfield1' value='{dgm_layer}' data-konfiguration-textfield='map-dgm-layer' onchange='editKonfigurationTextField(event)'></input>
                             </div>
+
+                            <h2 style='font-size:20px;margin-top:20px;'>DAVID Antragskopf</h2>
+                            {antragskopf}
+
+                            <h2 style='font-size:20px;margin-top:20px;'>Rissplanung</h2>
+                            {rissplanung}
                         </div>
                     </div>
                 ",
@@ -395,6 +401,35 @@ pub fn render_popover_content(rpc_data: &UiData, konfiguration: &Konfiguration)
                     dop_layer = konfiguration.map.dop_layers.clone().unwrap_or_default().trim(),
                     dgm_source = konfiguration.map.dgm_source.clone().unwrap_or_default().trim(),
                     dgm_layer = konfiguration.map.dgm_layers.clone().unwrap_or_default().trim(),
+                    antragskopf = [
+                        ("Profilkennung", &konfiguration.antrag.profilkennung, "antrag-profilkennung"),
+                        ("Dienststelle", &konfiguration.antrag.dienststelle, "antrag-dienststelle"),
+                        ("Antragsnummer", &konfiguration.antrag.antragsnummer_schema, "antrag-antragsnummer-schema"),
+                        ("Auftragsnummer (leer: Antragsnummer)", &konfiguration.antrag.auftragsnummer_schema, "antrag-auftragsnummer-schema"),
+                        ("Auftragsart", &konfiguration.antrag.auftragsart, "antrag-auftragsart"),
+                        ("Verarbeitungsart", &konfiguration.antrag.verarbeitungsart, "antrag-verarbeitungsart"),
+                        ("Implizite Löschung der Reservierung", &konfiguration.antrag.implizite_loeschung_der_reservierung, "antrag-implizite-loeschung-der-reservierung"),
+                        ("CRS (leer: aus NAS)", &konfiguration.antrag.crs, "antrag-crs"),
+                        ("OID-Präfix", &konfiguration.antrag.oid_praefix, "antrag-oid-praefix"),
+                    ].iter().map(|(name, value, id)| format!("
+                            <div style='display:flex;justify-content:space-between;padding:10px 0px;font-size:16px;'>
+                                <label style='font-size:20px;font-style:italic;'>{name}</label>
+                                <input type='text' class='konfiguration-editfield1' value='{value}' data-konfiguration-textfield='{id}' onchange='editKonfigurationTextField(event)'></input>
+                            </div>
+                    ", value = value.trim())).collect::<Vec<_>>().join(""),
+                    rissplanung = [
+                        ("Papierformate", konfiguration.rissplanung.formate.join(", "), "rissplanung-formate"),
+                        ("Ausrichtung (hoch, quer)", [
+                            if konfiguration.rissplanung.hochformat { Some("hoch") } else { None },
+                            if konfiguration.rissplanung.querformat { Some("quer") } else { None },
+                        ].into_iter().flatten().collect::<Vec<_>>().join(", "), "rissplanung-ausrichtung"),
+                        ("Maßstäbe", konfiguration.rissplanung.massstaebe.iter().map(|m| m.to_string()).collect::<Vec<_>>().join(", "), "rissplanung-massstaebe"),
+                    ].iter().map(|(name, value, id)| format!("
+                            <div style='display:flex;justify-content:space-between;padding:10px 0px;font-size:16px;'>
+                                <label style='font-size:20px;font-style:italic;'>{name}</label>
+                                <input type='text' class='konfiguration-editfield1' value='{value}' data-konfiguration-textfield='{id}' onchange='editKonfigurationTextField(event)'></input>
+                            </div>
+                    ")).collect::<Vec<_>>().join(""),
                 ),
                 DarstellungBearbeitung => {
                     format!("
@@ -586,21 +621,58 @@ pub fn render_popover_content(rpc_data: &UiData, konfiguration: &Konfiguration)
                         <div style='padding:5px 0px;display:flex;flex-direction:column;flex-grow:1;'>
                             <div>
                                 <h2 style='font-size:20px;'>PDF Beschriftungen</h2>
-                                
+                                <div style='display:flex;justify-content:space-between;padding:10px 0px;font-size:16px;'>
+                                    <label style='font-size:12px;font-style:italic;'>Schriftgröße Nutzungsarten (pt)</label>
+                                    <input type='number' min='1' step='0.5' class='konfiguration-editfield1' value='{schriftgroesse}' data-konfiguration-textfield='map-pdf-beschriftung-schriftgroesse' onchange='editKonfigurationTextField(event)'></input>
+                                </div>
                             </div>
                         </div>
                     ",
+                        schriftgroesse = konfiguration.pdf.get_beschriftung_schriftgroesse(),
                     )
                 },
                 PdfSymbole => {
                     format!("
                         <div style='padding:5px 0px;display:flex;flex-direction:column;flex-grow:1;'>
                             <div>
-                                <h2 style='font-size:20px;'>PDF Symbole</h2>
-                                
+                                <h2 style='font-size:20px;'>PDF Symbole / Signaturen</h2>
+                                <button onclick='konfigurationSignaturNeu(event);' style='margin: 10px 0px;padding: 5px 10px;cursor: pointer;'>Signatur hinzufügen</button>
+                                {signaturen}
                             </div>
                         </div>
                     ",
+                        signaturen = konfiguration.signaturen.0.iter().map(|(k, v)| {
+                            let feld = |(name, value, id): (&str, &str, &str)| format!("
+                                <div style='display:flex;justify-content:space-between;padding:5px 0px;font-size:16px;'>
+                                    <label style='font-size:12px;font-style:italic;'>{name}</label>
+                                    <input type='text' class='konfiguration-editfield1' value='{value}' data-konfiguration-style-id='{k}' data-konfiguration-textfield='{id}' onchange='editKonfigurationTextField(event)'></input>
+                                </div>
+                            ");
+                            format!("
+                            <div style='padding:10px;margin-bottom:10px;background:#eeeeee;border-radius:3px;'>
+                                <div style='display:flex;justify-content:space-between;'>
+                                    <p style='font-size:14px;font-weight:bold;'>{k}</p>
+                                    <p class='__application-secondary-undo' style='cursor:pointer;' data-konfiguration-type='signaturen' data-konfiguration-style-id='{k}' data-move-type='delete' onmouseup='moveOrDeleteKonfigurationField(event)'>X</p>
+                                </div>
+                                <div style='display:flex;justify-content:space-between;padding:5px 0px;font-size:16px;'>
+                                    <label style='font-size:12px;font-style:italic;'>Typ</label>
+                                    <select class='konfiguration-editfield1' data-konfiguration-style-id='{k}' data-konfiguration-textfield='signatur-typ' onchange='editKonfigurationTextField(event)'>
+                                        <option value='punkt' {punkt_selected}>Punkt (AP_PPO)</option>
+                                        <option value='flaeche' {flaeche_selected}>Fläche (AP_Darstellung)</option>
+                                    </select>
+                                </div>
+                                {signaturnummer}
+                                {art}
+                                {positionierungsregel}
+                            </div>
+                            ",
+                                punkt_selected = if v.typ == crate::pdf::SignaturTyp::Punkt { "selected='selected'" } else { "" },
+                                flaeche_selected = if v.typ == crate::pdf::SignaturTyp::Flaeche { "selected='selected'" } else { "" },
+                                signaturnummer = feld(("Signaturnummer", &v.signaturnummer, "signatur-signaturnummer")),
+                                art = feld(("Art", &v.art, "signatur-art")),
+                                positionierungsregel = feld(("Positionierungsregel", v.positionierungsregel.as_deref().unwrap_or_default(), "signatur-positionierungsregel")),
+                            )
+                        }).collect::<Vec<_>>().join(""),
                     )
                 }
             };
@@ -700,7 +772,7 @@ pub fn render_ribbon(rpc_data: &UiData, _data_loaded: bool) -> String {
     let icon_neu_base64 = base64_encode(ICON_NEU);
 
     static ICON_EXCEL: &[u8] = include_bytes!("./img/icons8-microsoft-excel-2019-96.png");
-    let _icon_export_csv = base64_encode(ICON_EXCEL);
+    let icon_export_csv = base64_encode(ICON_EXCEL);
 
     static ICON_BROOM: &[u8] = include_bytes!("./img/icons8-broom-94.png");
     let icon_export_lefis = base64_encode(ICON_BROOM);
@@ -896,6 +968,22 @@ pub fn render_ribbon(rpc_data: &UiData, _data_loaded: bool) -> String {
         })
     };
 
+    let aenderungen_importieren = {
+        format!("
+            <div class='__application-ribbon-section-content'>
+                <label onmouseup='tab_functions.aenderungen_importieren(event)' class='__application-ribbon-action-vertical-large'>
+                    <div class='icon-wrapper'>
+                        <img class='icon {disabled}' src='data:image/png;base64,{icon_open_base64}'>
+                    </div>
+                    <div>
+                        <p>Änderungen</p>
+                        <p>importieren</p>
+                    </div>
+                </label>
+            </div>
+        ")
+    };
+
     // TAB 3
 
     let export_geograf = {
@@ -914,6 +1002,22 @@ pub fn render_ribbon(rpc_data: &UiData, _data_loaded: bool) -> String {
         ")
     };
 
+    let import_grafbat = {
+        format!("
+        <div class='__application-ribbon-section-content'>
+            <label onmouseup='tab_functions.import_grafbat(event)' class='__application-ribbon-action-vertical-large'>
+                <div class='icon-wrapper'>
+                    <img class='icon {disabled}' src='data:image/png;base64,{icon_open_base64}'>
+                </div>
+                <div>
+                    <p>GEOgraf-Texte</p>
+                    <p>übernehmen</p>
+                </div>
+            </label>
+        </div>   
+        ")
+    };
+
     let export_log = {
         format!(
             "
@@ -932,6 +1036,24 @@ pub fn render_ribbon(rpc_data: &UiData, _data_loaded: bool) -> String {
         )
     };
 
+    let export_vergleich = {
+        format!(
+            "
+        <div class='__application-ribbon-section-content'>
+            <label onmouseup='tab_functions.export_vergleich(event)' class='__application-ribbon-action-vertical-large'>
+                <div class='icon-wrapper'>
+                    <img class='icon {disabled}' src='data:image/png;base64,{icon_export_csv}'>
+                </div>
+                <div>
+                    <p>Vergleich mit</p>
+                    <p>Projektdatei</p>
+                </div>
+            </label>
+        </div>   
+        "
+        )
+    };
+
     let export_alle_flurstuecke = {
         format!("
             <div class='__application-ribbon-section-content'>
@@ -1110,6 +1232,7 @@ pub fn render_ribbon(rpc_data: &UiData, _data_loaded: bool) -> String {
                     <div style='display:flex;flex-direction:row;'>
                         {gebaeude_loeschen}
                         {nutzung_einzeichnen}
+                        {aenderungen_importieren}
                     </div>
                 </div>
 
@@ -1160,12 +1283,14 @@ pub fn render_ribbon(rpc_data: &UiData, _data_loaded: bool) -> String {
                         {export_david}
                         {export_nas_xml}
                         {export_geograf}
+                        {import_grafbat}
                     </div>
                 </div>
 
                 <div class='__application-ribbon-section 2'>
                     <div style='display:flex;flex-direction:row;'>
                         {export_log}
+                        {export_vergleich}
                         {projekt_speichern}
                     </div>
                 </div>
@@ -1513,6 +1638,7 @@ impl AenderungenIntersections {
                         ref_pos: lp.clone(),
                         area: q.poly_cut.area_m2().round() as usize,
                         poly: q.poly_cut.clone(),
+                        splitflaeche: Some(q.get_splitflaeche_id()),
                     }]
                 } else {
                     let lp = match q.poly_cut.get_label_pos() {
@@ -1531,6 +1657,7 @@ impl AenderungenIntersections {
                             ref_pos: lp.clone(),
                             area: q.poly_cut.area_m2().round() as usize,
                             poly: q.poly_cut.clone(),
+                            splitflaeche: Some(q.get_splitflaeche_id()),
                         },
                         TextPlacement {
                             kuerzel: q.neu.clone(),
@@ -1539,6 +1666,7 @@ impl AenderungenIntersections {
                             ref_pos: sp.clone(),
                             area: q.poly_cut.area_m2().round() as usize,
                             poly: q.poly_cut.clone(),
+                            splitflaeche: Some(q.get_splitflaeche_id()),
                         },
                     ]
                 }
@@ -2048,6 +2176,14 @@ impl AenderungenIntersection {
         Self::format_flst_id_func(&self.flst_id)
     }
 
+    pub fn get_splitflaeche_id(&self) -> SplitflaecheId {
+        SplitflaecheId {
+            flst_id_part: self.flst_id_part.clone(),
+            alt: self.alt.clone(),
+            neu: self.neu.clone(),
+        }
+    }
+
     pub fn format_flst_id_search(&self) -> String {
         let s = FlstIdParsed::from_str(&self.flst_id);
         let q = match s.parse_num() {
@@ -2226,6 +2362,15 @@ impl AenderungenIntersection {
     }
 }
 
+/// Identity of a Splitfläche that stays the same when the Splitflächen
+/// are recalculated (as long as the Flurstück part and the Nutzungen do)
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct SplitflaecheId {
+    pub flst_id_part: String,
+    pub alt: Kuerzel,
+    pub neu: Kuerzel,
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct TextPlacement {
     pub kuerzel: String,
@@ -2234,6 +2379,9 @@ pub struct TextPlacement {
     pub ref_pos: SvgPoint,
     pub area: usize,
     pub poly: SvgPolygonInner,
+    /// Splitfläche of an Änderungs-Text, `None` for Flur- / Flurstücks-Texte
+    #[serde(default)]
+    pub splitflaeche: Option<SplitflaecheId>,
 }
 
 impl TextPlacement {
@@ -3030,8 +3178,24 @@ impl Aenderungen {
         maxdst_line: f64,
         force: bool,
     ) -> Aenderungen {
-        let qt = split_nas.create_quadtree();
+        self.clean_stage3_with_quadtree(
+            &split_nas.create_quadtree(),
+            _log,
+            maxdst_point,
+            maxdst_line,
+            force,
+        )
+    }
 
+    /// Same as [Self::clean_stage3], with an already built quadtree (see [crate::session])
+    pub fn clean_stage3_with_quadtree(
+        &self,
+        qt: &SplitNasXmlQuadTree,
+        _log: &mut Vec<String>,
+        maxdst_point: f64,
+        maxdst_line: f64,
+        force: bool,
+    ) -> Aenderungen {
         let mut moved_points = Vec::new();
         let mut changed_mut = self.clone();
         for (_id, polyneu) in changed_mut.na_polygone_neu.iter_mut() {
@@ -3075,9 +3239,28 @@ impl Aenderungen {
         maxdst_line2: f64,
         maxdev_followline: f64,
         force: bool,
+    ) -> Aenderungen {
+        self.clean_stage4_with_quadtree(
+            &original_xml.create_quadtree(),
+            log,
+            maxdst_line,
+            maxdst_line2,
+            maxdev_followline,
+            force,
+        )
+    }
+
+    /// Same as [Self::clean_stage4], with an already built quadtree (see [crate::session])
+    pub fn clean_stage4_with_quadtree(
+        &self,
+        nas_quadtree: &NasXmlQuadTree,
+        _log: &mut Vec<String>,
+        maxdst_line: f64,
+        maxdst_line2: f64,
+        maxdev_followline: f64,
+        force: bool,
     ) -> Aenderungen {
         let mut changed_mut = self.round_to_3decimal();
-        let nas_quadtree = original_xml.create_quadtree();
 
         for (id, polyneu) in changed_mut.na_polygone_neu.iter_mut() {
             if polyneu.locked && !force {
@@ -3483,6 +3666,7 @@ fn render_risse_ui(
             <div id='risse' style='display:flex;flex-direction:column;'>
                 <h2 style='font-size:16px;font-weight:bold;margin-top:20px;'>Risse</h2>
                 <button onclick='rissNeu(event);' style='margin: 10px 0px;display: flex;padding: 10px;border-radius: 5px;cursor: pointer;background: #828295;color: white;border: 1px solid black;'>Neuen Riss anlegen</button>
+                <button onclick='risseAutomatischPlanen(event);' style='margin: 0px 0px 10px 0px;display: flex;padding: 10px;border-radius: 5px;cursor: pointer;background: #828295;color: white;border: 1px solid black;'>Risse automatisch planen</button>
                 {risse}
             </div>
         </div>",
@@ -3523,18 +3707,24 @@ fn render_risse_ui(
                     <input id='riss-{id}-scale' type='number' value='{scale}' style='display:flex;flex-grow:1;margin-right:0px;' data-riss-id='{id}' data-input-id='scale' oninput='changeRiss(event);' onchange='changeRiss(event);'></input>
                 </div>
 
+                <div class='row' style='display: flex;justify-content: space-between;padding: 5px 0px;align-items:center;'>
+                    <label for='projekt-info-{id}' style='font-weight: bold;margin-right: 5px;'>Seed Beschriftung:</label>
+                    <input id='riss-{id}-seed' type='number' min='0' step='1' value='{seed}' style='display:flex;flex-grow:1;margin-right:0px;' data-riss-id='{id}' data-input-id='seed' onchange='changeRiss(event);'></input>
+                </div>
+
                 <button id='riss-{id}-rissgebiet-zeichnen' style='margin-top:10px;padding: 5px;cursor:pointer;' data-riss-id='{id}' data-input-id='scale' onclick='showHideRissGebiet(event);'>Rissgebiet zeichnen</button>
+                <button id='riss-{id}-label-verschieben' style='margin-top:10px;padding: 5px;cursor:pointer;' data-riss-id='{id}' onclick='showHideLabel(event);'>Label verschieben</button>
 
                 <!--
                 <button id='riss-{id}-flm-setzen' style='margin-top:10px;padding: 5px;cursor:pointer;' data-riss-id='{id}' data-input-id='scale' onclick='showHideFlurMarker(event);'>Flurmarker setzen</button>
                 <button id='riss-{id}-np-setzen' style='margin-top:10px;padding: 5px;cursor:pointer;' data-riss-id='{id}' onclick='showHideNordpfeil(event);'>Nordpfeil setzen</button>
                 <button id='riss-{id}-anschluss-setzen' style='margin-top:10px;padding: 5px;cursor:pointer;' data-riss-id='{id}' onclick='showHideAnschlussRisse(event);'>Anschlussrisse setzen</button>
-                <button id='riss-{id}-label-verschieben' style='margin-top:10px;padding: 5px;cursor:pointer;' data-riss-id='{id}' onclick='showHideLabel(event);'>Label verschieben</button>
                 -->
             </div>",
                 width = rc.width_mm,
                 height = rc.height_mm,
                 scale = rc.scale,
+                seed = rc.seed,
                 riss_num = riss_num + 1,
             )
         }).collect::<Vec<_>>().join("")
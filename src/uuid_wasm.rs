@@ -1,3 +1,4 @@
+use crate::runtime::with_runtime;
 use rand::{
     Rng,
     SeedableRng,
@@ -15,42 +16,35 @@ use std::{
         Mutex,
     },
 };
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = Math)]
-    fn random() -> f64;
-    fn update_export_status(s: String);
-    fn export_status_clear();
-    fn get_nak() -> String;
-    fn get_nak_ranking() -> String;
-}
-
-pub fn get_js_nak_ranking() -> String {
-    get_nak_ranking()
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod js {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = Math)]
+        pub fn random() -> f64;
+        pub fn update_export_status(s: String);
+        pub fn export_status_clear();
+        pub fn get_nak() -> String;
+        pub fn get_nak_ranking() -> String;
+    }
 }
 
-pub fn get_js_nak() -> String {
-    get_nak()
+pub fn get_js_nak_ranking() -> Vec<String> {
+    with_runtime(|r| r.get_nak_ranking())
 }
 
 pub fn js_random() -> f64 {
-    random().max(0.0).min(1.0)
+    with_runtime(|r| r.random())
 }
 
 pub fn log_status_clear() {
-    export_status_clear();
+    with_runtime(|r| r.log_status_clear())
 }
 
 pub fn log_status(s: &str) {
-    #[cfg(not(target_arch = "wasm32"))] {
-        println!("{s}");
-    }
-    #[cfg(target_arch = "wasm32")] {
-        web_sys::console::log_1(&s.into());
-        update_export_status(s.trim().to_string())
-    }
+    with_runtime(|r| r.log_status(s))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +55,8 @@ pub struct FetchWmsImageRequest {
     pub min_y: f64,
     pub width_px: usize,
     pub height_px: usize,
+    /// e.g. "EPSG:25833"
+    pub srs: String,
     pub dop_source: Option<String>,
     pub dop_layers: Option<String>,
 }
@@ -100,10 +96,10 @@ async fn wms_future(
     url += format!("&HEIGHT={}", o.height_px).as_str();
     url += format!("&WIDTH={}", o.width_px).as_str();
     url += "&MAXNATIVEZOOM=25";
-    url += "&SRS=EPSG%3A25833";
+    url += format!("&SRS={}", o.srs.replace(":", "%3A")).as_str();
     url += format!("&BBOX={},{},{},{}", o.min_x, o.min_y, o.max_x, o.max_y).as_str();
 
-    web_sys::console::log_1(&format!("reqwest fetching url {url}").into());
+    log_status(&format!("Lade Hintergrundbild: {url}"));
 
     let s = match reqwest::get(&url).await.ok() {
         Some(s) => match s.bytes().await.ok() {
@@ -122,14 +118,10 @@ pub fn decode_image(bytes: &[u8]) -> Option<printpdf::Image> {
     let format = match image::guess_format(bytes) {
         Ok(o) => o,
         Err(e) => {
-            web_sys::console::log_1(
-                &format!(
-                    "failed image format: {} {:?}",
-                    e.to_string(),
-                    bytes.iter().take(10).collect::<Vec<_>>()
-                )
-                .into(),
-            );
+            log_status(&format!(
+                "Bildformat nicht erkannt: {e} {:?}",
+                bytes.iter().take(10).collect::<Vec<_>>()
+            ));
             return None;
         }
     };
@@ -137,7 +129,7 @@ pub fn decode_image(bytes: &[u8]) -> Option<printpdf::Image> {
     let decoded = match image::load_from_memory_with_format(bytes, format) {
         Ok(o) => o,
         Err(e) => {
-            web_sys::console::log_1(&format!("error 1: {}", e.to_string()).into());
+            log_status(&format!("Bild konnte nicht gelesen werden: {e}"));
             return None;
         }
     };
@@ -150,7 +142,7 @@ pub fn decode_image(bytes: &[u8]) -> Option<printpdf::Image> {
 }
 
 pub fn uuid() -> String {
-    let seed = random();
+    let seed = js_random();
     gen_uuid_with_xorshift(seed)
 }
 
@@ -162,7 +154,7 @@ pub fn random_color() -> String {
 
     RandomColor::new()
         .luminosity(Luminosity::Light) // Optional
-        .seed((random() * 1000.0) as i64) // Optional
+        .seed((js_random() * 1000.0) as i64) // Optional
         .to_hex()
 }
 
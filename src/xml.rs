@@ -14,6 +14,8 @@ pub enum XmlParseError {
     ParseError(XmlError),
     /// Invalid hierarchy close tags, i.e `<app></p></app>`
     MalformedHierarchy(String, String),
+    /// Error from the streaming reader (quick-xml), with byte position
+    StreamError(String, usize),
 }
 
 /// Represents one XML node tag
@@ -206,6 +208,135 @@ pub fn parse_xml_string(xml: &str, _log: &mut Vec<String>) -> Result<Vec<XmlNode
     Ok(root_node.children)
 }
 
+/// Streams through the XML and only builds the [XmlNode] subtrees of the elements
+/// for which `collect` returns true (e.g. each `member` of a NAS file). Every subtree
+/// is handed to `on_subtree` as soon as it is closed, so the DOM of the whole
+/// document is never held in memory. Direct children of a collected element for
+/// which `skip(parent, child)` returns true are skipped without being parsed.
+pub fn stream_xml_subtrees<F: Fn(&str) -> bool, S: Fn(&str, &str) -> bool, G: FnMut(XmlNode)>(
+    xml: &str,
+    collect: F,
+    skip: S,
+    mut on_subtree: G,
+) -> Result<(), XmlParseError> {
+    use quick_xml::{
+        events::Event,
+        Reader,
+    };
+
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut skip_buf = Vec::new();
+
+    // open nodes of the subtree that is currently being collected
+    let mut stack: Vec<XmlNode> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event(&mut buf)
+            .map_err(|e| XmlParseError::StreamError(e.to_string(), reader.buffer_position()))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let node_type = local_name(e.local_name());
+                if stack.len() == 1 && skip(&stack[0].node_type, &node_type) {
+                    if matches!(event, Event::Start(_)) {
+                        reader
+                            .read_to_end(e.name(), &mut skip_buf)
+                            .map_err(|e| XmlParseError::StreamError(e.to_string(), reader.buffer_position()))?;
+                        skip_buf.clear();
+                    }
+                } else if !stack.is_empty() || collect(&node_type) {
+                    let mut attributes = BTreeMap::new();
+                    for a in e.attributes() {
+                        let a = a.map_err(|e| XmlParseError::StreamError(e.to_string(), reader.buffer_position()))?;
+                        let value = a
+                            .unescape_and_decode_value(&reader)
+                            .map_err(|e| XmlParseError::StreamError(e.to_string(), reader.buffer_position()))?;
+                        attributes.insert(local_name(a.key), value);
+                    }
+                    stack.push(XmlNode {
+                        node_type,
+                        attributes,
+                        children: Vec::new(),
+                        text: None,
+                    });
+                    if matches!(event, Event::Empty(_)) {
+                        close_streamed_node(&mut stack, &mut on_subtree);
+                    }
+                }
+            }
+            Event::End(_) => {
+                close_streamed_node(&mut stack, &mut on_subtree);
+            }
+            Event::Text(ref e) => {
+                if let Some(last) = stack.last_mut() {
+                    let text = e
+                        .unescape_and_decode(&reader)
+                        .map_err(|e| XmlParseError::StreamError(e.to_string(), reader.buffer_position()))?;
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        match last.text.as_mut() {
+                            Some(s) => s.push_str(text),
+                            None => last.text = Some(text.to_string()),
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(())
+}
+
+// "gml:id" => "id"
+fn local_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    match name.rsplit_once(':') {
+        Some((_, local)) => local.to_string(),
+        None => name.to_string(),
+    }
+}
+
+fn close_streamed_node<G: FnMut(XmlNode)>(stack: &mut Vec<XmlNode>, on_subtree: &mut G) {
+    let node = match stack.pop() {
+        Some(s) => s,
+        None => return,
+    };
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => (on_subtree)(node),
+    }
+}
+
+#[test]
+fn test_stream_xml_subtrees() {
+    let xml = r#"<?xml version="1.0"?>
+        <wfs:FeatureCollection xmlns:wfs="x" xmlns:gml="y">
+            <header><ignored a="b">text</ignored></header>
+            <wfs:member><AX_Flurstueck gml:id="DE1"><flurnummer>1</flurnummer><geo/></AX_Flurstueck></wfs:member>
+            <wfs:member><AX_Gebaeude gml:id="DE2">a &amp; b</AX_Gebaeude></wfs:member>
+        </wfs:FeatureCollection>"#;
+    let mut nodes = Vec::new();
+    stream_xml_subtrees(xml, |n| n == "member", |_, _| false, |n| nodes.push(n)).unwrap();
+    assert_eq!(nodes.len(), 2);
+    let flst = &nodes[0].children[0];
+    assert_eq!(flst.node_type, "AX_Flurstueck");
+    assert_eq!(flst.attributes.get("id").map(|s| s.as_str()), Some("DE1"));
+    assert_eq!(flst.children[0].text.as_deref(), Some("1"));
+    assert_eq!(flst.children[1].node_type, "geo");
+    assert_eq!(nodes[1].children[0].text.as_deref(), Some("a & b"));
+
+    let mut nodes = Vec::new();
+    stream_xml_subtrees(xml, |n| n == "member", |_, c| c == "AX_Flurstueck", |n| nodes.push(n)).unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert!(nodes[0].children.is_empty());
+    assert_eq!(nodes[1].children[0].node_type, "AX_Gebaeude");
+}
+
 /// Given a root node, traverses along the hierarchy, and returns a
 /// mutable reference to the last child node of the root node
 #[allow(trivial_casts)]
@@ -42,6 +42,10 @@ pub const LEGENDE_SHAREDSTRINGS_XML: &str = include_str!("./legende_sharedstring
 pub const LEGENDE_SHEET1_XML: &str = include_str!("./legende_sheet1.xml");
 
 pub const FORTFUEHRUNGSBELEG_DOCX_XML: &str = include_str!("./fortfuehrungsbeleg_document.xml");
+pub const EIGENTUEMERBENACHRICHTIGUNG_DOCX_XML: &str =
+    include_str!("./eigentuemerbenachrichtigung_document.xml");
+
+pub const AENDERUNGSBERICHT_SHEET1_XML: &str = include_str!("./aenderungsbericht_sheet1.xml");
 
 pub struct AntragsbegleitblattInfo {
     pub datum: String,                                     // %%REPLACEME_DATUM%%
@@ -221,6 +225,141 @@ pub fn generate_legende_xlsx(info: &LegendeInfo) -> Vec<u8> {
     crate::zip::write_files_to_zip(zip)
 }
 
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AenderungsberichtZeile {
+    pub bereich: String,
+    pub art: String,
+    pub id: String,
+    pub alt: String,
+    pub neu: String,
+    pub flaeche_alt_m2: Option<f64>,
+    pub flaeche_neu_m2: Option<f64>,
+    pub flaeche_delta_m2: Option<f64>,
+}
+
+pub struct AenderungsberichtInfo {
+    pub header: String,
+    pub zeilen: Vec<AenderungsberichtZeile>,
+}
+
+/// Uses the Legende.zip package (styles, workbook) with a table sheet
+pub fn generate_aenderungsbericht_xlsx(info: &AenderungsberichtInfo) -> Vec<u8> {
+    const SPALTEN: &[&str] = &[
+        "Bereich",
+        "Art",
+        "ID",
+        "Alt",
+        "Neu",
+        "Fläche alt (m²)",
+        "Fläche neu (m²)",
+        "Differenz (m²)",
+    ];
+    const COLS: &[&str] = &["A", "B", "C", "D", "E", "F", "G", "H"];
+
+    let mut sharedstrings = BTreeSet::new();
+    sharedstrings.insert(info.header.to_string());
+    sharedstrings.extend(SPALTEN.iter().map(|s| s.to_string()));
+    for z in info.zeilen.iter() {
+        sharedstrings.extend([&z.bereich, &z.art, &z.id, &z.alt, &z.neu].into_iter().cloned());
+    }
+    let sharedstrings_list = sharedstrings.iter().cloned().collect::<Vec<_>>();
+    let sharedstrings_lookup_list = sharedstrings_list
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(k, v)| (v, k))
+        .collect::<BTreeMap<_, _>>();
+
+    let sharedstrings_xml = LEGENDE_SHAREDSTRINGS_XML
+        .replace("%%SHARED_STRINGS_COUNT%%", &sharedstrings.len().to_string())
+        .replace(
+            "<!-- %%SHARED_STRINGS%% -->",
+            &sharedstrings_list
+                .iter()
+                .map(|s| format!("<si><t xml:space=\"preserve\">{}</t></si>", clean_ascii(s)))
+                .collect::<Vec<_>>()
+                .join("\r\n"),
+        );
+
+    let string_cell = |col: &str, row: usize, style: usize, s: &str| -> String {
+        match sharedstrings_lookup_list.get(s) {
+            Some(id) if !s.is_empty() => {
+                format!("<c r=\"{col}{row}\" s=\"{style}\" t=\"s\"><v>{id}</v></c>")
+            }
+            _ => format!("<c r=\"{col}{row}\" s=\"{style}\"/>"),
+        }
+    };
+    let number_cell = |col: &str, row: usize, f: Option<f64>| -> String {
+        match f {
+            Some(f) => format!("<c r=\"{col}{row}\" s=\"3\"><v>{f:.2}</v></c>"),
+            None => format!("<c r=\"{col}{row}\" s=\"3\"/>"),
+        }
+    };
+
+    let spalten = format!(
+        "<row r=\"2\" spans=\"1:8\">{}</row>",
+        SPALTEN
+            .iter()
+            .zip(COLS.iter())
+            .map(|(s, col)| string_cell(col, 2, 2, s))
+            .collect::<Vec<_>>()
+            .join("")
+    );
+
+    let rows = info
+        .zeilen
+        .iter()
+        .enumerate()
+        .map(|(i, z)| {
+            let row = i + 3;
+            let cells = [
+                string_cell("A", row, 3, &z.bereich),
+                string_cell("B", row, 3, &z.art),
+                string_cell("C", row, 3, &z.id),
+                string_cell("D", row, 3, &z.alt),
+                string_cell("E", row, 3, &z.neu),
+                number_cell("F", row, z.flaeche_alt_m2),
+                number_cell("G", row, z.flaeche_neu_m2),
+                number_cell("H", row, z.flaeche_delta_m2),
+            ];
+            format!("<row r=\"{row}\" spans=\"1:8\">{}</row>", cells.join(""))
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    let sheet1_xml = AENDERUNGSBERICHT_SHEET1_XML
+        .replace("%%LAST_ROW%%", &(info.zeilen.len() + 2).to_string())
+        .replace(
+            "%%HEADER%%",
+            &sharedstrings_lookup_list
+                .get(&info.header)
+                .unwrap_or(&0)
+                .to_string(),
+        )
+        .replace("<!-- %%SPALTEN%% -->", &spalten)
+        .replace("<!-- %%ROWS%% -->", &rows);
+
+    let mut zip = crate::zip::read_files_from_zip(LEGENDE_ZIP, true, &[".rels"]);
+    for (dir, name, bytes) in zip.iter_mut() {
+        if dir.as_deref() == Some("xl") && name.as_os_str() == "workbook.xml" {
+            *bytes = String::from_utf8_lossy(bytes)
+                .replace("<sheet name=\"Legende\"", "<sheet name=\"Aenderungen\"")
+                .into_bytes();
+        }
+    }
+    zip.push((
+        Some("xl".to_string()),
+        "sharedStrings.xml".into(),
+        sharedstrings_xml.as_bytes().to_vec(),
+    ));
+    zip.push((
+        Some("xl/worksheets".to_string()),
+        "sheet1.xml".into(),
+        sheet1_xml.as_bytes().to_vec(),
+    ));
+    crate::zip::write_files_to_zip(zip)
+}
+
 pub struct BearbeitungslisteInfo {
     pub auftragsnr: String,
     pub gemarkung_name: String,
@@ -409,3 +548,87 @@ pub fn generate_fortfuehrungsbeleg_docx(info: &FortfuehrungsbelegInfo) -> Vec<u8
     ));
     crate::zip::write_files_to_zip(zip)
 }
+
+pub const EIGENTUEMERBENACHRICHTIGUNG_ROW_XML: &str = r#"
+            <w:tr>
+                <w:tc><w:p><w:r><w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Arial" w:cs="Arial"/><w:sz w:val="18"/></w:rPr><w:t>%%FLST%%</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:r><w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Arial" w:cs="Arial"/><w:sz w:val="18"/></w:rPr><w:t>%%ALT%%</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:r><w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Arial" w:cs="Arial"/><w:sz w:val="18"/></w:rPr><w:t>%%WA_ALT%%</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:r><w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Arial" w:cs="Arial"/><w:sz w:val="18"/></w:rPr><w:t>%%NEU%%</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:r><w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Arial" w:cs="Arial"/><w:sz w:val="18"/></w:rPr><w:t>%%WA_NEU%%</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:pPr><w:jc w:val="right"/></w:pPr><w:r><w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Arial" w:cs="Arial"/><w:sz w:val="18"/></w:rPr><w:t>%%FLAECHE%%</w:t></w:r></w:p></w:tc>
+            </w:tr>
+"#;
+
+/// One Splitfläche of a Flurstück in the Eigentümerbenachrichtigung
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenachrichtigungZeile {
+    pub alt: String,
+    pub wia_alt: String,
+    pub neu: String,
+    pub wia_neu: String,
+    pub flaeche_m2: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EigentuemerbenachrichtigungInfo {
+    pub datum: String,
+    pub antragsnr: String,
+    pub gemarkung: String,
+    pub gemeinde: String,
+    pub vermessungsstelle: String,
+    pub erstellt_durch: String,
+    pub eigentuemer: EigentuemerClean,
+    /// Flurstück (formatted) -> affected Splitflächen
+    pub flurstuecke: Vec<(String, Vec<BenachrichtigungZeile>)>,
+}
+
+impl EigentuemerbenachrichtigungInfo {
+    pub fn get_anrede(&self) -> String {
+        match &self.eigentuemer {
+            EigentuemerClean::Herr { nachname, .. } => format!("Sehr geehrter Herr {nachname},"),
+            EigentuemerClean::Frau { nachname, .. } => format!("Sehr geehrte Frau {nachname},"),
+            EigentuemerClean::Firma { .. } | EigentuemerClean::Sonstige { .. } => {
+                "Sehr geehrte Damen und Herren,".to_string()
+            }
+        }
+    }
+}
+
+pub fn generate_eigentuemerbenachrichtigung_docx(info: &EigentuemerbenachrichtigungInfo) -> Vec<u8> {
+    let rows = info
+        .flurstuecke
+        .iter()
+        .flat_map(|(flst, zeilen)| {
+            zeilen.iter().enumerate().map(move |(i, z)| {
+                EIGENTUEMERBENACHRICHTIGUNG_ROW_XML
+                    .replace("%%FLST%%", &if i == 0 { clean_ascii(flst) } else { String::new() })
+                    .replace("%%ALT%%", &clean_ascii(&z.alt))
+                    .replace("%%WA_ALT%%", &clean_ascii(&z.wia_alt))
+                    .replace("%%NEU%%", &clean_ascii(&z.neu))
+                    .replace("%%WA_NEU%%", &clean_ascii(&z.wia_neu))
+                    .replace("%%FLAECHE%%", &format!("{:.0}", z.flaeche_m2.round()))
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    let document_xml = EIGENTUEMERBENACHRICHTIGUNG_DOCX_XML
+        .replace("%%VERMESSUNGSSTELLE%%", &clean_ascii(&info.vermessungsstelle))
+        .replace("%%EMPFAENGER%%", &clean_ascii(&info.eigentuemer.format()))
+        .replace("%%DATUM%%", &clean_ascii(&info.datum))
+        .replace("%%ANTRAGSNR%%", &clean_ascii(&info.antragsnr))
+        .replace("%%ANREDE%%", &clean_ascii(&info.get_anrede()))
+        .replace("%%GEMARKUNG%%", &clean_ascii(&info.gemarkung))
+        .replace("%%GEMEINDE%%", &clean_ascii(&info.gemeinde))
+        .replace("%%ERSTELLT_DURCH%%", &clean_ascii(&info.erstellt_durch))
+        .replace("<!-- %%ROWS%% -->", &rows);
+
+    let mut zip = crate::zip::read_files_from_zip(FORTFUEHRUNGSBELEG_ZIP, true, &[".rels"]);
+    zip.push((
+        Some("word".to_string()),
+        "document.xml".into(),
+        document_xml.as_bytes().to_vec(),
+    ));
+    crate::zip::write_files_to_zip(zip)
+}